@@ -1,10 +1,14 @@
 #![allow(clippy::all)]
 
+use bevy::tasks::{ComputeTaskPool, TaskPool};
 use bevy_core::Name;
-use bevy_ecs::prelude::*;
+use bevy_ecs::{prelude::*, query::BatchingStrategy};
 use bevy_trait_query::*;
 use criterion::*;
-use std::fmt::Display;
+use std::{
+    fmt::Display,
+    sync::atomic::{AtomicUsize, Ordering},
+};
 
 /// Define a trait for our components to implement.
 #[queryable]
@@ -103,6 +107,20 @@ impl<'w> Benchmark<'w> {
         Self(world, query, Default::default())
     }
 
+    // Only one impl is registered for the trait.
+    fn single_impl() -> Self {
+        let mut world = World::new();
+
+        world.register_component_as::<dyn Messages, RecA>();
+
+        for _ in 0..10_000 {
+            world.spawn((Name::new("Hello"), RecA { messages: vec![] }));
+        }
+
+        let query = world.query();
+        Self(world, query, Default::default())
+    }
+
     pub fn run(&mut self) {
         let mut output = Vec::new();
         for all in self.1.iter_mut(&mut self.0) {
@@ -130,5 +148,50 @@ pub fn distributed(c: &mut Criterion) {
     eprintln!("{}", benchmark.2.len());
 }
 
-criterion_group!(all, one, multiple, distributed);
+pub fn single_impl(c: &mut Criterion) {
+    let mut benchmark = Benchmark::single_impl();
+    c.bench_function("All<> - 1 impl registered", |b| b.iter(|| benchmark.run()));
+    eprintln!("{}", benchmark.2.len());
+}
+
+// Compares batching strategies for parallel iteration, since the cost of each entity
+// depends on how many impls it has.
+pub fn par_iter_batching(c: &mut Criterion) {
+    ComputeTaskPool::get_or_init(TaskPool::default);
+    let mut benchmark = Benchmark::distributed();
+    let strategies = [
+        ("default", BatchingStrategy::default()),
+        ("fixed 64", BatchingStrategy::fixed(64)),
+        ("fixed 1024", BatchingStrategy::fixed(1024)),
+        ("min 256", BatchingStrategy::new().min_batch_size(256)),
+    ];
+    let mut group = c.benchmark_group("All<> - par_iter");
+    for (name, strategy) in strategies {
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                let total = AtomicUsize::new(0);
+                benchmark
+                    .1
+                    .par_iter(&benchmark.0)
+                    .batching_strategy(strategy.clone())
+                    .for_each(|all| {
+                        for x in all {
+                            total.fetch_add(x.messages().len(), Ordering::Relaxed);
+                        }
+                    });
+                total.into_inner()
+            });
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    all,
+    one,
+    multiple,
+    distributed,
+    single_impl,
+    par_iter_batching
+);
 criterion_main!(all);
@@ -0,0 +1,95 @@
+#![allow(clippy::all)]
+
+use bevy_ecs::prelude::*;
+use bevy_trait_query::*;
+use criterion::*;
+use std::fmt::Display;
+
+/// Define a trait for our components to implement.
+#[queryable]
+pub trait Messages {
+    fn messages(&self) -> &[String];
+    fn send_message(&mut self, _: &dyn Display);
+}
+
+#[derive(Component)]
+pub struct RecA {
+    messages: Vec<String>,
+}
+
+impl Messages for RecA {
+    fn messages(&self) -> &[String] {
+        &self.messages
+    }
+    fn send_message(&mut self, msg: &dyn Display) {
+        self.messages.push(msg.to_string());
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct Output(usize);
+
+macro_rules! create_entities {
+    ($world:ident; $( $variants:ident ),*) => {
+        $(
+            #[derive(Component)]
+            struct $variants { _dummy: f32 }
+            $world.spawn(($variants { _dummy: 0.0 },));
+        )*
+        // Only the last archetype contains an impl.
+        $world.spawn(RecA { messages: vec![] });
+    };
+}
+
+pub struct Benchmark(World, Schedule);
+
+impl Benchmark {
+    fn new<M>(system: impl IntoSystemConfigs<M>) -> Self {
+        let mut world = World::new();
+        world.init_resource::<Output>();
+
+        world.register_component_as::<dyn Messages, RecA>();
+
+        create_entities!(
+            world; A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z
+        );
+
+        let mut schedule = Schedule::default();
+        schedule.add_systems(system);
+        Self(world, schedule)
+    }
+
+    // Runs 100 frames.
+    pub fn run(&mut self) {
+        for _ in 0..100 {
+            self.1.run(&mut self.0);
+        }
+    }
+}
+
+fn uncached(q: Query<(), WithOne<dyn Messages>>, mut output: ResMut<Output>) {
+    if !q.is_empty() {
+        output.0 += 1;
+    }
+}
+
+fn cached(q: CachedDynExistence<dyn Messages>, mut output: ResMut<Output>) {
+    if !q.is_empty() {
+        output.0 += 1;
+    }
+}
+
+pub fn uncached_existence(c: &mut Criterion) {
+    let mut benchmark = Benchmark::new(uncached);
+    c.bench_function("existence - uncached", |b| b.iter(|| benchmark.run()));
+    eprintln!("{}", benchmark.0.resource::<Output>().0);
+}
+
+pub fn cached_existence(c: &mut Criterion) {
+    let mut benchmark = Benchmark::new(cached);
+    c.bench_function("existence - cached", |b| b.iter(|| benchmark.run()));
+    eprintln!("{}", benchmark.0.resource::<Output>().0);
+}
+
+criterion_group!(existence, uncached_existence, cached_existence);
+criterion_main!(existence);
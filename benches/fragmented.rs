@@ -95,6 +95,32 @@ pub fn one(c: &mut Criterion) {
     });
     eprintln!("{}", output.len());
 }
+pub fn one_ref(c: &mut Criterion) {
+    let mut benchmark = Benchmark::new();
+    let mut query = benchmark.0.query::<One<&dyn Messages>>();
+    let mut output = Vec::new();
+    c.bench_function("One<&> - fragmented", |b| {
+        b.iter(|| {
+            for x in query.iter(&benchmark.0) {
+                output.push(x.messages().len());
+            }
+        });
+    });
+    eprintln!("{}", output.len());
+}
+pub fn one_plain(c: &mut Criterion) {
+    let mut benchmark = Benchmark::new();
+    let mut query = benchmark.0.query::<OnePlain<&dyn Messages>>();
+    let mut output = Vec::new();
+    c.bench_function("OnePlain<&> - fragmented", |b| {
+        b.iter(|| {
+            for x in query.iter(&benchmark.0) {
+                output.push(x.messages().len());
+            }
+        });
+    });
+    eprintln!("{}", output.len());
+}
 pub fn all(c: &mut Criterion) {
     let mut benchmark = Benchmark::new();
     let mut query = benchmark.0.query::<&mut dyn Messages>();
@@ -111,5 +137,5 @@ pub fn all(c: &mut Criterion) {
     eprintln!("{}", output.len());
 }
 
-criterion_group!(fragmented, concrete, one, all);
+criterion_group!(fragmented, concrete, one, one_ref, one_plain, all);
 criterion_main!(fragmented);
@@ -0,0 +1,155 @@
+#![allow(clippy::all)]
+
+use bevy_ecs::prelude::*;
+use bevy_trait_query::*;
+use criterion::*;
+use std::fmt::Display;
+
+/// Define a trait for our components to implement.
+#[queryable]
+pub trait Messages {
+    fn messages(&self) -> &[String];
+    fn send_message(&mut self, _: &dyn Display);
+}
+
+const ENTITIES: usize = 10_000;
+const IMPL_COUNTS: [usize; 3] = [2, 8, 32];
+
+// Defines a component implementing `Messages` for each of the given names,
+// along with a list of functions to register and spawn each of them.
+macro_rules! define_impls {
+    ($( $name:ident ),*) => {
+        $(
+            #[derive(Component)]
+            pub struct $name {
+                messages: Vec<String>,
+            }
+
+            impl Messages for $name {
+                fn messages(&self) -> &[String] {
+                    &self.messages
+                }
+                fn send_message(&mut self, msg: &dyn Display) {
+                    self.messages.push(msg.to_string());
+                }
+            }
+        )*
+
+        const IMPLS: &[(fn(&mut World), fn(&mut World))] = &[
+            $((
+                |world| {
+                    world.register_component_as::<dyn Messages, $name>();
+                },
+                |world| {
+                    world.spawn($name { messages: vec![] });
+                },
+            ),)*
+        ];
+    };
+}
+
+define_impls!(
+    R00, R01, R02, R03, R04, R05, R06, R07, R08, R09, R10, R11, R12, R13, R14, R15, R16, R17, R18,
+    R19, R20, R21, R22, R23, R24, R25, R26, R27, R28, R29, R30, R31
+);
+
+/// Registers `impl_count` impls, and spawns entities which each have exactly one of them.
+fn setup(impl_count: usize) -> World {
+    let mut world = World::new();
+    for (register, _) in &IMPLS[..impl_count] {
+        register(&mut world);
+    }
+    for i in 0..ENTITIES {
+        let (_, spawn) = IMPLS[i % impl_count];
+        spawn(&mut world);
+    }
+    world
+}
+
+// Baseline: queries a single concrete impl, which only matches a fraction of the entities.
+pub fn concrete(c: &mut Criterion) {
+    let mut group = c.benchmark_group("concrete - scaling");
+    for impl_count in IMPL_COUNTS {
+        let mut world = setup(impl_count);
+        let mut query = world.query::<&R00>();
+        let mut output = Vec::new();
+        group.bench_with_input(
+            BenchmarkId::from_parameter(impl_count),
+            &impl_count,
+            |b, _| {
+                b.iter(|| {
+                    for x in query.iter(&world) {
+                        output.push(x.messages().len());
+                    }
+                });
+            },
+        );
+        eprintln!("{}", output.len());
+    }
+    group.finish();
+}
+
+pub fn one(c: &mut Criterion) {
+    let mut group = c.benchmark_group("One<> - scaling");
+    for impl_count in IMPL_COUNTS {
+        let mut world = setup(impl_count);
+        let mut query = world.query::<One<&dyn Messages>>();
+        let mut output = Vec::new();
+        group.bench_with_input(
+            BenchmarkId::from_parameter(impl_count),
+            &impl_count,
+            |b, _| {
+                b.iter(|| {
+                    for x in query.iter(&world) {
+                        output.push(x.messages().len());
+                    }
+                });
+            },
+        );
+        eprintln!("{}", output.len());
+    }
+    group.finish();
+}
+
+pub fn all(c: &mut Criterion) {
+    let mut group = c.benchmark_group("All<> - scaling");
+    for impl_count in IMPL_COUNTS {
+        let mut world = setup(impl_count);
+        let mut query = world.query::<&dyn Messages>();
+        let mut output = Vec::new();
+        group.bench_with_input(
+            BenchmarkId::from_parameter(impl_count),
+            &impl_count,
+            |b, _| {
+                b.iter(|| {
+                    for all in query.iter(&world) {
+                        for x in all {
+                            output.push(x.messages().len());
+                        }
+                    }
+                });
+            },
+        );
+        eprintln!("{}", output.len());
+    }
+    group.finish();
+}
+
+// Measures building the query state, which has to compute the access for every registered impl.
+pub fn one_state_init(c: &mut Criterion) {
+    let mut group = c.benchmark_group("One<> - state init");
+    for impl_count in IMPL_COUNTS {
+        let mut world = setup(impl_count);
+        group.bench_with_input(
+            BenchmarkId::from_parameter(impl_count),
+            &impl_count,
+            |b, _| {
+                b.iter(|| QueryState::<One<&dyn Messages>>::new(&mut world));
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(scaling, concrete, one, all, one_state_init);
+criterion_main!(scaling);
@@ -1,7 +1,9 @@
 use proc_macro::TokenStream;
 use proc_macro2::TokenStream as TokenStream2;
-use quote::{format_ident, quote};
-use syn::{parse_quote, ItemTrait, Result, TraitItem};
+use quote::{format_ident, quote, quote_spanned, ToTokens};
+use syn::{
+    parse::Parser, parse_quote, punctuated::Punctuated, ItemTrait, Result, Token, TraitItem,
+};
 
 /// When added to a trait declaration, generates the impls required to use that trait in queries.
 ///
@@ -30,6 +32,25 @@ use syn::{parse_quote, ItemTrait, Result, TraitItem};
 ///
 /// You may opt out of this by using the form `#[queryable(no_bounds)]`,
 /// but you will have to add the bounds yourself to make it compile.
+/// In this case, an assertion is generated which reports any missing `'static` bounds
+/// at the trait declaration.
+///
+/// # Prelude
+///
+/// Using the form `#[queryable(prelude)]` will generate a module named after the trait
+/// (e.g. `tooltip_query` for a trait `Tooltip`), containing aliases for the query adapters
+/// specialized to that trait, such as `tooltip_query::One` for `One<&dyn Tooltip>`
+/// or `tooltip_query::OneChanged` for `OneChanged<dyn Tooltip>`.
+/// This is not supported for generic traits, or traits with associated types.
+///
+/// # Upcasting
+///
+/// Using the form `#[queryable(upcast)]` will generate a trait named `AsDyn` followed by the name
+/// of the trait (e.g. `AsDynTooltip` for a trait `Tooltip`), with a method `as_dyn` for converting
+/// a reference to any implementor into a trait object, such as `monster.as_dyn()`.
+/// This is not supported for generic traits, or traits with associated types.
+///
+/// Multiple options can be combined, as in `#[queryable(no_bounds, prelude)]`.
 #[proc_macro_attribute]
 pub fn queryable(attr: TokenStream, item: TokenStream) -> TokenStream {
     impl_trait_query(attr, item)
@@ -37,20 +58,47 @@ pub fn queryable(attr: TokenStream, item: TokenStream) -> TokenStream {
         .into()
 }
 
+#[derive(Default)]
+struct QueryableArgs {
+    no_bounds: bool,
+    prelude: bool,
+    upcast: bool,
+}
+
+fn parse_args(arg: TokenStream) -> Result<QueryableArgs> {
+    const VALID_FORMS: &str =
+        "Valid forms are: `#[queryable]` and `#[queryable(option, ...)]`, where the options are `no_bounds`, `prelude` and `upcast`";
+
+    let options = Punctuated::<syn::Ident, Token![,]>::parse_terminated
+        .parse(arg)
+        .map_err(|e| syn::Error::new(e.span(), VALID_FORMS))?;
+
+    let mut args = QueryableArgs::default();
+    for option in options {
+        let flag = match option.to_string().as_str() {
+            "no_bounds" => &mut args.no_bounds,
+            "prelude" => &mut args.prelude,
+            "upcast" => &mut args.upcast,
+            _ => return Err(syn::Error::new(option.span(), VALID_FORMS)),
+        };
+        if *flag {
+            return Err(syn::Error::new(option.span(), "Duplicate option"));
+        }
+        *flag = true;
+    }
+    Ok(args)
+}
+
 fn impl_trait_query(arg: TokenStream, item: TokenStream) -> Result<TokenStream2> {
-    syn::custom_keyword!(no_bounds);
-    let no_bounds: Option<no_bounds> = syn::parse(arg).map_err(|e| {
-        syn::Error::new(
-            e.span(),
-            "Valid forms are: `#[queryable]` and `#[queryable(no_bounds)]`",
-        )
-    })?;
+    let args = parse_args(arg)?;
 
     let mut trait_definition = syn::parse::<ItemTrait>(item)?;
     let trait_name = trait_definition.ident.clone();
 
+    check_object_safety(&trait_definition)?;
+
     // Add `'static` bounds, unless the user asked us not to.
-    if no_bounds.is_none() {
+    if !args.no_bounds {
         trait_definition.supertraits.push(parse_quote!('static));
 
         for param in &mut trait_definition.generics.params {
@@ -112,14 +160,7 @@ fn impl_trait_query(arg: TokenStream, item: TokenStream) -> Result<TokenStream2>
 
     let trait_object = quote! { dyn #trait_name #trait_generics };
 
-    let my_crate = proc_macro_crate::crate_name("bevy-trait-query").unwrap();
-    let my_crate = match my_crate {
-        proc_macro_crate::FoundCrate::Itself => quote! { bevy_trait_query },
-        proc_macro_crate::FoundCrate::Name(x) => {
-            let ident = quote::format_ident!("{x}");
-            quote! { #ident }
-        }
-    };
+    let my_crate = crate_path(&trait_name)?;
 
     let imports = quote! { #my_crate::imports };
 
@@ -130,6 +171,11 @@ fn impl_trait_query(arg: TokenStream, item: TokenStream) -> Result<TokenStream2>
         .push(parse_quote!(__Component: #trait_name #trait_generics + #imports::Component));
     let marker_impl_generics = quote! { <#( #marker_impl_generics_list ,)*> };
 
+    let mut resource_impl_generics_list = impl_generics_list.clone();
+    resource_impl_generics_list
+        .push(parse_quote!(__Resource: #trait_name #trait_generics + #imports::Resource));
+    let resource_impl_generics = quote! { <#( #resource_impl_generics_list ,)*> };
+
     let marker_impl_code = quote! {
         impl #impl_generics #trait_query for #trait_object #where_clause {}
 
@@ -141,6 +187,15 @@ fn impl_trait_query(arg: TokenStream, item: TokenStream) -> Result<TokenStream2>
                 ptr as *mut __Component as *mut _
             }
         }
+
+        impl #resource_impl_generics #my_crate::TraitResourceMarker::<#trait_object> for (__Resource,)
+        #where_clause
+        {
+            type Covered = __Resource;
+            fn cast(ptr: *mut u8) -> *mut #trait_object {
+                ptr as *mut __Resource as *mut _
+            }
+        }
     };
 
     let mut impl_generics_with_lifetime = impl_generics_list.clone();
@@ -364,11 +419,302 @@ fn impl_trait_query(arg: TokenStream, item: TokenStream) -> Result<TokenStream2>
         }
     };
 
+    // Point at the trait declaration if the user forgot to add any bounds themselves.
+    // Only generic parameters can make the trait object non-'static,
+    // so there is nothing to check for traits without any.
+    let bounds_assertion_code = if args.no_bounds && !impl_generics_list.is_empty() {
+        quote_spanned! {trait_name.span()=>
+            const _: () = {
+                fn __assert_static<T: ?Sized + 'static>() {}
+                #[allow(dead_code)]
+                fn __assert_bounds #impl_generics () #where_clause {
+                    __assert_static::<#trait_object>();
+                }
+            };
+        }
+    } else {
+        quote! {}
+    };
+
+    let prelude_code = if args.prelude {
+        impl_prelude(&trait_definition, &my_crate)?
+    } else {
+        quote! {}
+    };
+
+    let upcast_code = if args.upcast {
+        impl_upcast(&trait_definition)?
+    } else {
+        quote! {}
+    };
+
     Ok(quote! {
         #trait_definition
 
+        #bounds_assertion_code
+
         #marker_impl_code
 
         #trait_object_query_code
+
+        #prelude_code
+
+        #upcast_code
+    })
+}
+
+/// Resolves an absolute path to `bevy-trait-query`, accounting for renamed dependencies.
+///
+/// The path starts with `::` so that it cannot be shadowed by items in the module
+/// containing the trait.
+fn crate_path(trait_name: &syn::Ident) -> Result<TokenStream2> {
+    let name = match proc_macro_crate::crate_name("bevy-trait-query") {
+        // `bevy-trait-query` declares `extern crate self as bevy_trait_query`,
+        // so this works within the crate as well as in its tests and examples.
+        Ok(proc_macro_crate::FoundCrate::Itself) => "bevy_trait_query".to_owned(),
+        Ok(proc_macro_crate::FoundCrate::Name(name)) => name,
+        Err(e) => {
+            return Err(syn::Error::new(
+                trait_name.span(),
+                format!(
+                    "could not find the `bevy-trait-query` crate: {e}; make sure it is listed in the dependencies of this crate"
+                ),
+            ))
+        }
+    };
+    let ident = format_ident!("{name}");
+    Ok(quote! { ::#ident })
+}
+
+/// Reports methods that would prevent the trait from being made into an object,
+/// which would otherwise cause cryptic object safety errors in the generated code.
+///
+/// Methods with a `where Self: Sized` bound are skipped, since they are not callable
+/// on trait objects and so do not affect object safety.
+fn check_object_safety(trait_definition: &ItemTrait) -> Result<()> {
+    for item in &trait_definition.items {
+        let TraitItem::Fn(method) = item else {
+            continue;
+        };
+        let sig = &method.sig;
+        if requires_sized(sig) {
+            continue;
+        }
+        let name = &sig.ident;
+        if let Some(param) = sig
+            .generics
+            .params
+            .iter()
+            .find(|param| !matches!(param, syn::GenericParam::Lifetime(_)))
+        {
+            return Err(syn::Error::new_spanned(
+                param,
+                format!(
+                    "Generic methods are not supported in trait queries, since they prevent the trait from being made into an object. Consider using a concrete type or a `&dyn` parameter in `{name}` instead, or add `where Self: Sized` to exclude it from trait queries",
+                ),
+            ));
+        }
+        if sig.receiver().is_none() {
+            return Err(syn::Error::new_spanned(
+                sig,
+                format!(
+                    "Associated functions without a `self` parameter are not supported in trait queries, since they prevent the trait from being made into an object. Consider adding a receiver to `{name}`, or add `where Self: Sized` to exclude it from trait queries",
+                ),
+            ));
+        }
+        for input in &sig.inputs {
+            let syn::FnArg::Typed(arg) = input else {
+                continue;
+            };
+            if let syn::Type::ImplTrait(_) = &*arg.ty {
+                return Err(syn::Error::new_spanned(
+                    &arg.ty,
+                    format!(
+                        "Parameters of type `impl Trait` are not supported in trait queries, since they prevent the trait from being made into an object. Consider using a `&dyn` parameter in `{name}` instead",
+                    ),
+                ));
+            }
+            if mentions_self(arg.ty.to_token_stream()) {
+                return Err(syn::Error::new_spanned(
+                    &arg.ty,
+                    format!(
+                        "Parameters mentioning `Self` are not supported in trait queries, since they prevent the trait from being made into an object. Consider using a `&dyn` parameter in `{name}` instead, or add `where Self: Sized` to exclude it from trait queries",
+                    ),
+                ));
+            }
+        }
+        if let syn::ReturnType::Type(_, ty) = &sig.output {
+            if let syn::Type::ImplTrait(impl_trait) = &**ty {
+                let bounds = &impl_trait.bounds;
+                return Err(syn::Error::new_spanned(
+                    ty,
+                    format!(
+                        "Methods returning `impl Trait` are not supported in trait queries, since they prevent the trait from being made into an object. Consider returning `Box<dyn {}>` instead",
+                        render_tokens(quote!(#bounds)),
+                    ),
+                ));
+            }
+            if mentions_self(ty.to_token_stream()) {
+                return Err(syn::Error::new_spanned(
+                    ty,
+                    format!(
+                        "Methods returning `Self` are not supported in trait queries, since they prevent the trait from being made into an object. Consider returning a `Box<dyn {}>` from `{name}` instead, or add `where Self: Sized` to exclude it from trait queries",
+                        trait_definition.ident,
+                    ),
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Returns `true` if the method has a `Self: Sized` bound in its where clause.
+fn requires_sized(sig: &syn::Signature) -> bool {
+    let Some(where_clause) = &sig.generics.where_clause else {
+        return false;
+    };
+    where_clause.predicates.iter().any(|predicate| {
+        let syn::WherePredicate::Type(predicate) = predicate else {
+            return false;
+        };
+        let is_self = matches!(&predicate.bounded_ty, syn::Type::Path(ty) if ty.qself.is_none() && ty.path.is_ident("Self"));
+        is_self
+            && predicate.bounds.iter().any(|bound| {
+                matches!(bound, syn::TypeParamBound::Trait(bound)
+                    if bound.path.segments.last().is_some_and(|segment| segment.ident == "Sized"))
+            })
     })
 }
+
+/// Returns `true` if the tokens refer to the `Self` type.
+/// Associated types such as `Self::Item` are allowed in trait objects, so they don't count.
+fn mentions_self(tokens: TokenStream2) -> bool {
+    let mut tokens = tokens.into_iter().peekable();
+    while let Some(token) = tokens.next() {
+        match token {
+            proc_macro2::TokenTree::Ident(ident) if ident == "Self" => {
+                // `Self::Item` or `<Self as Trait>::Item`.
+                let is_path = match tokens.peek() {
+                    Some(proc_macro2::TokenTree::Punct(punct)) => punct.as_char() == ':',
+                    Some(proc_macro2::TokenTree::Ident(ident)) => ident == "as",
+                    _ => false,
+                };
+                if !is_path {
+                    return true;
+                }
+            }
+            proc_macro2::TokenTree::Group(group) => {
+                if mentions_self(group.stream()) {
+                    return true;
+                }
+            }
+            _ => {}
+        }
+    }
+    false
+}
+
+/// Generates a module containing the query adapters specialized to a trait.
+fn impl_prelude(trait_definition: &ItemTrait, my_crate: &TokenStream2) -> Result<TokenStream2> {
+    let has_assoc_types = trait_definition
+        .items
+        .iter()
+        .any(|item| matches!(item, TraitItem::Type(_)));
+    if !trait_definition.generics.params.is_empty() || has_assoc_types {
+        return Err(syn::Error::new(
+            trait_definition.ident.span(),
+            "`#[queryable(prelude)]` is not supported for generic traits or traits with associated types",
+        ));
+    }
+
+    let vis = &trait_definition.vis;
+    let trait_name = &trait_definition.ident;
+    let mod_name = format_ident!("{}_query", to_snake_case(&trait_name.to_string()));
+    let doc = format!("Query adapters specialized to [`{trait_name}`](super::{trait_name}).");
+
+    Ok(quote! {
+        #[doc = #doc]
+        #vis mod #mod_name {
+            pub type All = #my_crate::All<&'static dyn super::#trait_name>;
+            pub type AllMut = #my_crate::All<&'static mut dyn super::#trait_name>;
+            pub type One = #my_crate::One<&'static dyn super::#trait_name>;
+            pub type OneMut = #my_crate::One<&'static mut dyn super::#trait_name>;
+            pub type OneAdded = #my_crate::OneAdded<dyn super::#trait_name>;
+            pub type OneChanged = #my_crate::OneChanged<dyn super::#trait_name>;
+            pub type WithOne = #my_crate::WithOne<dyn super::#trait_name>;
+            pub type WithoutAny = #my_crate::WithoutAny<dyn super::#trait_name>;
+            pub type Matches = #my_crate::Matches<dyn super::#trait_name>;
+        }
+    })
+}
+
+fn impl_upcast(trait_definition: &ItemTrait) -> Result<TokenStream2> {
+    let has_assoc_types = trait_definition
+        .items
+        .iter()
+        .any(|item| matches!(item, TraitItem::Type(_)));
+    if !trait_definition.generics.params.is_empty() || has_assoc_types {
+        return Err(syn::Error::new(
+            trait_definition.ident.span(),
+            "`#[queryable(upcast)]` is not supported for generic traits or traits with associated types",
+        ));
+    }
+
+    let vis = &trait_definition.vis;
+    let trait_name = &trait_definition.ident;
+    let upcast_name = format_ident!("AsDyn{trait_name}");
+    let doc =
+        format!("Converts references to implementors of [`{trait_name}`] into trait objects.");
+    let method_doc = format!("Returns `self` as a `&dyn {trait_name}`.");
+
+    Ok(quote! {
+        #[doc = #doc]
+        #vis trait #upcast_name {
+            #[doc = #method_doc]
+            fn as_dyn(&self) -> &dyn #trait_name;
+        }
+
+        impl<__T: #trait_name> #upcast_name for __T {
+            #[inline]
+            fn as_dyn(&self) -> &dyn #trait_name {
+                self
+            }
+        }
+    })
+}
+
+/// Renders tokens for use in an error message.
+///
+/// `TokenStream::to_string` puts spaces around every punctuation token,
+/// giving types such as `Iterator < Item = u32 >`, so tighten those back up.
+fn render_tokens(tokens: TokenStream2) -> String {
+    let mut rendered = tokens.to_string();
+    for (from, to) in [
+        (" <", "<"),
+        ("< ", "<"),
+        (" >", ">"),
+        (" ::", "::"),
+        (":: ", "::"),
+        (" ,", ","),
+        (" (", "("),
+        ("& ", "&"),
+    ] {
+        rendered = rendered.replace(from, to);
+    }
+    rendered
+}
+
+fn to_snake_case(name: &str) -> String {
+    let mut snake = String::new();
+    for (i, c) in name.char_indices() {
+        if c.is_uppercase() {
+            if i != 0 {
+                snake.push('_');
+            }
+            snake.extend(c.to_lowercase());
+        } else {
+            snake.push(c);
+        }
+    }
+    snake
+}
@@ -0,0 +1,165 @@
+use std::ops::Range;
+
+use bevy_ecs::{archetype::ArchetypeId, component::Tick, storage::TableId, world::World};
+
+use crate::{
+    debug_unreachable, trait_registry_error, ReadTraits, TraitImplRegistry, TraitQuery,
+    TraitQueryState,
+};
+
+/// A contiguous chunk of entities matched by a trait query, all stored in the same table.
+///
+/// Created by [`TraitBatches`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TraitBatch {
+    archetype: ArchetypeId,
+    table: TableId,
+    range: Range<usize>,
+}
+
+impl TraitBatch {
+    /// The table containing the entities in this batch.
+    #[inline]
+    pub fn table(&self) -> TableId {
+        self.table
+    }
+
+    /// The range of entities in this batch, as indices into its archetype.
+    #[inline]
+    pub fn range(&self) -> Range<usize> {
+        self.range.clone()
+    }
+
+    /// The number of entities in this batch.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.range.len()
+    }
+
+    /// Returns `true` if there are no entities in this batch.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.range.is_empty()
+    }
+}
+
+/// Low-level manual batching for `&dyn Trait` queries, for use with custom parallel executors.
+///
+/// This splits every entity with at least one impl of `Trait` into [`TraitBatch`]es,
+/// which can be distributed between threads and read using [`fetch_at`](Self::fetch_at).
+/// Since this only borrows the [`World`] immutably, any number of threads can fetch
+/// from the same batches at once.
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_trait_query::*;
+/// #
+/// # #[bevy_trait_query::queryable]
+/// # pub trait Tooltip {
+/// #     fn tooltip(&self) -> &str;
+/// # }
+/// #
+/// fn print_tooltips(world: &World, state: &TraitQueryState<dyn Tooltip>) {
+///     let batches = TraitBatches::new(world, state, 64);
+///     std::thread::scope(|scope| {
+///         for batch in batches.batches() {
+///             let batches = &batches;
+///             scope.spawn(move || {
+///                 for index in 0..batch.len() {
+///                     // SAFETY: `batch` came from `batches`, and `index` is in bounds.
+///                     let tooltips = unsafe { batches.fetch_at(batch, index) };
+///                     for tooltip in tooltips {
+///                         println!("{}", tooltip.tooltip());
+///                     }
+///                 }
+///             });
+///         }
+///     });
+/// }
+/// ```
+pub struct TraitBatches<'w, Trait: ?Sized + TraitQuery> {
+    world: &'w World,
+    registry: &'w TraitImplRegistry<Trait>,
+    single_table: bool,
+    batches: Vec<TraitBatch>,
+    last_run: Tick,
+    this_run: Tick,
+}
+
+impl<'w, Trait: ?Sized + TraitQuery> TraitBatches<'w, Trait> {
+    /// Splits the entities matched by `state` into batches of at most `batch_size` entities.
+    ///
+    /// Change detection is relative to the last change tick of the world.
+    ///
+    /// # Panics
+    /// If `batch_size` is zero.
+    pub fn new(world: &'w World, state: &TraitQueryState<Trait>, batch_size: usize) -> Self {
+        assert!(batch_size > 0, "`batch_size` must be greater than zero");
+        let mut batches = Vec::new();
+        for archetype in world.archetypes().iter() {
+            if archetype.is_empty()
+                || !state.matches_component_set_any(&|id| archetype.contains(id))
+            {
+                continue;
+            }
+            let len = archetype.len();
+            for start in (0..len).step_by(batch_size) {
+                batches.push(TraitBatch {
+                    archetype: archetype.id(),
+                    table: archetype.table_id(),
+                    range: start..len.min(start + batch_size),
+                });
+            }
+        }
+        Self {
+            world,
+            registry: world
+                .get_resource()
+                .unwrap_or_else(|| trait_registry_error()),
+            single_table: state.single_table,
+            batches,
+            last_run: world.last_change_tick(),
+            this_run: world.read_change_tick(),
+        }
+    }
+
+    /// The batches of matched entities.
+    #[inline]
+    pub fn batches(&self) -> &[TraitBatch] {
+        &self.batches
+    }
+
+    /// Fetches the trait impls of the entity at `index` within `batch`.
+    ///
+    /// # Safety
+    /// - `batch` must be one of the batches returned by [`batches`](Self::batches) for this value.
+    /// - `index` must be less than `batch.len()`.
+    pub unsafe fn fetch_at(&self, batch: &TraitBatch, index: usize) -> ReadTraits<'w, Trait> {
+        let archetype = self
+            .world
+            .archetypes()
+            .get(batch.archetype)
+            .unwrap_or_else(|| debug_unreachable());
+        let table = self
+            .world
+            .storages()
+            .tables
+            .get(batch.table)
+            .unwrap_or_else(|| debug_unreachable());
+        // SAFETY: The caller guarantees that `index` is in bounds for the batch,
+        // and the batch's range is in bounds for its archetype.
+        let archetype_entity = archetype
+            .entities()
+            .get_unchecked(batch.range.start + index);
+        ReadTraits {
+            registry: self.registry,
+            entity: archetype_entity.id(),
+            table,
+            table_row: archetype_entity.table_row(),
+            sparse_sets: &self.world.storages().sparse_sets,
+            single_table: self.single_table,
+            last_run: self.last_run,
+            this_run: self.this_run,
+        }
+    }
+}
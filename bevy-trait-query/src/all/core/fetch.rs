@@ -1,15 +1,20 @@
+use std::cell::UnsafeCell;
+
 use bevy_ecs::{
-    component::Tick,
-    storage::{SparseSets, Table},
+    component::{ComponentId, StorageType, Tick},
+    entity::Entity,
+    ptr::UnsafeCellDeref,
+    storage::{ComponentSparseSet, SparseSets, Table, TableRow},
 };
 
-use crate::TraitImplRegistry;
+use crate::{debug_unreachable, TraitImplRegistry};
 
 #[doc(hidden)]
 pub struct AllTraitsFetch<'w, Trait: ?Sized> {
     pub(crate) registry: &'w TraitImplRegistry<Trait>,
     pub(crate) table: Option<&'w Table>,
     pub(crate) sparse_sets: &'w SparseSets,
+    pub(crate) single_table: bool,
     pub(crate) last_run: Tick,
     pub(crate) this_run: Tick,
 }
@@ -20,3 +25,42 @@ impl<Trait: ?Sized> Clone for AllTraitsFetch<'_, Trait> {
     }
 }
 impl<Trait: ?Sized> Copy for AllTraitsFetch<'_, Trait> {}
+
+impl<'w, Trait: ?Sized> AllTraitsFetch<'w, Trait> {
+    /// Returns `true` if any of the trait impls of `entity` have a tick newer than the
+    /// last run of the system, where the tick of each impl is looked up by
+    /// `table_tick` or `sparse_tick` depending on its storage type.
+    ///
+    /// # Safety
+    ///
+    /// The table must have been set, `table_row` must be a valid row in it,
+    /// and read access must have been registered for every component in the registry.
+    #[inline]
+    pub(crate) unsafe fn any_newer(
+        &self,
+        entity: Entity,
+        table_row: TableRow,
+        table_tick: impl Fn(&'w Table, ComponentId, TableRow) -> Option<&'w UnsafeCell<Tick>>,
+        sparse_tick: impl Fn(&'w ComponentSparseSet, Entity) -> Option<&'w UnsafeCell<Tick>>,
+    ) -> bool {
+        let table = self.table.unwrap_or_else(|| debug_unreachable());
+        crate::paranoid_check_row(table.entity_count(), table_row);
+        self.registry
+            .components
+            .iter()
+            .zip(&self.registry.storage_types)
+            .any(|(&component, storage_type)| {
+                let tick = match storage_type {
+                    StorageType::Table => table_tick(table, component, table_row),
+                    StorageType::SparseSet => self
+                        .sparse_sets
+                        .get(component)
+                        .and_then(|sparse_set| sparse_tick(sparse_set, entity)),
+                };
+                // SAFETY: Read access has been registered for this component.
+                tick.is_some_and(|tick| {
+                    unsafe { tick.deref() }.is_newer_than(self.last_run, self.this_run)
+                })
+            })
+    }
+}
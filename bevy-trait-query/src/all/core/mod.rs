@@ -1,3 +1,5 @@
+pub(crate) mod batch;
 pub(crate) mod fetch;
 pub(crate) mod read;
+pub(crate) mod view;
 pub(crate) mod write;
@@ -1,8 +1,10 @@
+use std::mem::MaybeUninit;
+
 use bevy_ecs::{
     change_detection::{DetectChanges, Ref},
-    component::{ComponentId, Tick},
+    component::{Component, ComponentId, StorageType, Tick},
     entity::Entity,
-    ptr::UnsafeCellDeref,
+    ptr::{Ptr, UnsafeCellDeref},
     storage::{SparseSets, Table, TableRow},
 };
 
@@ -14,11 +16,20 @@ use crate::{zip_exact, TraitImplMeta, TraitImplRegistry, TraitQuery};
 ///
 /// - [`ReadTraits::iter_changed`]
 /// - [`ReadTraits::iter_added`]
+///
+/// Each item also implements [`DetectChanges`], so the ticks of individual impls
+/// can be inspected with methods such as [`DetectChanges::last_changed`].
+///
+/// Components are always iterated in the order they were registered for the trait in,
+/// regardless of whether they are stored in tables or sparse sets.
+/// Use [`TraitRegistryExt::deterministic_trait_queries`](crate::TraitRegistryExt::deterministic_trait_queries)
+/// to make this order independent of registration order.
 pub struct ReadTraits<'a, Trait: ?Sized + TraitQuery> {
     // Read-only access to the global trait registry.
     // Since no one outside of the crate can name the registry type,
     // we can be confident that no write accesses will conflict with this.
     pub(crate) registry: &'a TraitImplRegistry<Trait>,
+    pub(crate) entity: Entity,
     pub(crate) table: &'a Table,
     pub(crate) table_row: TableRow,
     /// This grants shared access to all sparse set components,
@@ -26,153 +37,302 @@ pub struct ReadTraits<'a, Trait: ?Sized + TraitQuery> {
     /// The fetch impl registers read-access for all of these components,
     /// so there will be no runtime conflicts.
     pub(crate) sparse_sets: &'a SparseSets,
+    /// If the trait has a single impl which is stored in a table,
+    /// we can skip searching the table for registered components.
+    pub(crate) single_table: bool,
     pub(crate) last_run: Tick,
     pub(crate) this_run: Tick,
 }
 
+/// Iterator over the components implementing a trait for a given entity,
+/// in the order they were registered in.
 #[doc(hidden)]
-pub type CombinedReadTraitsIter<'a, Trait> =
-    std::iter::Chain<ReadTableTraitsIter<'a, Trait>, ReadSparseTraitsIter<'a, Trait>>;
-
-#[doc(hidden)]
-pub struct ReadTableTraitsIter<'a, Trait: ?Sized> {
-    // SAFETY: These two iterators must have equal length.
+pub struct ReadTraitsIter<'a, Trait: ?Sized> {
+    // SAFETY: These three iterators must have equal length.
     pub(crate) components: std::slice::Iter<'a, ComponentId>,
     pub(crate) meta: std::slice::Iter<'a, TraitImplMeta<Trait>>,
+    pub(crate) storage_types: std::slice::Iter<'a, StorageType>,
+    pub(crate) entity: Entity,
     pub(crate) table_row: TableRow,
     // Grants shared access to the components corresponding to `components` in this table.
     // Not all components are guaranteed to exist in the table.
     pub(crate) table: &'a Table,
+    // Grants shared access to the components corresponding to both `components` and `entity`.
+    pub(crate) sparse_sets: &'a SparseSets,
+    // If set, `components` contains exactly one component, which is stored in tables.
+    pub(crate) single: bool,
     pub(crate) last_run: Tick,
     pub(crate) this_run: Tick,
 }
 
-impl<'a, Trait: ?Sized + TraitQuery> Iterator for ReadTableTraitsIter<'a, Trait> {
+// Manual impl, since deriving would require `Trait: Clone`.
+impl<Trait: ?Sized> Clone for ReadTraitsIter<'_, Trait> {
+    fn clone(&self) -> Self {
+        Self {
+            components: self.components.clone(),
+            meta: self.meta.clone(),
+            storage_types: self.storage_types.clone(),
+            entity: self.entity,
+            table_row: self.table_row,
+            table: self.table,
+            sparse_sets: self.sparse_sets,
+            single: self.single,
+            last_run: self.last_run,
+            this_run: self.this_run,
+        }
+    }
+}
+
+impl<'a, Trait: ?Sized + TraitQuery> Iterator for ReadTraitsIter<'a, Trait> {
     type Item = Ref<'a, Trait>;
+    #[inline]
     fn next(&mut self) -> Option<Self::Item> {
-        // Iterate the remaining table components that are registered,
-        // until we find one that exists in the table.
-        let (ptr, component, meta) = unsafe { zip_exact(&mut self.components, &mut self.meta) }
-            .find_map(|(&component, meta)| {
-                // SAFETY: we know that the `table_row` is a valid index.
-                let ptr = unsafe { self.table.get_component(component, self.table_row) }?;
-                Some((ptr, component, meta))
-            })?;
-        let trait_object = unsafe { meta.dyn_ctor.cast(ptr) };
+        self.next_with_id().map(|(_, item)| item)
+    }
+}
 
+impl<'a, Trait: ?Sized + TraitQuery> ReadTraitsIter<'a, Trait> {
+    /// Returns the next trait object, along with the [`ComponentId`] of its concrete component.
+    pub(crate) fn next_with_id(&mut self) -> Option<(ComponentId, Ref<'a, Trait>)> {
+        crate::paranoid_check_row(self.table.entity_count(), self.table_row);
+        if self.single {
+            return self.next_single();
+        }
+        // Iterate the remaining components that are registered,
+        // until we find one that exists on the entity.
+        let (component, ptr, added, changed, meta) = unsafe {
+            zip_exact(
+                zip_exact(&mut self.components, &mut self.meta),
+                &mut self.storage_types,
+            )
+        }
+        .find_map(|((&component, meta), &storage_type)| match storage_type {
+            // SAFETY: we know that the `table_row` is a valid index.
+            StorageType::Table => unsafe {
+                let ptr = self.table.get_component(component, self.table_row)?;
+                let added = self.table.get_added_tick(component, self.table_row)?;
+                let changed = self.table.get_changed_tick(component, self.table_row)?;
+                Some((component, ptr, added, changed, meta))
+            },
+            StorageType::SparseSet => {
+                let set = self.sparse_sets.get(component)?;
+                let (ptr, ticks, _) = set.get_with_ticks(self.entity)?;
+                Some((component, ptr, ticks.added, ticks.changed, meta))
+            }
+        })?;
         // SAFETY:
         // Read access has been registered, so we can dereference it immutably.
-        let added_tick = unsafe {
-            self.table
-                .get_added_tick(component, self.table_row)?
-                .deref()
+        let item = unsafe {
+            Ref::new(
+                meta.dyn_ctor.cast(ptr),
+                added.deref(),
+                changed.deref(),
+                self.last_run,
+                self.this_run,
+            )
         };
-        let changed_tick = unsafe {
-            self.table
-                .get_changed_tick(component, self.table_row)?
-                .deref()
-        };
-
-        Some(Ref::new(
-            trait_object,
-            added_tick,
-            changed_tick,
-            self.last_run,
-            self.this_run,
-        ))
+        Some((component, item))
     }
-}
-
-#[doc(hidden)]
-pub struct ReadSparseTraitsIter<'a, Trait: ?Sized> {
-    // SAFETY: These two iterators must have equal length.
-    pub(crate) components: std::slice::Iter<'a, ComponentId>,
-    pub(crate) meta: std::slice::Iter<'a, TraitImplMeta<Trait>>,
-    pub(crate) entity: Entity,
-    // Grants shared access to the components corresponding to both `components` and `entity`.
-    pub(crate) sparse_sets: &'a SparseSets,
-    pub(crate) last_run: Tick,
-    pub(crate) this_run: Tick,
-}
 
-impl<'a, Trait: ?Sized + TraitQuery> Iterator for ReadSparseTraitsIter<'a, Trait> {
-    type Item = Ref<'a, Trait>;
-    fn next(&mut self) -> Option<Self::Item> {
-        // Iterate the remaining sparse set components that are registered,
-        // until we find one that exists in the archetype.
-        let (ptr, ticks_ptr, meta) = unsafe { zip_exact(&mut self.components, &mut self.meta) }
-            .find_map(|(&component, meta)| {
-                let set = self.sparse_sets.get(component)?;
-                let (ptr, ticks, _) = set.get_with_ticks(self.entity)?;
-                Some((ptr, ticks, meta))
-            })?;
-        let trait_object = unsafe { meta.dyn_ctor.cast(ptr) };
-        let added_tick = unsafe { ticks_ptr.added.deref() };
-        let changed_tick = unsafe { ticks_ptr.changed.deref() };
-        Some(Ref::new(
-            trait_object,
-            added_tick,
-            changed_tick,
-            self.last_run,
-            self.this_run,
-        ))
+    /// Fast path for traits with a single impl, which is stored in tables.
+    #[inline]
+    fn next_single(&mut self) -> Option<(ComponentId, Ref<'a, Trait>)> {
+        let component = *self.components.next()?;
+        let meta = self.meta.next()?;
+        // The impl is not guaranteed to exist in this table,
+        // since the entity may have been matched by some other part of the query.
+        let column = self.table.get_column(component)?;
+        // SAFETY: we know that the `table_row` is a valid index.
+        // Read access has been registered, so we can dereference it immutably.
+        let item = unsafe {
+            Ref::new(
+                meta.dyn_ctor
+                    .cast(column.get_data_unchecked(self.table_row)),
+                column.get_added_tick_unchecked(self.table_row).deref(),
+                column.get_changed_tick_unchecked(self.table_row).deref(),
+                self.last_run,
+                self.this_run,
+            )
+        };
+        Some((component, item))
     }
 }
 
 impl<'w, Trait: ?Sized + TraitQuery> IntoIterator for ReadTraits<'w, Trait> {
     type Item = Ref<'w, Trait>;
-    type IntoIter = CombinedReadTraitsIter<'w, Trait>;
+    type IntoIter = ReadTraitsIter<'w, Trait>;
     #[inline]
     fn into_iter(self) -> Self::IntoIter {
-        let table = ReadTableTraitsIter {
-            components: self.registry.table_components.iter(),
-            meta: self.registry.table_meta.iter(),
-            table: self.table,
-            table_row: self.table_row,
-            last_run: self.last_run,
-            this_run: self.this_run,
-        };
-        let sparse = ReadSparseTraitsIter {
-            components: self.registry.sparse_components.iter(),
-            meta: self.registry.sparse_meta.iter(),
-            entity: self.table.entities()[self.table_row.as_usize()],
-            sparse_sets: self.sparse_sets,
-            last_run: self.last_run,
-            this_run: self.this_run,
-        };
-        table.chain(sparse)
+        self.iter()
     }
 }
 
 impl<'w, Trait: ?Sized + TraitQuery> IntoIterator for &ReadTraits<'w, Trait> {
     type Item = Ref<'w, Trait>;
-    type IntoIter = CombinedReadTraitsIter<'w, Trait>;
+    type IntoIter = ReadTraitsIter<'w, Trait>;
     #[inline]
     fn into_iter(self) -> Self::IntoIter {
-        let table = ReadTableTraitsIter {
-            components: self.registry.table_components.iter(),
-            meta: self.registry.table_meta.iter(),
-            table: self.table,
+        self.iter()
+    }
+}
+
+/// How a component has changed since the last time a system was run,
+/// as yielded by [`ReadTraits::iter_classified`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ChangeKind {
+    /// The component was added since the last run.
+    /// Newly added components are not also classified as changed.
+    Added,
+    /// The component was changed since the last run, but not added.
+    Changed,
+    /// The component was neither added nor changed since the last run.
+    Unchanged,
+}
+
+impl ChangeKind {
+    #[inline]
+    fn of(item: &impl DetectChanges) -> Self {
+        if item.is_added() {
+            Self::Added
+        } else if item.is_changed() {
+            Self::Changed
+        } else {
+            Self::Unchanged
+        }
+    }
+}
+
+/// Returns the ids of the components implementing `Trait` for `entity`,
+/// without fetching any of them.
+pub(crate) fn present_components<'a, Trait: ?Sized>(
+    registry: &'a TraitImplRegistry<Trait>,
+    table: &'a Table,
+    sparse_sets: &'a SparseSets,
+    entity: Entity,
+) -> impl Iterator<Item = ComponentId> + 'a {
+    registry
+        .components
+        .iter()
+        .zip(&registry.storage_types)
+        .filter(move |&(&component, storage_type)| match storage_type {
+            StorageType::Table => table.has_column(component),
+            StorageType::SparseSet => sparse_sets
+                .get(component)
+                .is_some_and(|sparse_set| sparse_set.contains(entity)),
+        })
+        .map(|(&component, _)| component)
+}
+
+impl<'w, Trait: ?Sized + TraitQuery> ReadTraits<'w, Trait> {
+    /// Returns the entity that these trait impls belong to.
+    #[inline]
+    pub fn entity(&self) -> Entity {
+        self.entity
+    }
+
+    /// Returns an iterator over the components implementing `Trait` for the current entity.
+    ///
+    /// Components are yielded in the order they were registered in with
+    /// [`register_component_as`](crate::RegisterExt::register_component_as),
+    /// regardless of whether they are stored in tables or sparse sets.
+    pub fn iter(&self) -> ReadTraitsIter<'w, Trait> {
+        ReadTraitsIter {
+            components: self.registry.components.iter(),
+            meta: self.registry.meta.iter(),
+            storage_types: self.registry.storage_types.iter(),
+            entity: self.entity,
             table_row: self.table_row,
-            last_run: self.last_run,
-            this_run: self.this_run,
-        };
-        let sparse = ReadSparseTraitsIter {
-            components: self.registry.sparse_components.iter(),
-            meta: self.registry.sparse_meta.iter(),
-            entity: self.table.entities()[self.table_row.as_usize()],
+            table: self.table,
             sparse_sets: self.sparse_sets,
+            single: self.single_table,
             last_run: self.last_run,
             this_run: self.this_run,
+        }
+    }
+
+    /// Returns the ids of the components implementing `Trait` for the current entity,
+    /// without fetching any of them.
+    fn present_components(&self) -> impl Iterator<Item = ComponentId> + '_ {
+        present_components(self.registry, self.table, self.sparse_sets, self.entity)
+    }
+
+    /// Folds every component implementing `Trait` for the current entity into an accumulator.
+    ///
+    /// This is equivalent to `iter().fold(init, f)`, but is faster since it skips reading
+    /// the change detection ticks of each component.
+    pub fn fold<B>(&self, init: B, mut f: impl FnMut(B, &Trait) -> B) -> B {
+        let mut acc = init;
+        // SAFETY: Each component is registered along with its metadata and storage type.
+        let impls = unsafe {
+            zip_exact(
+                zip_exact(&self.registry.components, &self.registry.meta),
+                &self.registry.storage_types,
+            )
         };
-        table.chain(sparse)
+        for ((&component, meta), storage_type) in impls {
+            let ptr = match storage_type {
+                // SAFETY: we know that the `table_row` is a valid index.
+                StorageType::Table => unsafe {
+                    self.table.get_component(component, self.table_row)
+                },
+                StorageType::SparseSet => self
+                    .sparse_sets
+                    .get(component)
+                    .and_then(|sparse_set| sparse_set.get(self.entity)),
+            };
+            if let Some(ptr) = ptr {
+                // SAFETY: Read access has been registered, so we can dereference it immutably.
+                acc = f(acc, unsafe { meta.dyn_ctor.cast(ptr) });
+            }
+        }
+        acc
     }
-}
 
-impl<'w, Trait: ?Sized + TraitQuery> ReadTraits<'w, Trait> {
-    /// Returns an iterator over the components implementing `Trait` for the current entity.
-    pub fn iter(&self) -> CombinedReadTraitsIter<'w, Trait> {
-        self.into_iter()
+    /// Returns the number of components implementing `Trait` for the current entity.
+    ///
+    /// This is cheaper than `iter().count()`, since it only checks which components are present.
+    pub fn len(&self) -> usize {
+        self.present_components().count()
+    }
+
+    /// Returns `true` if the current entity has no components implementing `Trait`.
+    pub fn is_empty(&self) -> bool {
+        self.present_components().next().is_none()
+    }
+
+    /// Returns the first component implementing `Trait` for the current entity,
+    /// in the same order as [`iter`](Self::iter).
+    ///
+    /// This stops at the first registered component that is present on the entity,
+    /// without looking at any of the components registered after it.
+    #[inline]
+    pub fn first(&self) -> Option<Ref<'w, Trait>> {
+        self.iter().next()
+    }
+
+    /// Returns the `n`th component implementing `Trait` for the current entity,
+    /// in the same order as [`iter`](Self::iter).
+    #[inline]
+    pub fn nth(&self, n: usize) -> Option<Ref<'w, Trait>> {
+        self.iter().nth(n)
+    }
+
+    /// Returns the components implementing `Trait` for the current entity as an array,
+    /// if there are exactly `N` of them.
+    ///
+    /// This is convenient for entities which are known to have a small, fixed number of impls.
+    pub fn try_into_array<const N: usize>(self) -> Option<[Ref<'w, Trait>; N]> {
+        if self.len() != N {
+            return None;
+        }
+        let mut array = [const { MaybeUninit::uninit() }; N];
+        let mut iter = self.iter();
+        for slot in &mut array {
+            // `Ref` has no drop glue, so returning early doesn't leak anything.
+            slot.write(iter.next()?);
+        }
+        // SAFETY: Every element of the array was initialized above.
+        Some(array.map(|item| unsafe { item.assume_init() }))
     }
 
     /// Returns an iterator over the components implementing `Trait` for the current entity
@@ -186,4 +346,302 @@ impl<'w, Trait: ?Sized + TraitQuery> ReadTraits<'w, Trait> {
     pub fn iter_changed(&self) -> impl Iterator<Item = Ref<'w, Trait>> {
         self.iter().filter(DetectChanges::is_changed)
     }
+
+    /// Returns an iterator over the components implementing `Trait` for the current entity,
+    /// along with how each of them has changed since the last time the system was run.
+    ///
+    /// This classifies every component in a single pass, which is cheaper than
+    /// calling both [`iter_added`](Self::iter_added) and [`iter_changed`](Self::iter_changed).
+    pub fn iter_classified(&self) -> impl Iterator<Item = (Ref<'w, Trait>, ChangeKind)> {
+        self.iter().map(|item| {
+            let kind = ChangeKind::of(&item);
+            (item, kind)
+        })
+    }
+
+    /// Returns an iterator over the components implementing `Trait` for the current entity,
+    /// along with the [`ComponentId`] of each concrete component.
+    pub fn iter_with_id(&self) -> impl Iterator<Item = (ComponentId, Ref<'w, Trait>)> {
+        let mut iter = self.iter();
+        std::iter::from_fn(move || iter.next_with_id())
+    }
+
+    /// Returns an iterator over the components implementing `Trait` for the current entity,
+    /// along with the [`ComponentId`] of each concrete component, in ascending order of [`ComponentId`].
+    ///
+    /// Unlike the other iterators, which yield impls in the order they were registered in,
+    /// the order of this one does not depend on registration order.
+    /// This requires collecting and sorting the impls on each call.
+    pub fn iter_sorted_by_id(&self) -> std::vec::IntoIter<(ComponentId, Ref<'w, Trait>)> {
+        let mut impls: Vec<_> = self.iter_with_id().collect();
+        impls.sort_unstable_by_key(|&(id, _)| id);
+        impls.into_iter()
+    }
+
+    /// Returns an iterator over the components implementing `Trait` for the current entity
+    /// that were added since the last time the system was run,
+    /// along with the [`ComponentId`] of each concrete component.
+    pub fn iter_added_with_id(&self) -> impl Iterator<Item = (ComponentId, Ref<'w, Trait>)> {
+        self.iter_with_id().filter(|(_, item)| item.is_added())
+    }
+
+    /// Returns an iterator over the components implementing `Trait` for the current entity
+    /// whose values were changed since the last time the system was run,
+    /// along with the [`ComponentId`] of each concrete component.
+    pub fn iter_changed_with_id(&self) -> impl Iterator<Item = (ComponentId, Ref<'w, Trait>)> {
+        self.iter_with_id().filter(|(_, item)| item.is_changed())
+    }
+
+    /// Returns an untyped pointer to the component implementing `Trait`, along with its [`ComponentId`],
+    /// if the current entity has exactly one impl and that impl is stored in a table.
+    ///
+    /// This is an escape hatch for processing the raw component data directly.
+    /// Returns `None` if the entity has more than one impl, or if its impl is stored in a sparse set.
+    pub fn single_table_ptr(&self) -> Option<(ComponentId, Ptr<'w>)> {
+        let mut found = None;
+        for component in self.registry.components_with_storage(StorageType::Table) {
+            // SAFETY: we know that the `table_row` is a valid index.
+            if let Some(ptr) = unsafe { self.table.get_component(component, self.table_row) } {
+                if found.is_some() {
+                    return None;
+                }
+                found = Some((component, ptr));
+            }
+        }
+        let has_sparse_impl = self
+            .registry
+            .components_with_storage(StorageType::SparseSet)
+            .any(|component| {
+                self.sparse_sets
+                    .get(component)
+                    .is_some_and(|set| set.contains(self.entity))
+            });
+        if has_sparse_impl {
+            return None;
+        }
+        found
+    }
+
+    /// Returns a pointer to the registered component `component`, if it is present on the current entity.
+    fn get_registered_ptr(&self, component: ComponentId) -> Option<Ptr<'w>> {
+        // We only have access to components that are registered for this trait.
+        let index = self
+            .registry
+            .components
+            .iter()
+            .position(|&c| c == component)?;
+        match self.registry.storage_types[index] {
+            // SAFETY: we know that the `table_row` is a valid index,
+            // and read access has been registered for the component.
+            StorageType::Table => unsafe { self.table.get_component(component, self.table_row) },
+            StorageType::SparseSet => self.sparse_sets.get(component)?.get(self.entity),
+        }
+    }
+
+    /// Returns a reference to the concrete component `C`, if it is registered as an impl of `Trait`
+    /// and is present on the current entity.
+    pub fn get_as<C: Component>(&self) -> Option<&'w C> {
+        let ptr = self.get_registered_ptr(self.registry.component_id_of::<C>()?)?;
+        // SAFETY: the pointer refers to a component of type `C`.
+        Some(unsafe { ptr.deref::<C>() })
+    }
+
+    /// Returns an owned clone of the concrete component `C`, if it is registered as an impl of `Trait`
+    /// and is present on the current entity.
+    pub fn extract<C: Component + Clone>(&self) -> Option<C> {
+        let ptr = self.get_registered_ptr(self.registry.component_id_of::<C>()?)?;
+        // SAFETY: the pointer refers to a component of type `C`.
+        Some(unsafe { ptr.deref::<C>() }.clone())
+    }
+
+    /// Returns references to each of the concrete components in the tuple `T`,
+    /// or `None` if any of them are missing from the current entity or are not registered as
+    /// impls of `Trait`.
+    ///
+    /// ```
+    /// # use bevy::prelude::*;
+    /// # use bevy_trait_query::*;
+    /// #
+    /// # #[bevy_trait_query::queryable]
+    /// # pub trait Tooltip {
+    /// #     fn tooltip(&self) -> &str;
+    /// # }
+    /// #
+    /// # #[derive(Component)]
+    /// # struct Player(String);
+    /// #
+    /// # #[derive(Component)]
+    /// # struct Monster;
+    /// #
+    /// # impl Tooltip for Player {
+    /// #     fn tooltip(&self) -> &str {
+    /// #         &self.0
+    /// #     }
+    /// # }
+    /// #
+    /// # impl Tooltip for Monster {
+    /// #     fn tooltip(&self) -> &str {
+    /// #         "Run!"
+    /// #     }
+    /// # }
+    /// #
+    /// fn system(tooltips: Query<&dyn Tooltip>) {
+    ///     for tooltips in &tooltips {
+    ///         if let Some((player, _monster)) = tooltips.try_as::<(Player, Monster)>() {
+    ///             println!("{} is a monster!", player.0);
+    ///         }
+    ///     }
+    /// }
+    /// # bevy_ecs::system::assert_is_system(system);
+    /// ```
+    pub fn try_as<T: ImplTuple>(&self) -> Option<T::Refs<'w>> {
+        T::get_from(self)
+    }
+}
+
+/// A tuple of concrete components, which can be extracted from [`ReadTraits`] using [`ReadTraits::try_as`].
+pub trait ImplTuple {
+    /// A tuple of references to each of the components.
+    type Refs<'w>;
+
+    /// Gets references to each of the components from `traits`.
+    fn get_from<'w, Trait: ?Sized + TraitQuery>(
+        traits: &ReadTraits<'w, Trait>,
+    ) -> Option<Self::Refs<'w>>;
+}
+
+macro_rules! impl_tuple {
+    ($($C:ident),*) => {
+        impl<$($C: Component),*> ImplTuple for ($($C,)*) {
+            type Refs<'w> = ($(&'w $C,)*);
+
+            #[inline]
+            fn get_from<'w, Trait: ?Sized + TraitQuery>(
+                traits: &ReadTraits<'w, Trait>,
+            ) -> Option<Self::Refs<'w>> {
+                Some(($(traits.get_as::<$C>()?,)*))
+            }
+        }
+    };
+}
+
+impl_tuple!(C0);
+impl_tuple!(C0, C1);
+impl_tuple!(C0, C1, C2);
+impl_tuple!(C0, C1, C2, C3);
+impl_tuple!(C0, C1, C2, C3, C4);
+impl_tuple!(C0, C1, C2, C3, C4, C5);
+impl_tuple!(C0, C1, C2, C3, C4, C5, C6);
+impl_tuple!(C0, C1, C2, C3, C4, C5, C6, C7);
+
+/// Generates an enum over a fixed set of impls of a trait, for exhaustive matching over trait impls.
+///
+/// The generated enum borrows the concrete component for each variant, and has two associated
+/// functions:
+///
+/// - `classify`, which returns the variant for the first impl in the list that is present in
+///   a [`ReadTraits`], or `None` if none of them are present.
+/// - `classify_one`, which returns the variant for the item of a [`OneTyped`](crate::OneTyped)
+///   query, or `None` if its impl is not in the list.
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_trait_query::*;
+/// #
+/// #[bevy_trait_query::queryable]
+/// pub trait Tooltip {
+///     fn tooltip(&self) -> &str;
+/// }
+///
+/// #[derive(Component)]
+/// struct Player(String);
+///
+/// #[derive(Component)]
+/// struct Monster;
+///
+/// # impl Tooltip for Player {
+/// #     fn tooltip(&self) -> &str {
+/// #         &self.0
+/// #     }
+/// # }
+/// #
+/// # impl Tooltip for Monster {
+/// #     fn tooltip(&self) -> &str {
+/// #         "Run!"
+/// #     }
+/// # }
+/// #
+/// classify_trait! {
+///     pub enum TooltipKind for dyn Tooltip {
+///         Player(Player),
+///         Monster(Monster),
+///     }
+/// }
+///
+/// fn system(tooltips: Query<&dyn Tooltip>) {
+///     for tooltips in &tooltips {
+///         match TooltipKind::classify(&tooltips) {
+///             Some(TooltipKind::Player(player)) => println!("Player: {}", player.0),
+///             Some(TooltipKind::Monster(_)) => println!("Monster!"),
+///             None => {}
+///         }
+///     }
+/// }
+/// # bevy_ecs::system::assert_is_system(system);
+///
+/// fn single_system(tooltips: Query<OneTyped<&dyn Tooltip>>) {
+///     for tooltip in &tooltips {
+///         if let Some(TooltipKind::Player(player)) = TooltipKind::classify_one(tooltip) {
+///             println!("Player: {}", player.0);
+///         }
+///     }
+/// }
+/// # bevy_ecs::system::assert_is_system(single_system);
+/// ```
+#[macro_export]
+macro_rules! classify_trait {
+    (
+        $(#[$attr:meta])*
+        $vis:vis enum $name:ident for $trait:ty {
+            $( $variant:ident($component:ty) ),* $(,)?
+        }
+    ) => {
+        $(#[$attr])*
+        $vis enum $name<'w> {
+            $( $variant(&'w $component), )*
+        }
+
+        impl<'w> $name<'w> {
+            /// Returns the variant for the first impl in this enum that is present in `traits`.
+            #[allow(dead_code)]
+            $vis fn classify(
+                traits: &$crate::ReadTraits<'w, $trait>,
+            ) -> ::core::option::Option<Self> {
+                $(
+                    if let ::core::option::Option::Some(component) =
+                        traits.get_as::<$component>()
+                    {
+                        return ::core::option::Option::Some(Self::$variant(component));
+                    }
+                )*
+                ::core::option::Option::None
+            }
+
+            /// Returns the variant for the impl yielded by a `OneTyped` query,
+            /// or `None` if it is not in this enum.
+            #[allow(dead_code)]
+            $vis fn classify_one(
+                item: $crate::OneTypedItem<'w, $trait>,
+            ) -> ::core::option::Option<Self> {
+                $(
+                    if item.is::<$component>() {
+                        return item.downcast::<$component>().map(|component| {
+                            Self::$variant($crate::imports::Ref::into_inner(component))
+                        });
+                    }
+                )*
+                ::core::option::Option::None
+            }
+        }
+    };
 }
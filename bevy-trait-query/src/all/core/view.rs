@@ -0,0 +1,77 @@
+use bevy_ecs::entity::Entity;
+
+use crate::{ReadTraits, ReadTraitsIter, TraitQuery, WriteTraits};
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+impl<Trait: ?Sized + TraitQuery> sealed::Sealed for ReadTraits<'_, Trait> {}
+impl<Trait: ?Sized + TraitQuery> sealed::Sealed for WriteTraits<'_, Trait> {}
+
+/// Shared read access to the trait impls of an entity,
+/// implemented by both [`ReadTraits`] and [`WriteTraits`].
+///
+/// This allows helper functions to accept the items of both `&dyn Trait`
+/// and `&mut dyn Trait` queries.
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_trait_query::*;
+/// #
+/// #[bevy_trait_query::queryable]
+/// pub trait Person {
+///     fn age(&self) -> u32;
+/// }
+///
+/// fn total_age(people: &impl AsTraitImpls<dyn Person>) -> u32 {
+///     people.iter().map(|person| person.age()).sum()
+/// }
+///
+/// fn print_ages(people: Query<&dyn Person>) {
+///     for traits in &people {
+///         println!("{}: {}", traits.entity(), total_age(&traits));
+///     }
+/// }
+///
+/// fn print_ages_mut(mut people: Query<&mut dyn Person>) {
+///     for traits in &mut people {
+///         println!("{}: {}", traits.entity(), total_age(&traits));
+///     }
+/// }
+/// # bevy_ecs::system::assert_is_system(print_ages);
+/// # bevy_ecs::system::assert_is_system(print_ages_mut);
+/// ```
+///
+/// This trait is sealed, and cannot be implemented outside of this crate.
+pub trait AsTraitImpls<Trait: ?Sized + TraitQuery>: sealed::Sealed {
+    /// Returns the entity that these trait impls belong to.
+    fn entity(&self) -> Entity;
+
+    /// Returns an iterator over the components implementing `Trait` for the current entity.
+    fn iter(&self) -> ReadTraitsIter<'_, Trait>;
+}
+
+impl<Trait: ?Sized + TraitQuery> AsTraitImpls<Trait> for ReadTraits<'_, Trait> {
+    #[inline]
+    fn entity(&self) -> Entity {
+        ReadTraits::entity(self)
+    }
+
+    #[inline]
+    fn iter(&self) -> ReadTraitsIter<'_, Trait> {
+        ReadTraits::iter(self)
+    }
+}
+
+impl<Trait: ?Sized + TraitQuery> AsTraitImpls<Trait> for WriteTraits<'_, Trait> {
+    #[inline]
+    fn entity(&self) -> Entity {
+        WriteTraits::entity(self)
+    }
+
+    #[inline]
+    fn iter(&self) -> ReadTraitsIter<'_, Trait> {
+        WriteTraits::iter(self)
+    }
+}
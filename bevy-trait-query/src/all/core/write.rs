@@ -1,15 +1,13 @@
 use bevy_ecs::{
-    change_detection::{DetectChanges, Mut, Ref},
-    component::{ComponentId, Tick},
+    change_detection::{DetectChanges, Mut, MutUntyped, Ref},
+    component::{ComponentId, StorageType, Tick},
     entity::Entity,
     ptr::UnsafeCellDeref,
     storage::{SparseSets, Table, TableRow},
 };
 
-use crate::{
-    zip_exact, CombinedReadTraitsIter, ReadSparseTraitsIter, ReadTableTraitsIter, TraitImplMeta,
-    TraitImplRegistry, TraitQuery,
-};
+use super::read::present_components;
+use crate::{zip_exact, ReadTraitsIter, TraitImplMeta, TraitImplRegistry, TraitQuery};
 
 /// Write-access to all components implementing a trait for a given entity.
 ///
@@ -17,12 +15,21 @@ use crate::{
 ///
 /// - [`WriteTraits::iter_changed`]
 /// - [`WriteTraits::iter_added`]
+///
+/// Each item also implements [`DetectChanges`], so the ticks of individual impls
+/// can be inspected with methods such as [`DetectChanges::last_changed`].
+///
+/// Components are always iterated in the order they were registered for the trait in,
+/// regardless of whether they are stored in tables or sparse sets.
+/// Use [`TraitRegistryExt::deterministic_trait_queries`](crate::TraitRegistryExt::deterministic_trait_queries)
+/// to make this order independent of registration order.
 pub struct WriteTraits<'a, Trait: ?Sized + TraitQuery> {
     // Read-only access to the global trait registry.
     // Since no one outside of the crate can name the registry type,
     // we can be confident that no write accesses will conflict with this.
     pub(crate) registry: &'a TraitImplRegistry<Trait>,
 
+    pub(crate) entity: Entity,
     pub(crate) table: &'a Table,
     pub(crate) table_row: TableRow,
 
@@ -34,123 +41,143 @@ pub struct WriteTraits<'a, Trait: ?Sized + TraitQuery> {
     /// The fetch impl registers write-access for all of these components,
     /// guaranteeing us exclusive access at runtime.
     pub(crate) sparse_sets: &'a SparseSets,
+    /// If the trait has a single impl which is stored in a table,
+    /// we can skip searching the table for registered components.
+    pub(crate) single_table: bool,
 }
 
+/// Mutable iterator over the components implementing a trait for a given entity,
+/// in the order they were registered in.
 #[doc(hidden)]
-pub type CombinedWriteTraitsIter<'a, Trait> =
-    std::iter::Chain<WriteTableTraitsIter<'a, Trait>, WriteSparseTraitsIter<'a, Trait>>;
-
-#[doc(hidden)]
-pub struct WriteTableTraitsIter<'a, Trait: ?Sized> {
-    // SAFETY: These two iterators must have equal length.
+pub struct WriteTraitsIter<'a, Trait: ?Sized> {
+    // SAFETY: These three iterators must have equal length.
     pub(crate) components: std::slice::Iter<'a, ComponentId>,
     pub(crate) meta: std::slice::Iter<'a, TraitImplMeta<Trait>>,
-    pub(crate) table: &'a Table,
+    pub(crate) storage_types: std::slice::Iter<'a, StorageType>,
     /// SAFETY: Given the same trait type and same archetype,
-    /// no two instances of this struct may have the same `table_row`.
+    /// no two instances of this struct may have the same `entity` or `table_row`.
+    pub(crate) entity: Entity,
     pub(crate) table_row: TableRow,
+    pub(crate) table: &'a Table,
+    pub(crate) sparse_sets: &'a SparseSets,
     pub(crate) last_run: Tick,
     pub(crate) this_run: Tick,
 }
 
-impl<'a, Trait: ?Sized + TraitQuery> Iterator for WriteTableTraitsIter<'a, Trait> {
+impl<'a, Trait: ?Sized + TraitQuery> Iterator for WriteTraitsIter<'a, Trait> {
     type Item = Mut<'a, Trait>;
+    #[inline]
     fn next(&mut self) -> Option<Self::Item> {
-        // Iterate the remaining table components that are registered,
-        // until we find one that exists in the table.
-        let (ptr, component, meta) = unsafe { zip_exact(&mut self.components, &mut self.meta) }
-            .find_map(|(&component, meta)| {
-                // SAFETY: we know that the `table_row` is a valid index.
-                let ptr = unsafe { self.table.get_component(component, self.table_row) }?;
-                Some((ptr, component, meta))
-            })?;
-        // SAFETY: The instance of `WriteTraits` that created this iterator
-        // has exclusive access to all table components registered with the trait.
-        //
-        // Since `self.table_row` is guaranteed to be unique, we know that other instances
-        // of `WriteTableTraitsIter` will not conflict with this pointer.
-        let ptr = unsafe { ptr.assert_unique() };
-        let trait_object = unsafe { meta.dyn_ctor.cast_mut(ptr) };
-        // SAFETY: We have exclusive access to the component, so by extension
-        // we have exclusive access to the corresponding `ComponentTicks`.
-        let added = unsafe {
-            self.table
-                .get_added_tick(component, self.table_row)?
-                .deref_mut()
-        };
-        let changed = unsafe {
-            self.table
-                .get_changed_tick(component, self.table_row)?
-                .deref_mut()
-        };
-        Some(Mut::new(
-            trait_object,
-            added,
-            changed,
-            self.last_run,
-            self.this_run,
-        ))
+        self.next_with_id().map(|(_, item)| item)
     }
 }
 
-#[doc(hidden)]
-pub struct WriteSparseTraitsIter<'a, Trait: ?Sized> {
-    // SAFETY: These two iterators must have equal length.
-    pub(crate) components: std::slice::Iter<'a, ComponentId>,
-    pub(crate) meta: std::slice::Iter<'a, TraitImplMeta<Trait>>,
-    /// SAFETY: Given the same trait type and same archetype,
-    /// no two instances of this struct may have the same `entity`.
-    pub(crate) entity: Entity,
-    pub(crate) sparse_sets: &'a SparseSets,
-    pub(crate) last_run: Tick,
-    pub(crate) this_run: Tick,
-}
-
-impl<'a, Trait: ?Sized + TraitQuery> Iterator for WriteSparseTraitsIter<'a, Trait> {
-    type Item = Mut<'a, Trait>;
-    fn next(&mut self) -> Option<Self::Item> {
-        // Iterate the remaining sparse set components we have registered,
-        // until we find one that exists in the archetype.
-        let (ptr, component_ticks, meta) =
-            unsafe { zip_exact(&mut self.components, &mut self.meta) }.find_map(
-                |(&component, meta)| {
-                    let set = self.sparse_sets.get(component)?;
-                    let (ptr, ticks, _) = set.get_with_ticks(self.entity)?;
-                    Some((ptr, ticks, meta))
-                },
-            )?;
-
+impl<'a, Trait: ?Sized + TraitQuery> WriteTraitsIter<'a, Trait> {
+    /// Returns the next trait object, along with the [`ComponentId`] of its concrete component.
+    pub(crate) fn next_with_id(&mut self) -> Option<(ComponentId, Mut<'a, Trait>)> {
+        crate::paranoid_check_row(self.table.entity_count(), self.table_row);
+        // Iterate the remaining components that are registered,
+        // until we find one that exists on the entity.
+        let (component, ptr, added, changed, meta) = unsafe {
+            zip_exact(
+                zip_exact(&mut self.components, &mut self.meta),
+                &mut self.storage_types,
+            )
+        }
+        .find_map(|((&component, meta), &storage_type)| match storage_type {
+            // SAFETY: we know that the `table_row` is a valid index.
+            StorageType::Table => unsafe {
+                let ptr = self.table.get_component(component, self.table_row)?;
+                let added = self.table.get_added_tick(component, self.table_row)?;
+                let changed = self.table.get_changed_tick(component, self.table_row)?;
+                Some((component, ptr, added, changed, meta))
+            },
+            StorageType::SparseSet => {
+                let set = self.sparse_sets.get(component)?;
+                let (ptr, ticks, _) = set.get_with_ticks(self.entity)?;
+                Some((component, ptr, ticks.added, ticks.changed, meta))
+            }
+        })?;
         // SAFETY: The instance of `WriteTraits` that created this iterator
-        // has exclusive access to all sparse set components registered with the trait.
+        // has exclusive access to all components registered with the trait.
         //
-        // Since `self.entity` is guaranteed to be unique, we know that other instances
-        // of `WriteSparseTraitsIter` will not conflict with this pointer.
+        // Since `self.entity` and `self.table_row` are guaranteed to be unique, we know that
+        // other instances of `WriteTraitsIter` will not conflict with this pointer.
         let ptr = unsafe { ptr.assert_unique() };
         let trait_object = unsafe { meta.dyn_ctor.cast_mut(ptr) };
         // SAFETY: We have exclusive access to the component, so by extension
         // we have exclusive access to the corresponding `ComponentTicks`.
-        let added = unsafe { component_ticks.added.deref_mut() };
-        let changed = unsafe { component_ticks.changed.deref_mut() };
-
-        Some(Mut::new(
-            trait_object,
-            added,
-            changed,
-            self.last_run,
-            self.this_run,
+        let (added, changed) = unsafe { (added.deref_mut(), changed.deref_mut()) };
+        Some((
+            component,
+            Mut::new(trait_object, added, changed, self.last_run, self.this_run),
         ))
     }
 }
 
 impl<Trait: ?Sized + TraitQuery> WriteTraits<'_, Trait> {
+    /// Returns the entity that these trait impls belong to.
+    #[inline]
+    pub fn entity(&self) -> Entity {
+        self.entity
+    }
+
+    /// Returns the number of components implementing `Trait` for the current entity.
+    ///
+    /// This is cheaper than `iter().count()`, since it only checks which components are present.
+    pub fn len(&self) -> usize {
+        present_components(self.registry, self.table, self.sparse_sets, self.entity).count()
+    }
+
+    /// Returns `true` if the current entity has no components implementing `Trait`.
+    pub fn is_empty(&self) -> bool {
+        present_components(self.registry, self.table, self.sparse_sets, self.entity)
+            .next()
+            .is_none()
+    }
+
     /// Returns an iterator over the components implementing `Trait` for the current entity.
-    pub fn iter(&self) -> CombinedReadTraitsIter<'_, Trait> {
-        self.into_iter()
+    ///
+    /// Components are yielded in the order they were registered in with
+    /// [`register_component_as`](crate::RegisterExt::register_component_as),
+    /// regardless of whether they are stored in tables or sparse sets.
+    pub fn iter(&self) -> ReadTraitsIter<'_, Trait> {
+        ReadTraitsIter {
+            components: self.registry.components.iter(),
+            meta: self.registry.meta.iter(),
+            storage_types: self.registry.storage_types.iter(),
+            entity: self.entity,
+            table_row: self.table_row,
+            table: self.table,
+            sparse_sets: self.sparse_sets,
+            single: self.single_table,
+            last_run: self.last_run,
+            this_run: self.this_run,
+        }
     }
 
     /// Returns a mutable iterator over the components implementing `Trait` for the current entity.
-    pub fn iter_mut(&mut self) -> CombinedWriteTraitsIter<'_, Trait> {
-        self.into_iter()
+    ///
+    /// Components are yielded in the same order as [`iter`](Self::iter).
+    pub fn iter_mut(&mut self) -> WriteTraitsIter<'_, Trait> {
+        WriteTraitsIter {
+            components: self.registry.components.iter(),
+            meta: self.registry.meta.iter(),
+            storage_types: self.registry.storage_types.iter(),
+            entity: self.entity,
+            table_row: self.table_row,
+            table: self.table,
+            sparse_sets: self.sparse_sets,
+            last_run: self.last_run,
+            this_run: self.this_run,
+        }
+    }
+
+    /// Returns a mutable iterator over the components implementing `Trait` for the current entity,
+    /// along with the [`ComponentId`] of each concrete component.
+    pub fn iter_mut_with_id(&mut self) -> impl Iterator<Item = (ComponentId, Mut<'_, Trait>)> {
+        let mut iter = self.iter_mut();
+        std::iter::from_fn(move || iter.next_with_id())
     }
 
     /// Returns an iterator over the components implementing `Trait` for the current entity
@@ -176,79 +203,200 @@ impl<Trait: ?Sized + TraitQuery> WriteTraits<'_, Trait> {
     pub fn iter_changed_mut(&mut self) -> impl Iterator<Item = Mut<'_, Trait>> {
         self.iter_mut().filter(DetectChanges::is_changed)
     }
+
+    /// Returns a mutable iterator over the components implementing `Trait` for the current entity
+    /// that were added since the last time the system was run,
+    /// along with the [`ComponentId`] of each concrete component.
+    pub fn iter_added_mut_with_id(
+        &mut self,
+    ) -> impl Iterator<Item = (ComponentId, Mut<'_, Trait>)> {
+        self.iter_mut_with_id().filter(|(_, item)| item.is_added())
+    }
+
+    /// Returns a mutable iterator over the components implementing `Trait` for the current entity
+    /// whose values were changed since the last time the system was run,
+    /// along with the [`ComponentId`] of each concrete component.
+    pub fn iter_changed_mut_with_id(
+        &mut self,
+    ) -> impl Iterator<Item = (ComponentId, Mut<'_, Trait>)> {
+        self.iter_mut_with_id()
+            .filter(|(_, item)| item.is_changed())
+    }
+
+    /// Returns a mutable iterator over the components implementing `Trait` for the current entity
+    /// for which `pred` returns `true`.
+    ///
+    /// The predicate only gets shared access to each component, so components that are
+    /// filtered out will not be flagged as changed.
+    pub fn iter_mut_where<'s>(
+        &'s mut self,
+        mut pred: impl FnMut(&Trait) -> bool + 's,
+    ) -> impl Iterator<Item = Mut<'s, Trait>> + 's {
+        // `Mut` only sets the change tick when it is mutably dereferenced,
+        // so reading through it here does not count as a change.
+        self.iter_mut().filter(move |component| pred(component))
+    }
+
+    /// Returns the first component implementing `Trait` for the current entity,
+    /// in the same order as [`iter_mut`](Self::iter_mut).
+    ///
+    /// This stops at the first registered component that is present on the entity,
+    /// without looking at any of the components registered after it.
+    #[inline]
+    pub fn first_mut(&mut self) -> Option<Mut<'_, Trait>> {
+        self.iter_mut().next()
+    }
+
+    /// Returns the `n`th component implementing `Trait` for the current entity,
+    /// in the same order as [`iter_mut`](Self::iter_mut).
+    #[inline]
+    pub fn nth_mut(&mut self, n: usize) -> Option<Mut<'_, Trait>> {
+        self.iter_mut().nth(n)
+    }
+
+    /// Overwrites the added and changed ticks of every component implementing `Trait`
+    /// for the current entity, such as when restoring a snapshot for rollback netcode.
+    ///
+    /// This bypasses the usual change detection, so the components are not flagged as changed
+    /// by this call, unless `changed` itself is newer than the last run of a system.
+    /// Note that bevy periodically clamps ticks that are too far in the past, so ticks
+    /// older than [`Tick::MAX_CHANGE_AGE`] relative to the current tick may not be preserved.
+    pub fn set_all_ticks(&mut self, added: Tick, changed: Tick) {
+        let impls = self
+            .registry
+            .components
+            .iter()
+            .zip(&self.registry.storage_types);
+        for (&component, storage_type) in impls {
+            // SAFETY: We have exclusive access to the component, so by extension
+            // we have exclusive access to the corresponding `ComponentTicks`.
+            unsafe {
+                let ticks = match storage_type {
+                    StorageType::Table => self
+                        .table
+                        .get_added_tick(component, self.table_row)
+                        .zip(self.table.get_changed_tick(component, self.table_row)),
+                    StorageType::SparseSet => self
+                        .sparse_sets
+                        .get(component)
+                        .and_then(|set| set.get_with_ticks(self.entity))
+                        .map(|(_, ticks, _)| (ticks.added, ticks.changed)),
+                };
+                let Some((added_tick, changed_tick)) = ticks else {
+                    continue;
+                };
+                *added_tick.deref_mut() = added;
+                *changed_tick.deref_mut() = changed;
+            }
+        }
+    }
+
+    /// Returns an iterator over the components implementing `Trait` for the current entity
+    /// as type-erased [`MutUntyped`] references, along with the [`ComponentId`] of each.
+    ///
+    /// This allows generic tools such as editors to operate on the raw components
+    /// located by the trait query. Change detection works the same as for [`Mut`].
+    /// Components registered with
+    /// [`register_component_as_dynamic`](crate::TraitRegistryExt::register_component_as_dynamic)
+    /// are not included, since their concrete type is not known.
+    pub fn iter_untyped_mut(&mut self) -> impl Iterator<Item = (ComponentId, MutUntyped<'_>)> {
+        let Self {
+            registry,
+            entity,
+            table,
+            table_row,
+            last_run,
+            this_run,
+            sparse_sets,
+            ..
+        } = *self;
+        // SAFETY: Each component is registered along with its storage type and constructor.
+        let impls = unsafe {
+            zip_exact(
+                zip_exact(&registry.components, &registry.storage_types),
+                &registry.untyped_ctors,
+            )
+        };
+        impls.filter_map(move |((&component, &storage_type), &untyped_ctor)| {
+            let untyped_ctor = untyped_ctor?;
+            let (ptr, added, changed) = match storage_type {
+                // SAFETY: We know that `table_row` is a valid index.
+                StorageType::Table => unsafe {
+                    (
+                        table.get_component(component, table_row)?,
+                        table.get_added_tick(component, table_row)?,
+                        table.get_changed_tick(component, table_row)?,
+                    )
+                },
+                StorageType::SparseSet => {
+                    let (ptr, ticks, _) = sparse_sets.get(component)?.get_with_ticks(entity)?;
+                    (ptr, ticks.added, ticks.changed)
+                }
+            };
+            // SAFETY: We have exclusive access to all components registered with the trait,
+            // and by extension to their ticks. `untyped_ctor` was registered for the type
+            // of this component.
+            unsafe {
+                Some((
+                    component,
+                    untyped_ctor(
+                        ptr.assert_unique(),
+                        added.deref_mut(),
+                        changed.deref_mut(),
+                        last_run,
+                        this_run,
+                    ),
+                ))
+            }
+        })
+    }
+
+    /// Splits the components implementing `Trait` for the current entity into two groups,
+    /// those for which `pred` returns `true` and those for which it returns `false`.
+    ///
+    /// As with [`iter_mut_where`](Self::iter_mut_where), the predicate only gets shared access,
+    /// so components will not be flagged as changed unless they are mutated afterwards.
+    pub fn partition_mut(
+        &mut self,
+        mut pred: impl FnMut(&Trait) -> bool,
+    ) -> (Vec<Mut<'_, Trait>>, Vec<Mut<'_, Trait>>) {
+        self.iter_mut().partition(|component| pred(component))
+    }
 }
 
 impl<'w, Trait: ?Sized + TraitQuery> IntoIterator for WriteTraits<'w, Trait> {
     type Item = Mut<'w, Trait>;
-    type IntoIter = CombinedWriteTraitsIter<'w, Trait>;
+    type IntoIter = WriteTraitsIter<'w, Trait>;
     #[inline]
     fn into_iter(self) -> Self::IntoIter {
-        let table = WriteTableTraitsIter {
-            components: self.registry.table_components.iter(),
-            meta: self.registry.table_meta.iter(),
-            table: self.table,
+        WriteTraitsIter {
+            components: self.registry.components.iter(),
+            meta: self.registry.meta.iter(),
+            storage_types: self.registry.storage_types.iter(),
+            entity: self.entity,
             table_row: self.table_row,
-            last_run: self.last_run,
-            this_run: self.this_run,
-        };
-        let sparse = WriteSparseTraitsIter {
-            components: self.registry.sparse_components.iter(),
-            meta: self.registry.sparse_meta.iter(),
-            entity: self.table.entities()[self.table_row.as_usize()],
+            table: self.table,
             sparse_sets: self.sparse_sets,
             last_run: self.last_run,
             this_run: self.this_run,
-        };
-        table.chain(sparse)
+        }
     }
 }
 
 impl<'local, Trait: ?Sized + TraitQuery> IntoIterator for &'local WriteTraits<'_, Trait> {
     type Item = Ref<'local, Trait>;
-    type IntoIter = CombinedReadTraitsIter<'local, Trait>;
+    type IntoIter = ReadTraitsIter<'local, Trait>;
     #[inline]
     fn into_iter(self) -> Self::IntoIter {
-        let table = ReadTableTraitsIter {
-            components: self.registry.table_components.iter(),
-            meta: self.registry.table_meta.iter(),
-            table: self.table,
-            table_row: self.table_row,
-            last_run: self.last_run,
-            this_run: self.this_run,
-        };
-        let sparse = ReadSparseTraitsIter {
-            components: self.registry.sparse_components.iter(),
-            meta: self.registry.sparse_meta.iter(),
-            entity: self.table.entities()[self.table_row.as_usize()],
-            sparse_sets: self.sparse_sets,
-            last_run: self.last_run,
-            this_run: self.this_run,
-        };
-        table.chain(sparse)
+        self.iter()
     }
 }
 
 impl<'local, Trait: ?Sized + TraitQuery> IntoIterator for &'local mut WriteTraits<'_, Trait> {
     type Item = Mut<'local, Trait>;
-    type IntoIter = CombinedWriteTraitsIter<'local, Trait>;
+    type IntoIter = WriteTraitsIter<'local, Trait>;
     #[inline]
     fn into_iter(self) -> Self::IntoIter {
-        let table = WriteTableTraitsIter {
-            components: self.registry.table_components.iter(),
-            meta: self.registry.table_meta.iter(),
-            table: self.table,
-            table_row: self.table_row,
-            last_run: self.last_run,
-            this_run: self.this_run,
-        };
-        let sparse = WriteSparseTraitsIter {
-            components: self.registry.sparse_components.iter(),
-            meta: self.registry.sparse_meta.iter(),
-            entity: self.table.entities()[self.table_row.as_usize()],
-            sparse_sets: self.sparse_sets,
-            last_run: self.last_run,
-            this_run: self.this_run,
-        };
-        table.chain(sparse)
+        self.iter_mut()
     }
 }
@@ -24,6 +24,48 @@ use crate::{
 ///
 /// - `Query<&dyn Trait>` yields a [`ReadTraits`] object
 /// - `Query<&mut dyn Trait>` yields a [`WriteTraits`] object
+///
+/// As with `Option<&C>` for a concrete component, `Option<&dyn Trait>` matches every entity,
+/// yielding `Some` for entities with at least one impl of the trait, and `None` for the rest.
+///
+/// # Parallel iteration
+///
+/// Trait queries can be iterated in parallel using `Query::par_iter`.
+/// Since trait queries are not dense, entities are split into batches per archetype, and the
+/// default batch size is based on the size of the largest matched archetype. The cost of each
+/// entity grows with the number of impls it has, so when a few archetypes contain entities with
+/// many impls, a smaller fixed batch size may distribute the work more evenly:
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy::ecs::query::BatchingStrategy;
+/// # use bevy_trait_query::*;
+/// #
+/// # #[bevy_trait_query::queryable]
+/// # pub trait Tooltip {
+/// #     fn tooltip(&self) -> &str;
+/// # }
+/// #
+/// fn check_tooltips(tooltips: Query<&dyn Tooltip>) {
+///     tooltips
+///         .par_iter()
+///         .batching_strategy(BatchingStrategy::fixed(64))
+///         .for_each(|tooltips| {
+///             for tooltip in tooltips {
+///                 assert!(!tooltip.tooltip().is_empty());
+///             }
+///         });
+/// }
+/// # bevy_ecs::system::assert_is_system(check_tooltips);
+/// ```
+///
+/// # Unchecked access
+///
+/// Trait queries can be used with unchecked methods such as `Query::iter_unchecked`
+/// and `Query::get_unchecked`. Each [`WriteTraits`] grants mutable access to every
+/// registered component of its entity, so the usual rules apply to all of them at once:
+/// the caller must not hold two [`WriteTraits`] for the same entity at the same time,
+/// nor a [`WriteTraits`] alongside any other reference to one of that entity's trait impls.
 pub struct All<T: ?Sized>(T);
 
 unsafe impl<Trait: ?Sized + TraitQuery> QueryData for All<&Trait> {
@@ -47,7 +89,7 @@ unsafe impl<Trait: ?Sized + TraitQuery> WorldQuery for All<&Trait> {
     #[inline]
     unsafe fn init_fetch<'w>(
         world: UnsafeWorldCell<'w>,
-        _state: &Self::State,
+        state: &Self::State,
         last_run: Tick,
         this_run: Tick,
     ) -> Self::Fetch<'w> {
@@ -57,6 +99,7 @@ unsafe impl<Trait: ?Sized + TraitQuery> WorldQuery for All<&Trait> {
                 .unwrap_or_else(|| trait_registry_error()),
             table: None,
             sparse_sets: &world.storages().sparse_sets,
+            single_table: state.single_table,
             last_run,
             this_run,
         }
@@ -71,6 +114,8 @@ unsafe impl<Trait: ?Sized + TraitQuery> WorldQuery for All<&Trait> {
         _archetype: &'w bevy_ecs::archetype::Archetype,
         table: &'w bevy_ecs::storage::Table,
     ) {
+        // Setting up an archetype is O(1), and the impls of an entity are only looked up
+        // once its item is iterated, so `query.iter().flatten().take(n)` stops early.
         fetch.table = Some(table);
     }
 
@@ -85,16 +130,18 @@ unsafe impl<Trait: ?Sized + TraitQuery> WorldQuery for All<&Trait> {
     #[inline]
     unsafe fn fetch<'w>(
         fetch: &mut Self::Fetch<'w>,
-        _entity: Entity,
+        entity: Entity,
         table_row: TableRow,
     ) -> Self::Item<'w> {
         let table = fetch.table.unwrap_or_else(|| debug_unreachable());
 
         ReadTraits {
             registry: fetch.registry,
+            entity,
             table,
             table_row,
             sparse_sets: fetch.sparse_sets,
+            single_table: fetch.single_table,
             last_run: fetch.last_run,
             this_run: fetch.this_run,
         }
@@ -172,7 +219,7 @@ unsafe impl<Trait: ?Sized + TraitQuery> WorldQuery for All<&mut Trait> {
     #[inline]
     unsafe fn init_fetch<'w>(
         world: UnsafeWorldCell<'w>,
-        _state: &Self::State,
+        state: &Self::State,
         last_run: Tick,
         this_run: Tick,
     ) -> Self::Fetch<'w> {
@@ -182,6 +229,7 @@ unsafe impl<Trait: ?Sized + TraitQuery> WorldQuery for All<&mut Trait> {
                 .unwrap_or_else(|| trait_registry_error()),
             table: None,
             sparse_sets: &world.storages().sparse_sets,
+            single_table: state.single_table,
             last_run,
             this_run,
         }
@@ -211,16 +259,18 @@ unsafe impl<Trait: ?Sized + TraitQuery> WorldQuery for All<&mut Trait> {
     #[inline]
     unsafe fn fetch<'w>(
         fetch: &mut Self::Fetch<'w>,
-        _entity: Entity,
+        entity: Entity,
         table_row: TableRow,
     ) -> Self::Item<'w> {
         let table = fetch.table.unwrap_or_else(|| debug_unreachable());
 
         WriteTraits {
             registry: fetch.registry,
+            entity,
             table,
             table_row,
             sparse_sets: fetch.sparse_sets,
+            single_table: fetch.single_table,
             last_run: fetch.last_run,
             this_run: fetch.this_run,
         }
@@ -235,7 +285,7 @@ unsafe impl<Trait: ?Sized + TraitQuery> WorldQuery for All<&mut Trait> {
         let mut new_access = access.clone();
         for &component in &*state.components {
             assert!(
-                !access.access().has_component_write(component),
+                !access.access().has_component_read(component),
                 "&mut {} conflicts with a previous access in this query. Mutable component access must be unique.",
                 std::any::type_name::<Trait>(),
             );
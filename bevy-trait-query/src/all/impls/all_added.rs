@@ -0,0 +1,155 @@
+use std::marker::PhantomData;
+
+use bevy_ecs::{
+    archetype::Archetype,
+    component::{ComponentId, Components, Tick},
+    entity::Entity,
+    query::{FilteredAccess, QueryData, QueryFilter, ReadOnlyQueryData, WorldQuery},
+    storage::{ComponentSparseSet, Table, TableRow},
+    world::{unsafe_world_cell::UnsafeWorldCell, World},
+};
+
+use crate::{trait_registry_error, All, AllTraitsFetch, TraitQuery, TraitQueryState};
+
+/// [`WorldQuery`] filter for entities with at least one component implementing a trait
+/// that has been added since the last time the system ran.
+///
+/// Unlike calling [`ReadTraits::iter_added`](crate::ReadTraits::iter_added) on every entity,
+/// entities without any newly added impls are skipped by the query itself.
+/// Archetypes without any impls of the trait are never visited.
+///
+/// This is a filter rather than query data, since query data cannot skip entities.
+/// To get at the added impls themselves, pair it with `&dyn Trait` and call
+/// [`ReadTraits::iter_added`](crate::ReadTraits::iter_added) on each item, as below.
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_trait_query::*;
+/// #
+/// # #[bevy_trait_query::queryable]
+/// # pub trait Tooltip {
+/// #     fn tooltip(&self) -> &str;
+/// # }
+/// #
+/// fn log_new_tooltips(tooltips: Query<&dyn Tooltip, AllAdded<dyn Tooltip>>) {
+///     for tooltips in &tooltips {
+///         for tooltip in tooltips.iter_added() {
+///             println!("New tooltip: {}", tooltip.tooltip());
+///         }
+///     }
+/// }
+/// # bevy_ecs::system::assert_is_system(log_new_tooltips);
+/// ```
+pub struct AllAdded<Trait: ?Sized + TraitQuery> {
+    marker: PhantomData<&'static Trait>,
+}
+
+// SAFETY: We only access the components registered in the trait registry.
+// This is known to match the set of components in the TraitQueryState,
+// which is used to match archetypes and register world access.
+unsafe impl<Trait: ?Sized + TraitQuery> WorldQuery for AllAdded<Trait> {
+    type Item<'w> = bool;
+    type Fetch<'w> = AllTraitsFetch<'w, Trait>;
+    type State = TraitQueryState<Trait>;
+
+    #[inline]
+    fn shrink<'wlong: 'wshort, 'wshort>(item: Self::Item<'wlong>) -> Self::Item<'wshort> {
+        item
+    }
+
+    #[inline]
+    unsafe fn init_fetch<'w>(
+        world: UnsafeWorldCell<'w>,
+        state: &Self::State,
+        last_run: Tick,
+        this_run: Tick,
+    ) -> Self::Fetch<'w> {
+        AllTraitsFetch {
+            registry: world
+                .get_resource()
+                .unwrap_or_else(|| trait_registry_error()),
+            table: None,
+            sparse_sets: &world.storages().sparse_sets,
+            single_table: state.single_table,
+            last_run,
+            this_run,
+        }
+    }
+
+    const IS_DENSE: bool = false;
+
+    #[inline]
+    unsafe fn set_archetype<'w>(
+        fetch: &mut Self::Fetch<'w>,
+        _state: &Self::State,
+        _archetype: &'w Archetype,
+        table: &'w Table,
+    ) {
+        fetch.table = Some(table);
+    }
+
+    #[inline]
+    unsafe fn set_table<'w>(fetch: &mut Self::Fetch<'w>, _state: &Self::State, table: &'w Table) {
+        fetch.table = Some(table);
+    }
+
+    #[inline]
+    unsafe fn fetch<'w>(
+        fetch: &mut Self::Fetch<'w>,
+        entity: Entity,
+        table_row: TableRow,
+    ) -> Self::Item<'w> {
+        fetch.any_newer(
+            entity,
+            table_row,
+            Table::get_added_tick,
+            ComponentSparseSet::get_added_tick,
+        )
+    }
+
+    #[inline]
+    fn update_component_access(state: &Self::State, access: &mut FilteredAccess<ComponentId>) {
+        // Reads the ticks of the same components as `&dyn Trait`.
+        <All<&Trait> as WorldQuery>::update_component_access(state, access);
+    }
+
+    #[inline]
+    fn init_state(world: &mut World) -> Self::State {
+        TraitQueryState::init(world)
+    }
+
+    #[inline]
+    fn get_state(_: &Components) -> Option<Self::State> {
+        // TODO: fix this https://github.com/bevyengine/bevy/issues/13798
+        panic!("transmuting and any other operations concerning the state of a query are currently broken and shouldn't be used. See https://github.com/JoJoJet/bevy-trait-query/issues/59");
+    }
+
+    #[inline]
+    fn matches_component_set(
+        state: &Self::State,
+        set_contains_id: &impl Fn(ComponentId) -> bool,
+    ) -> bool {
+        state.matches_component_set_any(set_contains_id)
+    }
+
+    #[inline]
+    fn shrink_fetch<'wlong: 'wshort, 'wshort>(fetch: Self::Fetch<'wlong>) -> Self::Fetch<'wshort> {
+        fetch
+    }
+}
+
+unsafe impl<Trait: ?Sized + TraitQuery> QueryData for AllAdded<Trait> {
+    type ReadOnly = Self;
+}
+/// SAFETY: read-only access
+unsafe impl<Trait: ?Sized + TraitQuery> ReadOnlyQueryData for AllAdded<Trait> {}
+unsafe impl<Trait: ?Sized + TraitQuery> QueryFilter for AllAdded<Trait> {
+    const IS_ARCHETYPAL: bool = false;
+    unsafe fn filter_fetch(
+        fetch: &mut Self::Fetch<'_>,
+        entity: Entity,
+        table_row: TableRow,
+    ) -> bool {
+        <Self as WorldQuery>::fetch(fetch, entity, table_row)
+    }
+}
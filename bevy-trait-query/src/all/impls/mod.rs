@@ -1,3 +1,7 @@
 mod all;
+mod all_added;
+mod all_changed;
 
 pub use all::All;
+pub use all_added::AllAdded;
+pub use all_changed::AllChanged;
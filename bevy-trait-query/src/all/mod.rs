@@ -1,5 +1,5 @@
 mod core;
 mod impls;
 
-pub use core::{fetch::AllTraitsFetch, read::*, write::*};
+pub use core::{batch::*, fetch::AllTraitsFetch, read::*, view::*, write::*};
 pub use impls::*;
@@ -0,0 +1,150 @@
+use std::{alloc::Layout, ptr::NonNull};
+
+use bevy_ecs::{
+    component::{Component, ComponentId},
+    entity::Entity,
+    ptr::OwningPtr,
+    system::{Commands, EntityCommands},
+    world::World,
+};
+
+use crate::{RegisterExt, TraitImplRegistry, TraitQuery, TraitQueryMarker};
+
+/// Extension methods for registering trait impls through [`Commands`].
+pub trait RegisterCommandsExt {
+    /// Registers `C` as an impl of `Trait` the next time commands are applied,
+    /// as with [`RegisterExt::register_component_as`].
+    ///
+    /// Registration is only possible until a trait query for `Trait` first runs,
+    /// which seals the registry. If the registry has already been sealed when the
+    /// command is applied, an error is logged and the component is not registered.
+    fn register_trait_impl<Trait: ?Sized + TraitQuery, C: Component>(&mut self) -> &mut Self
+    where
+        (C,): TraitQueryMarker<Trait, Covered = C>;
+}
+
+impl RegisterCommandsExt for Commands<'_, '_> {
+    fn register_trait_impl<Trait: ?Sized + TraitQuery, C: Component>(&mut self) -> &mut Self
+    where
+        (C,): TraitQueryMarker<Trait, Covered = C>,
+    {
+        self.queue(|world: &mut World| {
+            let sealed = world
+                .get_resource::<TraitImplRegistry<Trait>>()
+                .is_some_and(|registry| registry.sealed);
+            if sealed {
+                tracing::error!(
+                    "Cannot register `{}` as an impl of `{}`: trait queries for it have already \
+                     been initialized. Register it before the first system using the trait runs.",
+                    std::any::type_name::<C>(),
+                    std::any::type_name::<Trait>(),
+                );
+                return;
+            }
+            world.register_component_as::<Trait, C>();
+        });
+        self
+    }
+}
+
+/// Extension methods for inserting trait impls through [`EntityCommands`].
+pub trait TraitCommandsExt {
+    /// Inserts the component with the given [`ComponentId`], reading its value from `bytes`.
+    ///
+    /// This allows a component implementing `Trait` to be chosen at runtime,
+    /// such as from a scripting layer, without naming its type.
+    ///
+    /// # Panics
+    /// When the command is applied, if `component_id` has not been registered for `Trait`,
+    /// or if the length of `bytes` does not match the size of the component.
+    ///
+    /// # Safety
+    /// `bytes` must be a valid, initialized value of the component identified by `component_id`.
+    /// Ownership of the value is transferred to the world, so it will be dropped along with the component.
+    unsafe fn insert_trait_impl_dynamic<Trait: ?Sized + TraitQuery>(
+        &mut self,
+        component_id: ComponentId,
+        bytes: Vec<u8>,
+    ) -> &mut Self;
+}
+
+impl TraitCommandsExt for EntityCommands<'_> {
+    unsafe fn insert_trait_impl_dynamic<Trait: ?Sized + TraitQuery>(
+        &mut self,
+        component_id: ComponentId,
+        bytes: Vec<u8>,
+    ) -> &mut Self {
+        self.queue(move |entity: Entity, world: &mut World| {
+            // SAFETY: The caller promises that `bytes` is a valid value of the component.
+            unsafe { insert_dynamic::<Trait>(world, entity, component_id, &bytes) };
+        })
+    }
+}
+
+/// # Safety
+/// `bytes` must be a valid value of the component identified by `component_id`.
+unsafe fn insert_dynamic<Trait: ?Sized + TraitQuery>(
+    world: &mut World,
+    entity: Entity,
+    component_id: ComponentId,
+    bytes: &[u8],
+) {
+    let registered = world
+        .get_resource::<TraitImplRegistry<Trait>>()
+        .is_some_and(|registry| registry.components.contains(&component_id));
+    assert!(
+        registered,
+        "{component_id:?} is not registered as an impl of `{}`",
+        std::any::type_name::<Trait>(),
+    );
+    let layout = world
+        .components()
+        .get_info(component_id)
+        .expect("registered components must exist in the world")
+        .layout();
+    assert_eq!(
+        bytes.len(),
+        layout.size(),
+        "the value for {component_id:?} has the wrong size",
+    );
+
+    // `bytes` is not guaranteed to be aligned for the component,
+    // so copy it into a buffer with the correct layout.
+    let buffer = AlignedBuffer::new(layout);
+    std::ptr::copy_nonoverlapping(bytes.as_ptr(), buffer.ptr.as_ptr(), bytes.len());
+    // SAFETY: The buffer is aligned for the component and holds a valid value of it.
+    // The value is moved out of the buffer, which is deallocated without dropping its contents.
+    world
+        .entity_mut(entity)
+        .insert_by_id(component_id, OwningPtr::new(buffer.ptr));
+}
+
+/// An uninitialized allocation with a given layout, which is freed on drop.
+struct AlignedBuffer {
+    ptr: NonNull<u8>,
+    layout: Layout,
+}
+
+impl AlignedBuffer {
+    fn new(layout: Layout) -> Self {
+        if layout.size() == 0 {
+            // Zero-sized values only need a well-aligned, non-null pointer.
+            // SAFETY: Alignments are always non-zero.
+            let ptr = unsafe { NonNull::new_unchecked(layout.align() as *mut u8) };
+            return Self { ptr, layout };
+        }
+        // SAFETY: The layout has a non-zero size.
+        let ptr = unsafe { std::alloc::alloc(layout) };
+        let ptr = NonNull::new(ptr).unwrap_or_else(|| std::alloc::handle_alloc_error(layout));
+        Self { ptr, layout }
+    }
+}
+
+impl Drop for AlignedBuffer {
+    fn drop(&mut self) {
+        if self.layout.size() != 0 {
+            // SAFETY: The pointer was allocated with this layout in `AlignedBuffer::new`.
+            unsafe { std::alloc::dealloc(self.ptr.as_ptr(), self.layout) };
+        }
+    }
+}
@@ -0,0 +1,28 @@
+use bevy_ecs::system::Query;
+
+use crate::{TraitQuery, WithAny};
+
+/// A run condition which returns `true` if any entity has a component implementing `Trait`.
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_trait_query::*;
+/// #
+/// # #[bevy_trait_query::queryable]
+/// # pub trait DeadFlag {}
+/// #
+/// # fn clean_up_dead() {}
+/// #
+/// # let mut app = App::new();
+/// app.add_systems(Update, clean_up_dead.run_if(any_with_trait::<dyn DeadFlag>()));
+/// ```
+pub fn any_with_trait<Trait: ?Sized + TraitQuery>(
+) -> impl FnMut(Query<(), WithAny<Trait>>) -> bool + Clone {
+    |query: Query<(), WithAny<Trait>>| !query.is_empty()
+}
+
+/// A run condition which returns `true` if no entities have a component implementing `Trait`.
+pub fn no_entity_with_trait<Trait: ?Sized + TraitQuery>(
+) -> impl FnMut(Query<(), WithAny<Trait>>) -> bool + Clone {
+    |query: Query<(), WithAny<Trait>>| query.is_empty()
+}
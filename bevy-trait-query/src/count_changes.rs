@@ -0,0 +1,186 @@
+use std::marker::PhantomData;
+
+use bevy_ecs::{
+    archetype::{ArchetypeGeneration, ArchetypeId, Archetypes},
+    component::{ComponentId, Tick},
+    entity::{Entity, EntityHashMap},
+    system::{ReadOnlySystemParam, SystemMeta, SystemParam},
+    world::{unsafe_world_cell::UnsafeWorldCell, World},
+};
+
+use crate::{TraitQuery, TraitQueryState};
+
+/// [`SystemParam`] for reacting to entities gaining or losing components implementing `Trait`.
+///
+/// Each time the system runs, this yields every entity whose number of impls of `Trait`
+/// changed since the previous run, such as when a component implementing `Trait` is inserted
+/// or removed, or when the entity is spawned or despawned.
+/// Unlike change detection, this does not report changes to the values of the components.
+///
+/// The number of impls is tracked for every matching entity, so each run visits all of them.
+/// On the first run, every entity with at least one impl is reported.
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_trait_query::*;
+/// #
+/// # #[bevy_trait_query::queryable]
+/// # pub trait Buff {}
+/// #
+/// fn log_buffs(changes: TraitCountChanges<dyn Buff>) {
+///     for change in &changes {
+///         if change.delta() > 0 {
+///             println!("{} gained {} buffs", change.entity, change.delta());
+///         } else {
+///             println!("{} lost {} buffs", change.entity, -change.delta());
+///         }
+///     }
+/// }
+/// # bevy_ecs::system::assert_is_system(log_buffs);
+/// ```
+pub struct TraitCountChanges<'s, Trait: ?Sized + TraitQuery> {
+    changes: &'s [TraitCountChange],
+    marker: PhantomData<fn() -> Box<Trait>>,
+}
+
+/// A change in the number of components implementing a trait for an entity,
+/// as yielded by [`TraitCountChanges`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TraitCountChange {
+    /// The entity whose impls changed.
+    pub entity: Entity,
+    /// The number of impls the entity had the last time the system ran.
+    pub previous: usize,
+    /// The number of impls the entity has now. This is zero if the entity was despawned.
+    pub current: usize,
+}
+
+impl TraitCountChange {
+    /// The number of impls gained by the entity, or a negative number if it lost impls.
+    pub fn delta(&self) -> isize {
+        self.current as isize - self.previous as isize
+    }
+}
+
+#[doc(hidden)]
+pub struct TraitCountState<Trait: ?Sized> {
+    components: Box<[ComponentId]>,
+    // The archetypes that have been scanned so far.
+    generation: ArchetypeGeneration,
+    // The scanned archetypes which contain at least one impl of the trait,
+    // along with the number of impls in each.
+    matched: Vec<(ArchetypeId, usize)>,
+    // The number of impls of each entity as of the last run.
+    counts: EntityHashMap<usize>,
+    changes: Vec<TraitCountChange>,
+    marker: PhantomData<fn() -> Box<Trait>>,
+}
+
+impl<Trait: ?Sized> TraitCountState<Trait> {
+    fn update(&mut self, archetypes: &Archetypes) {
+        if self.generation != archetypes.generation() {
+            for archetype in &archetypes[self.generation..] {
+                let count = self
+                    .components
+                    .iter()
+                    .filter(|&&component| archetype.contains(component))
+                    .count();
+                if count > 0 {
+                    self.matched.push((archetype.id(), count));
+                }
+            }
+            self.generation = archetypes.generation();
+        }
+
+        let mut counts = EntityHashMap::default();
+        for &(id, count) in &self.matched {
+            for entity in archetypes[id].entities() {
+                counts.insert(entity.id(), count);
+            }
+        }
+
+        self.changes.clear();
+        for (&entity, &current) in &counts {
+            let previous = self.counts.get(&entity).copied().unwrap_or(0);
+            if previous != current {
+                self.changes.push(TraitCountChange {
+                    entity,
+                    previous,
+                    current,
+                });
+            }
+        }
+        for (&entity, &previous) in &self.counts {
+            if !counts.contains_key(&entity) {
+                self.changes.push(TraitCountChange {
+                    entity,
+                    previous,
+                    current: 0,
+                });
+            }
+        }
+        self.changes.sort_unstable_by_key(|change| change.entity);
+        self.counts = counts;
+    }
+}
+
+// SAFETY: This only reads archetype metadata, which does not require registering any access.
+unsafe impl<Trait: ?Sized + TraitQuery> SystemParam for TraitCountChanges<'_, Trait> {
+    type State = TraitCountState<Trait>;
+    type Item<'w, 's> = TraitCountChanges<'s, Trait>;
+
+    fn init_state(world: &mut World, _system_meta: &mut SystemMeta) -> Self::State {
+        TraitCountState {
+            components: TraitQueryState::<Trait>::init(world).components().into(),
+            generation: ArchetypeGeneration::initial(),
+            matched: Vec::new(),
+            counts: EntityHashMap::default(),
+            changes: Vec::new(),
+            marker: PhantomData,
+        }
+    }
+
+    #[inline]
+    unsafe fn get_param<'w, 's>(
+        state: &'s mut Self::State,
+        _system_meta: &SystemMeta,
+        world: UnsafeWorldCell<'w>,
+        _change_tick: Tick,
+    ) -> Self::Item<'w, 's> {
+        state.update(world.archetypes());
+        TraitCountChanges {
+            changes: &state.changes,
+            marker: PhantomData,
+        }
+    }
+}
+
+// SAFETY: This only reads archetype metadata.
+unsafe impl<Trait: ?Sized + TraitQuery> ReadOnlySystemParam for TraitCountChanges<'_, Trait> {}
+
+impl<'s, Trait: ?Sized + TraitQuery> TraitCountChanges<'s, Trait> {
+    /// Returns an iterator over the entities whose number of impls changed since the last run,
+    /// in ascending order of [`Entity`].
+    pub fn iter(&self) -> std::slice::Iter<'s, TraitCountChange> {
+        self.changes.iter()
+    }
+
+    /// Returns the number of entities whose number of impls changed since the last run.
+    pub fn len(&self) -> usize {
+        self.changes.len()
+    }
+
+    /// Returns `true` if no entity's number of impls changed since the last run.
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+impl<'s, Trait: ?Sized + TraitQuery> IntoIterator for &TraitCountChanges<'s, Trait> {
+    type Item = &'s TraitCountChange;
+    type IntoIter = std::slice::Iter<'s, TraitCountChange>;
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
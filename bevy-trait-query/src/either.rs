@@ -0,0 +1,242 @@
+use std::marker::PhantomData;
+
+use bevy_ecs::{
+    change_detection::Ref,
+    component::{ComponentId, Components, Tick},
+    entity::Entity,
+    query::{FilteredAccess, QueryData, QueryItem, ReadOnlyQueryData, WorldQuery},
+    storage::TableRow,
+    world::{unsafe_world_cell::UnsafeWorldCell, World},
+};
+
+use crate::{All, AllTraitsFetch, ReadTraits, ReadTraitsIter, TraitQuery, TraitQueryState};
+
+/// An impl of one of two traits, yielded by [`EitherTraits`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Either<L, R> {
+    /// An impl of the first trait.
+    Left(L),
+    /// An impl of the second trait.
+    Right(R),
+}
+
+/// [`WorldQuery`] adapter that fetches all implementations of either of two traits for an entity.
+///
+/// This matches every entity with at least one component implementing `A` or `B`,
+/// and yields [`EitherTraits`], which iterates over the impls of both traits.
+/// A component implementing both traits is yielded once for each of them.
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_trait_query::*;
+/// #
+/// #[bevy_trait_query::queryable]
+/// pub trait Damageable {
+///     fn health(&self) -> f32;
+/// }
+///
+/// #[bevy_trait_query::queryable]
+/// pub trait Healable {
+///     fn max_healing(&self) -> f32;
+/// }
+///
+/// fn print_status(query: Query<EitherTrait<dyn Damageable, dyn Healable>>) {
+///     for traits in &query {
+///         for item in &traits {
+///             match item {
+///                 Either::Left(damageable) => println!("Health: {}", damageable.health()),
+///                 Either::Right(healable) => println!("Healing: {}", healable.max_healing()),
+///             }
+///         }
+///     }
+/// }
+/// # bevy_ecs::system::assert_is_system(print_status);
+/// ```
+pub struct EitherTrait<A: ?Sized + TraitQuery, B: ?Sized + TraitQuery>(
+    PhantomData<(&'static A, &'static B)>,
+);
+
+/// The impls of either of two traits for a given entity, fetched by [`EitherTrait`].
+pub struct EitherTraits<'w, A: ?Sized + TraitQuery, B: ?Sized + TraitQuery> {
+    left: ReadTraits<'w, A>,
+    right: ReadTraits<'w, B>,
+}
+
+impl<'w, A: ?Sized + TraitQuery, B: ?Sized + TraitQuery> EitherTraits<'w, A, B> {
+    /// Returns the entity that these trait impls belong to.
+    #[inline]
+    pub fn entity(&self) -> Entity {
+        self.left.entity()
+    }
+
+    /// Returns the impls of the first trait.
+    #[inline]
+    pub fn left(&self) -> &ReadTraits<'w, A> {
+        &self.left
+    }
+
+    /// Returns the impls of the second trait.
+    #[inline]
+    pub fn right(&self) -> &ReadTraits<'w, B> {
+        &self.right
+    }
+
+    /// Returns an iterator over the impls of both traits for the current entity,
+    /// starting with the impls of the first trait.
+    pub fn iter(&self) -> EitherTraitsIter<'w, A, B> {
+        EitherTraitsIter {
+            left: self.left.iter(),
+            right: self.right.iter(),
+        }
+    }
+}
+
+impl<'w, A: ?Sized + TraitQuery, B: ?Sized + TraitQuery> IntoIterator for EitherTraits<'w, A, B> {
+    type Item = Either<Ref<'w, A>, Ref<'w, B>>;
+    type IntoIter = EitherTraitsIter<'w, A, B>;
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'w, A: ?Sized + TraitQuery, B: ?Sized + TraitQuery> IntoIterator for &EitherTraits<'w, A, B> {
+    type Item = Either<Ref<'w, A>, Ref<'w, B>>;
+    type IntoIter = EitherTraitsIter<'w, A, B>;
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[doc(hidden)]
+pub struct EitherTraitsIter<'w, A: ?Sized + TraitQuery, B: ?Sized + TraitQuery> {
+    left: ReadTraitsIter<'w, A>,
+    right: ReadTraitsIter<'w, B>,
+}
+
+impl<'w, A: ?Sized + TraitQuery, B: ?Sized + TraitQuery> Iterator for EitherTraitsIter<'w, A, B> {
+    type Item = Either<Ref<'w, A>, Ref<'w, B>>;
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(item) = self.left.next() {
+            return Some(Either::Left(item));
+        }
+        self.right.next().map(Either::Right)
+    }
+}
+
+#[doc(hidden)]
+pub struct EitherState<A: ?Sized, B: ?Sized> {
+    left: TraitQueryState<A>,
+    right: TraitQueryState<B>,
+}
+
+unsafe impl<A: ?Sized + TraitQuery, B: ?Sized + TraitQuery> QueryData for EitherTrait<A, B> {
+    type ReadOnly = Self;
+}
+unsafe impl<A: ?Sized + TraitQuery, B: ?Sized + TraitQuery> ReadOnlyQueryData
+    for EitherTrait<A, B>
+{
+}
+
+// SAFETY: Fetching is delegated to `All<&A>` and `All<&B>`, and the access of both is registered.
+// Since neither of them requires the other to match, their access is combined with `OR`,
+// like bevy's `AnyOf`.
+unsafe impl<A: ?Sized + TraitQuery, B: ?Sized + TraitQuery> WorldQuery for EitherTrait<A, B> {
+    type Item<'w> = EitherTraits<'w, A, B>;
+    type Fetch<'w> = (AllTraitsFetch<'w, A>, AllTraitsFetch<'w, B>);
+    type State = EitherState<A, B>;
+
+    #[inline]
+    fn shrink<'wlong: 'wshort, 'wshort>(item: QueryItem<'wlong, Self>) -> QueryItem<'wshort, Self> {
+        item
+    }
+
+    #[inline]
+    unsafe fn init_fetch<'w>(
+        world: UnsafeWorldCell<'w>,
+        state: &Self::State,
+        last_run: Tick,
+        this_run: Tick,
+    ) -> Self::Fetch<'w> {
+        (
+            <All<&A> as WorldQuery>::init_fetch(world, &state.left, last_run, this_run),
+            <All<&B> as WorldQuery>::init_fetch(world, &state.right, last_run, this_run),
+        )
+    }
+
+    const IS_DENSE: bool = false;
+
+    #[inline]
+    unsafe fn set_archetype<'w>(
+        fetch: &mut Self::Fetch<'w>,
+        state: &Self::State,
+        archetype: &'w bevy_ecs::archetype::Archetype,
+        table: &'w bevy_ecs::storage::Table,
+    ) {
+        <All<&A> as WorldQuery>::set_archetype(&mut fetch.0, &state.left, archetype, table);
+        <All<&B> as WorldQuery>::set_archetype(&mut fetch.1, &state.right, archetype, table);
+    }
+
+    #[inline]
+    unsafe fn set_table<'w>(
+        fetch: &mut Self::Fetch<'w>,
+        state: &Self::State,
+        table: &'w bevy_ecs::storage::Table,
+    ) {
+        <All<&A> as WorldQuery>::set_table(&mut fetch.0, &state.left, table);
+        <All<&B> as WorldQuery>::set_table(&mut fetch.1, &state.right, table);
+    }
+
+    #[inline]
+    unsafe fn fetch<'w>(
+        fetch: &mut Self::Fetch<'w>,
+        entity: Entity,
+        table_row: TableRow,
+    ) -> Self::Item<'w> {
+        EitherTraits {
+            left: <All<&A> as WorldQuery>::fetch(&mut fetch.0, entity, table_row),
+            right: <All<&B> as WorldQuery>::fetch(&mut fetch.1, entity, table_row),
+        }
+    }
+
+    #[inline]
+    fn update_component_access(state: &Self::State, access: &mut FilteredAccess<ComponentId>) {
+        let mut new_access = access.clone();
+        <All<&A> as WorldQuery>::update_component_access(&state.left, &mut new_access);
+        let mut right = access.clone();
+        <All<&B> as WorldQuery>::update_component_access(&state.right, &mut right);
+        new_access.append_or(&right);
+        new_access.extend_access(&right);
+        *access = new_access;
+    }
+
+    #[inline]
+    fn init_state(world: &mut World) -> Self::State {
+        EitherState {
+            left: TraitQueryState::init(world),
+            right: TraitQueryState::init(world),
+        }
+    }
+
+    #[inline]
+    fn get_state(_: &Components) -> Option<Self::State> {
+        // TODO: fix this https://github.com/bevyengine/bevy/issues/13798
+        panic!("transmuting and any other operations concerning the state of a query are currently broken and shouldn't be used. See https://github.com/JoJoJet/bevy-trait-query/issues/59");
+    }
+
+    #[inline]
+    fn matches_component_set(
+        state: &Self::State,
+        set_contains_id: &impl Fn(ComponentId) -> bool,
+    ) -> bool {
+        state.left.matches_component_set_any(set_contains_id)
+            || state.right.matches_component_set_any(set_contains_id)
+    }
+
+    #[inline]
+    fn shrink_fetch<'wlong: 'wshort, 'wshort>(fetch: Self::Fetch<'wlong>) -> Self::Fetch<'wshort> {
+        fetch
+    }
+}
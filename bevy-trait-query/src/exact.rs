@@ -0,0 +1,124 @@
+use bevy_ecs::{
+    component::{ComponentId, Components, Tick},
+    entity::Entity,
+    query::{QueryData, QueryItem, ReadOnlyQueryData, WorldQuery},
+    storage::TableRow,
+    world::{unsafe_world_cell::UnsafeWorldCell, World},
+};
+
+use crate::{All, AllTraitsFetch, ReadTraits, TraitQuery, TraitQueryState};
+
+/// [`WorldQuery`] adapter that fetches entities with exactly `N` components implementing a trait.
+///
+/// This sits between [`One`](crate::One), which matches entities with exactly one impl,
+/// and [`All`], which matches entities with one or more. The impls are counted among the
+/// components present on each entity, not among all the components registered for the trait.
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_trait_query::*;
+/// #
+/// #[bevy_trait_query::queryable]
+/// pub trait StatModifier {
+///     fn modify(&self, stat: f32) -> f32;
+/// }
+///
+/// fn dual_wield(units: Query<Exactly<2, &dyn StatModifier>>) {
+///     for modifiers in &units {
+///         let attack = modifiers.iter().fold(1.0, |stat, modifier| modifier.modify(stat));
+///         println!("Attack: {attack}");
+///     }
+/// }
+/// # bevy_ecs::system::assert_is_system(dual_wield);
+/// ```
+pub struct Exactly<const N: usize, T>(T);
+
+unsafe impl<const N: usize, T: ?Sized + TraitQuery> QueryData for Exactly<N, &T> {
+    type ReadOnly = Self;
+}
+unsafe impl<const N: usize, T: ?Sized + TraitQuery> ReadOnlyQueryData for Exactly<N, &T> {}
+
+// SAFETY: Fetching and world access are the same as `All<&Trait>`, which this delegates to.
+// This only matches a subset of the archetypes that `All<&Trait>` matches.
+unsafe impl<const N: usize, Trait: ?Sized + TraitQuery> WorldQuery for Exactly<N, &Trait> {
+    type Item<'w> = ReadTraits<'w, Trait>;
+    type Fetch<'w> = AllTraitsFetch<'w, Trait>;
+    type State = TraitQueryState<Trait>;
+
+    #[inline]
+    fn shrink<'wlong: 'wshort, 'wshort>(item: QueryItem<'wlong, Self>) -> QueryItem<'wshort, Self> {
+        item
+    }
+
+    #[inline]
+    unsafe fn init_fetch<'w>(
+        world: UnsafeWorldCell<'w>,
+        state: &Self::State,
+        last_run: Tick,
+        this_run: Tick,
+    ) -> Self::Fetch<'w> {
+        <All<&Trait> as WorldQuery>::init_fetch(world, state, last_run, this_run)
+    }
+
+    const IS_DENSE: bool = false;
+
+    #[inline]
+    unsafe fn set_archetype<'w>(
+        fetch: &mut Self::Fetch<'w>,
+        state: &Self::State,
+        archetype: &'w bevy_ecs::archetype::Archetype,
+        table: &'w bevy_ecs::storage::Table,
+    ) {
+        <All<&Trait> as WorldQuery>::set_archetype(fetch, state, archetype, table);
+    }
+
+    #[inline]
+    unsafe fn set_table<'w>(
+        fetch: &mut Self::Fetch<'w>,
+        state: &Self::State,
+        table: &'w bevy_ecs::storage::Table,
+    ) {
+        <All<&Trait> as WorldQuery>::set_table(fetch, state, table);
+    }
+
+    #[inline]
+    unsafe fn fetch<'w>(
+        fetch: &mut Self::Fetch<'w>,
+        entity: Entity,
+        table_row: TableRow,
+    ) -> Self::Item<'w> {
+        <All<&Trait> as WorldQuery>::fetch(fetch, entity, table_row)
+    }
+
+    #[inline]
+    fn update_component_access(
+        state: &Self::State,
+        access: &mut bevy_ecs::query::FilteredAccess<ComponentId>,
+    ) {
+        <All<&Trait> as WorldQuery>::update_component_access(state, access);
+    }
+
+    #[inline]
+    fn init_state(world: &mut World) -> Self::State {
+        TraitQueryState::init(world)
+    }
+
+    #[inline]
+    fn get_state(_: &Components) -> Option<Self::State> {
+        // TODO: fix this https://github.com/bevyengine/bevy/issues/13798
+        panic!("transmuting and any other operations concerning the state of a query are currently broken and shouldn't be used. See https://github.com/JoJoJet/bevy-trait-query/issues/59");
+    }
+
+    #[inline]
+    fn matches_component_set(
+        state: &Self::State,
+        set_contains_id: &impl Fn(ComponentId) -> bool,
+    ) -> bool {
+        state.matches_component_set_exact(set_contains_id, N)
+    }
+
+    #[inline]
+    fn shrink_fetch<'wlong: 'wshort, 'wshort>(fetch: Self::Fetch<'wlong>) -> Self::Fetch<'wshort> {
+        fetch
+    }
+}
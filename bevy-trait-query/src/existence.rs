@@ -0,0 +1,114 @@
+use bevy_ecs::{
+    archetype::{ArchetypeGeneration, ArchetypeId, Archetypes},
+    component::{ComponentId, Tick},
+    system::{ReadOnlySystemParam, SystemMeta, SystemParam},
+    world::{unsafe_world_cell::UnsafeWorldCell, World},
+};
+
+use crate::{TraitQuery, TraitQueryState};
+
+/// [`SystemParam`] for checking whether any entities have a component implementing `Trait`.
+///
+/// The archetypes containing an impl of `Trait` are cached between runs of the system,
+/// and only newly-created archetypes are scanned when the world's archetypes change.
+/// This makes existence checks cheap in systems that run often.
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_trait_query::*;
+/// #
+/// # #[bevy_trait_query::queryable]
+/// # pub trait DeadFlag {}
+/// #
+/// fn clean_up_dead(dead: CachedDynExistence<dyn DeadFlag>) {
+///     if dead.is_empty() {
+///         return;
+///     }
+///     // ...
+/// }
+/// # bevy_ecs::system::assert_is_system(clean_up_dead);
+/// ```
+pub struct CachedDynExistence<'w, 's, Trait: ?Sized + TraitQuery> {
+    archetypes: &'w Archetypes,
+    cache: &'s ExistenceCache<Trait>,
+}
+
+#[doc(hidden)]
+pub struct ExistenceCache<Trait: ?Sized> {
+    components: Box<[ComponentId]>,
+    // The archetypes that have been scanned so far.
+    generation: ArchetypeGeneration,
+    // The scanned archetypes which contain at least one impl of the trait.
+    matched: Vec<ArchetypeId>,
+    marker: std::marker::PhantomData<fn() -> Box<Trait>>,
+}
+
+impl<Trait: ?Sized> ExistenceCache<Trait> {
+    fn update(&mut self, archetypes: &Archetypes) {
+        if self.generation == archetypes.generation() {
+            return;
+        }
+        for archetype in &archetypes[self.generation..] {
+            if self
+                .components
+                .iter()
+                .any(|&component| archetype.contains(component))
+            {
+                self.matched.push(archetype.id());
+            }
+        }
+        self.generation = archetypes.generation();
+    }
+}
+
+// SAFETY: This only reads archetype metadata, which does not require registering any access.
+unsafe impl<Trait: ?Sized + TraitQuery> SystemParam for CachedDynExistence<'_, '_, Trait> {
+    type State = ExistenceCache<Trait>;
+    type Item<'w, 's> = CachedDynExistence<'w, 's, Trait>;
+
+    fn init_state(world: &mut World, _system_meta: &mut SystemMeta) -> Self::State {
+        ExistenceCache {
+            components: TraitQueryState::<Trait>::init(world).components().into(),
+            generation: ArchetypeGeneration::initial(),
+            matched: Vec::new(),
+            marker: std::marker::PhantomData,
+        }
+    }
+
+    #[inline]
+    unsafe fn get_param<'w, 's>(
+        state: &'s mut Self::State,
+        _system_meta: &SystemMeta,
+        world: UnsafeWorldCell<'w>,
+        _change_tick: Tick,
+    ) -> Self::Item<'w, 's> {
+        let archetypes = world.archetypes();
+        state.update(archetypes);
+        CachedDynExistence {
+            archetypes,
+            cache: state,
+        }
+    }
+}
+
+// SAFETY: This only reads archetype metadata.
+unsafe impl<Trait: ?Sized + TraitQuery> ReadOnlySystemParam for CachedDynExistence<'_, '_, Trait> {}
+
+impl<Trait: ?Sized + TraitQuery> CachedDynExistence<'_, '_, Trait> {
+    /// Returns `true` if no entities have a component implementing `Trait`.
+    pub fn is_empty(&self) -> bool {
+        self.cache
+            .matched
+            .iter()
+            .all(|&id| self.archetypes[id].is_empty())
+    }
+
+    /// Returns the number of entities with at least one component implementing `Trait`.
+    pub fn count(&self) -> usize {
+        self.cache
+            .matched
+            .iter()
+            .map(|&id| self.archetypes[id].len())
+            .sum()
+    }
+}
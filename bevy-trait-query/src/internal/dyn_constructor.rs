@@ -1,4 +1,8 @@
-use bevy_ecs::ptr::{Ptr, PtrMut};
+use bevy_ecs::{
+    change_detection::{Mut, MutUntyped},
+    component::{Component, Tick},
+    ptr::{Ptr, PtrMut},
+};
 
 /// Turns an untyped pointer into a trait object pointer,
 /// for a specific erased concrete type.
@@ -23,3 +27,20 @@ impl<Trait: ?Sized> DynCtor<Trait> {
         &mut *(self.cast)(ptr.as_ptr())
     }
 }
+
+/// Turns an untyped pointer to a component, along with its ticks, into a [`MutUntyped`]
+/// for a specific erased concrete type.
+pub(crate) type UntypedCtor =
+    for<'a> unsafe fn(PtrMut<'a>, &'a mut Tick, &'a mut Tick, Tick, Tick) -> MutUntyped<'a>;
+
+/// # Safety
+/// `ptr` must point to a valid instance of `C`.
+pub(crate) unsafe fn untyped_mut<'a, C: Component>(
+    ptr: PtrMut<'a>,
+    added: &'a mut Tick,
+    changed: &'a mut Tick,
+    last_run: Tick,
+    this_run: Tick,
+) -> MutUntyped<'a> {
+    Mut::new(ptr.deref_mut::<C>(), added, changed, last_run, this_run).into()
+}
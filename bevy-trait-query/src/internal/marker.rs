@@ -1,4 +1,4 @@
-use bevy_ecs::component::Component;
+use bevy_ecs::{component::Component, system::Resource};
 
 /// Marker for traits that can be used in queries.
 pub trait TraitQuery: 'static {}
@@ -10,3 +10,11 @@ pub trait TraitQueryMarker<Trait: ?Sized + TraitQuery> {
     /// with a vtable corresponding to `Self::Covered`.
     fn cast(_: *mut u8) -> *mut Trait;
 }
+
+#[doc(hidden)]
+pub trait TraitResourceMarker<Trait: ?Sized + TraitQuery> {
+    type Covered: Resource;
+    /// Casts an untyped pointer to a trait object pointer,
+    /// with a vtable corresponding to `Self::Covered`.
+    fn cast(_: *mut u8) -> *mut Trait;
+}
@@ -9,5 +9,6 @@ pub use marker::*;
 pub use register_ext::*;
 pub use trait_state::*;
 
-pub(crate) use trait_registry::{TraitImplMeta, TraitImplRegistry};
+pub use trait_registry::TraitImplMeta;
+pub(crate) use trait_registry::TraitImplRegistry;
 pub(crate) use zip_exact::zip_exact;
@@ -1,7 +1,13 @@
 use crate::{
-    dyn_constructor::DynCtor, TraitImplMeta, TraitImplRegistry, TraitQuery, TraitQueryMarker,
+    dyn_constructor::{untyped_mut, DynCtor, UntypedCtor},
+    trait_registry::DeterministicTraitQueries,
+    TraitImplMeta, TraitImplRegistry, TraitQuery, TraitQueryMarker,
 };
-use bevy_ecs::prelude::{Component, World};
+use bevy_ecs::{
+    component::{ComponentId, StorageType},
+    prelude::{Component, World},
+};
+use std::{any::TypeId, fmt, marker::PhantomData};
 
 /// Extension methods for registering components with trait queries.
 pub trait RegisterExt {
@@ -16,8 +22,152 @@ pub trait RegisterExt {
         (C,): TraitQueryMarker<Trait, Covered = C>;
 }
 
+/// Extension methods for managing the components registered with trait queries,
+/// beyond [`RegisterExt::register_component_as`].
+pub trait TraitRegistryExt {
+    /// Allows a component to be used in trait queries,
+    /// as with [`register_component_as`](RegisterExt::register_component_as).
+    ///
+    /// Instead of panicking, this returns an error if trait queries for `Trait` have already
+    /// been initialized, so that late registrations (such as from plugins loaded at runtime)
+    /// can be handled gracefully. Registering a component that has already been registered
+    /// always succeeds.
+    fn try_register_component_as<Trait: ?Sized + TraitQuery, C: Component>(
+        &mut self,
+    ) -> Result<&mut Self, RegistrySealedError>
+    where
+        (C,): TraitQueryMarker<Trait, Covered = C>;
+
+    /// Allows a component registered at runtime, such as one defined by a scripting language,
+    /// to be used in trait queries.
+    ///
+    /// This shares its deduplication with [`register_component_as`](RegisterExt::register_component_as),
+    /// so registering the same component both ways only registers it once.
+    /// Components registered this way are not included in
+    /// [`export_trait_impls`](Self::export_trait_impls), since they can't be registered by type.
+    ///
+    /// # Safety
+    /// `cast` must turn a pointer to an instance of `component` into a pointer to
+    /// a trait object for that same instance.
+    ///
+    /// # Panics
+    /// If `component` is not registered in this world,
+    /// or if this function is called after the simulation starts for a given [`World`].
+    unsafe fn register_component_as_dynamic<Trait: ?Sized + TraitQuery>(
+        &mut self,
+        component: ComponentId,
+        cast: unsafe fn(*mut u8) -> *mut Trait,
+    ) -> &mut Self;
+
+    /// Warns if any component registered for `Trait` requires `C` via `#[require]`,
+    /// while `C` itself has not been registered for `Trait`.
+    ///
+    /// Entities with such a component will always have `C` as well,
+    /// but trait queries will silently skip `C`'s impl unless it is registered.
+    /// Since there is no way to discover at runtime whether a required component implements `Trait`,
+    /// the candidate component must be named explicitly.
+    ///
+    /// Returns `true` if a warning was emitted.
+    fn warn_unregistered_required_impls<Trait: ?Sized + TraitQuery, C: Component>(&self) -> bool
+    where
+        (C,): TraitQueryMarker<Trait, Covered = C>;
+
+    /// Returns the number of registered impls of `Trait` which are stored in tables and sparse sets,
+    /// in that order.
+    ///
+    /// Traits whose impls are all stored in tables can be queried faster.
+    /// Returns `(0, 0)` if no impls have been registered for `Trait`.
+    fn trait_impl_storage_breakdown<Trait: ?Sized + TraitQuery>(&self) -> (usize, usize);
+
+    /// Exports the set of components registered for `Trait`,
+    /// so that the same registrations can be applied to another world using
+    /// [`import_trait_impls`](Self::import_trait_impls).
+    fn export_trait_impls<Trait: ?Sized + TraitQuery>(&self) -> TraitImplExport<Trait>;
+
+    /// Registers each of the components in `export` for `Trait`,
+    /// as if by calling [`register_component_as`](RegisterExt::register_component_as) for each of them.
+    ///
+    /// # Panics
+    /// If this function is called after the simulation starts for a given [`World`].
+    fn import_trait_impls<Trait: ?Sized + TraitQuery>(
+        &mut self,
+        export: &TraitImplExport<Trait>,
+    ) -> &mut Self;
+
+    /// Makes the order of trait impls independent of the order they were registered in.
+    ///
+    /// By default, trait queries iterate the impls of each entity in registration order,
+    /// which depends on things such as the order plugins were added in.
+    /// After calling this, the impls of every trait are instead sorted by the type name
+    /// of each component when the first query for that trait is initialized.
+    ///
+    /// This must be called before any trait queries are initialized to affect them.
+    fn deterministic_trait_queries(&mut self) -> &mut Self;
+}
+
+/// An error from [`TraitRegistryExt::try_register_component_as`],
+/// returned when trait queries for the trait have already been initialized.
+///
+/// Carries the name of the trait.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RegistrySealedError(pub &'static str);
+
+impl fmt::Display for RegistrySealedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Cannot register new impls of `{}` after the game has started",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for RegistrySealedError {}
+
+/// The set of components registered for a trait in some world,
+/// obtained from [`TraitRegistryExt::export_trait_impls`].
+pub struct TraitImplExport<Trait: ?Sized> {
+    registrars: Vec<fn(&mut World)>,
+    marker: PhantomData<fn() -> Box<Trait>>,
+}
+
+impl<Trait: ?Sized> Clone for TraitImplExport<Trait> {
+    fn clone(&self) -> Self {
+        Self {
+            registrars: self.registrars.clone(),
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<Trait: ?Sized> TraitImplExport<Trait> {
+    /// Returns the number of components in this export.
+    pub fn len(&self) -> usize {
+        self.registrars.len()
+    }
+
+    /// Returns `true` if no components were registered for the trait.
+    pub fn is_empty(&self) -> bool {
+        self.registrars.is_empty()
+    }
+}
+
 impl RegisterExt for World {
     fn register_component_as<Trait: ?Sized + TraitQuery, C: Component>(&mut self) -> &mut Self
+    where
+        (C,): TraitQueryMarker<Trait, Covered = C>,
+    {
+        if let Err(error) = self.try_register_component_as::<Trait, C>() {
+            panic!("{error}");
+        }
+        self
+    }
+}
+
+impl TraitRegistryExt for World {
+    fn try_register_component_as<Trait: ?Sized + TraitQuery, C: Component>(
+        &mut self,
+    ) -> Result<&mut Self, RegistrySealedError>
     where
         (C,): TraitQueryMarker<Trait, Covered = C>,
     {
@@ -28,10 +178,117 @@ impl RegisterExt for World {
         let meta = TraitImplMeta {
             size_bytes: std::mem::size_of::<C>(),
             dyn_ctor: DynCtor { cast: <(C,)>::cast },
+            type_id: Some(TypeId::of::<C>()),
+        };
+        let registrar: fn(&mut World) = |world| {
+            world.register_component_as::<Trait, C>();
         };
-        registry.register::<C>(component_id, meta);
+        let untyped_ctor: UntypedCtor = untyped_mut::<C>;
+        registry.try_register(
+            component_id,
+            C::STORAGE_TYPE,
+            meta,
+            Some(registrar),
+            Some(untyped_ctor),
+        )?;
+        Ok(self)
+    }
+
+    unsafe fn register_component_as_dynamic<Trait: ?Sized + TraitQuery>(
+        &mut self,
+        component: ComponentId,
+        cast: unsafe fn(*mut u8) -> *mut Trait,
+    ) -> &mut Self {
+        let info = self
+            .components()
+            .get_info(component)
+            .unwrap_or_else(|| panic!("{component:?} is not registered in this world"));
+        let storage_type = info.storage_type();
+        let meta = TraitImplMeta {
+            size_bytes: info.layout().size(),
+            dyn_ctor: DynCtor { cast },
+            type_id: info.type_id(),
+        };
+        let registry = self
+            .get_resource_or_insert_with::<TraitImplRegistry<Trait>>(Default::default)
+            .into_inner();
+        registry.register(component, storage_type, meta, None, None);
+        self
+    }
+
+    fn warn_unregistered_required_impls<Trait: ?Sized + TraitQuery, C: Component>(&self) -> bool
+    where
+        (C,): TraitQueryMarker<Trait, Covered = C>,
+    {
+        let Some(registry) = self.get_resource::<TraitImplRegistry<Trait>>() else {
+            return false;
+        };
+        let Some(required) = self.components().component_id::<C>() else {
+            return false;
+        };
+        if registry.components.contains(&required) {
+            return false;
+        }
+
+        let mut warned = false;
+        for &component in &registry.components {
+            let Some(info) = self.components().get_info(component) else {
+                continue;
+            };
+            if info
+                .required_components()
+                .iter_ids()
+                .any(|id| id == required)
+            {
+                tracing::warn!(
+                    "`{}` requires `{}`, which implements `{}` but is not registered for it",
+                    info.name(),
+                    std::any::type_name::<C>(),
+                    std::any::type_name::<Trait>(),
+                );
+                warned = true;
+            }
+        }
+        warned
+    }
+
+    fn export_trait_impls<Trait: ?Sized + TraitQuery>(&self) -> TraitImplExport<Trait> {
+        TraitImplExport {
+            registrars: self
+                .get_resource::<TraitImplRegistry<Trait>>()
+                .map_or_else(Vec::new, |registry| {
+                    registry.registrars.iter().flatten().copied().collect()
+                }),
+            marker: PhantomData,
+        }
+    }
+
+    fn import_trait_impls<Trait: ?Sized + TraitQuery>(
+        &mut self,
+        export: &TraitImplExport<Trait>,
+    ) -> &mut Self {
+        for registrar in &export.registrars {
+            registrar(self);
+        }
+        self
+    }
+
+    fn deterministic_trait_queries(&mut self) -> &mut Self {
+        self.insert_resource(DeterministicTraitQueries);
         self
     }
+
+    fn trait_impl_storage_breakdown<Trait: ?Sized + TraitQuery>(&self) -> (usize, usize) {
+        self.get_resource::<TraitImplRegistry<Trait>>()
+            .map_or((0, 0), |registry| {
+                (
+                    registry.components_with_storage(StorageType::Table).count(),
+                    registry
+                        .components_with_storage(StorageType::SparseSet)
+                        .count(),
+                )
+            })
+    }
 }
 
 #[cfg(feature = "bevy_app")]
@@ -44,3 +301,160 @@ impl RegisterExt for bevy_app::App {
         self
     }
 }
+
+#[cfg(feature = "bevy_app")]
+impl TraitRegistryExt for bevy_app::App {
+    fn try_register_component_as<Trait: ?Sized + TraitQuery, C: Component>(
+        &mut self,
+    ) -> Result<&mut Self, RegistrySealedError>
+    where
+        (C,): TraitQueryMarker<Trait, Covered = C>,
+    {
+        self.world_mut().try_register_component_as::<Trait, C>()?;
+        Ok(self)
+    }
+
+    unsafe fn register_component_as_dynamic<Trait: ?Sized + TraitQuery>(
+        &mut self,
+        component: ComponentId,
+        cast: unsafe fn(*mut u8) -> *mut Trait,
+    ) -> &mut Self {
+        self.world_mut()
+            .register_component_as_dynamic::<Trait>(component, cast);
+        self
+    }
+
+    fn warn_unregistered_required_impls<Trait: ?Sized + TraitQuery, C: Component>(&self) -> bool
+    where
+        (C,): TraitQueryMarker<Trait, Covered = C>,
+    {
+        self.world().warn_unregistered_required_impls::<Trait, C>()
+    }
+
+    fn trait_impl_storage_breakdown<Trait: ?Sized + TraitQuery>(&self) -> (usize, usize) {
+        self.world().trait_impl_storage_breakdown::<Trait>()
+    }
+
+    fn export_trait_impls<Trait: ?Sized + TraitQuery>(&self) -> TraitImplExport<Trait> {
+        self.world().export_trait_impls::<Trait>()
+    }
+
+    fn import_trait_impls<Trait: ?Sized + TraitQuery>(
+        &mut self,
+        export: &TraitImplExport<Trait>,
+    ) -> &mut Self {
+        self.world_mut().import_trait_impls(export);
+        self
+    }
+
+    fn deterministic_trait_queries(&mut self) -> &mut Self {
+        self.world_mut().deterministic_trait_queries();
+        self
+    }
+}
+
+/// Registers several monomorphizations of a generic component with a trait query.
+///
+/// `register_generic!(app, dyn Trait, Wrapper<[i32, f32, String]>)` is equivalent to:
+///
+/// ```ignore
+/// app.register_component_as::<dyn Trait, Wrapper<i32>>()
+///     .register_component_as::<dyn Trait, Wrapper<f32>>()
+///     .register_component_as::<dyn Trait, Wrapper<String>>();
+/// ```
+///
+/// This works with anything implementing [`RegisterExt`], such as `App` or `World`.
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_trait_query::*;
+/// #
+/// #[bevy_trait_query::queryable]
+/// pub trait Describe {
+///     fn describe(&self) -> String;
+/// }
+///
+/// #[derive(Component)]
+/// struct Wrapper<T: Send + Sync + 'static>(T);
+///
+/// impl<T: std::fmt::Debug + Send + Sync + 'static> Describe for Wrapper<T> {
+///     fn describe(&self) -> String {
+///         format!("{:?}", self.0)
+///     }
+/// }
+///
+/// let mut world = World::new();
+/// register_generic!(world, dyn Describe, Wrapper<[i32, f32, String]>);
+/// ```
+#[macro_export]
+macro_rules! register_generic {
+    ($registrar:expr, $trait:ty, $($wrapper:ident)::+ < [ $( $arg:ty ),* $(,)? ] >) => {{
+        use $crate::RegisterExt as _;
+        $registrar $( .register_component_as::<$trait, $($wrapper)::+<$arg>>() )*
+    }};
+}
+
+/// Registers several components with one or more trait queries.
+///
+/// `register_components_as!(app, dyn Tooltip => A, B; dyn Describe => A)` is equivalent to:
+///
+/// ```ignore
+/// app.register_component_as::<dyn Tooltip, A>()
+///     .register_component_as::<dyn Tooltip, B>()
+///     .register_component_as::<dyn Describe, A>();
+/// ```
+///
+/// This works with anything implementing [`RegisterExt`], such as `App` or `World`.
+/// Trailing commas and semicolons are allowed.
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_trait_query::*;
+/// #
+/// #[bevy_trait_query::queryable]
+/// pub trait Tooltip {
+///     fn tooltip(&self) -> &str;
+/// }
+///
+/// #[bevy_trait_query::queryable]
+/// pub trait Describe {
+///     fn describe(&self) -> String;
+/// }
+///
+/// #[derive(Component)]
+/// struct Player(String);
+///
+/// #[derive(Component)]
+/// struct Monster;
+///
+/// impl Tooltip for Player {
+///     fn tooltip(&self) -> &str {
+///         &self.0
+///     }
+/// }
+///
+/// impl Tooltip for Monster {
+///     fn tooltip(&self) -> &str {
+///         "Run!"
+///     }
+/// }
+///
+/// impl Describe for Monster {
+///     fn describe(&self) -> String {
+///         "A monster".to_owned()
+///     }
+/// }
+///
+/// let mut world = World::new();
+/// register_components_as!(world,
+///     dyn Tooltip => Player, Monster;
+///     dyn Describe => Monster,
+/// );
+/// ```
+#[macro_export]
+macro_rules! register_components_as {
+    ($registrar:expr, $( $trait:ty => $( $component:ty ),+ $(,)? );+ $(;)?) => {{
+        use $crate::RegisterExt as _;
+        $registrar $( $( .register_component_as::<$trait, $component>() )+ )+
+    }};
+}
@@ -1,18 +1,30 @@
-use crate::dyn_constructor::DynCtor;
-use crate::TraitQuery;
-use bevy_ecs::component::{Component, ComponentId, StorageType};
-use bevy_ecs::prelude::Resource;
+use std::any::TypeId;
+
+use crate::dyn_constructor::{DynCtor, UntypedCtor};
+use crate::{RegistrySealedError, TraitQuery};
+use bevy_ecs::component::{Component, ComponentId, Components, StorageType};
+use bevy_ecs::prelude::{Resource, World};
+/// When present in a world, trait registries sort their impls by type name when they are sealed.
+///
+/// Inserted by [`TraitRegistryExt::deterministic_trait_queries`](crate::TraitRegistryExt::deterministic_trait_queries).
+#[derive(Resource)]
+pub(crate) struct DeterministicTraitQueries;
+
 #[derive(Resource)]
 pub(crate) struct TraitImplRegistry<Trait: ?Sized> {
+    // Impls are stored in the order they were registered in, regardless of storage type,
+    // and trait queries iterate them in this order.
     // Component IDs are stored contiguously so that we can search them quickly.
     pub(crate) components: Vec<ComponentId>,
     pub(crate) meta: Vec<TraitImplMeta<Trait>>,
-
-    pub(crate) table_components: Vec<ComponentId>,
-    pub(crate) table_meta: Vec<TraitImplMeta<Trait>>,
-
-    pub(crate) sparse_components: Vec<ComponentId>,
-    pub(crate) sparse_meta: Vec<TraitImplMeta<Trait>>,
+    // The storage type of each component in `components`.
+    pub(crate) storage_types: Vec<StorageType>,
+    // Functions which register each component in `components` with another world.
+    // This is `None` for components that were registered dynamically.
+    pub(crate) registrars: Vec<Option<fn(&mut World)>>,
+    // Functions which expose each component in `components` as a `MutUntyped`.
+    // This is `None` for components that were registered dynamically.
+    pub(crate) untyped_ctors: Vec<Option<UntypedCtor>>,
 
     pub(crate) sealed: bool,
 }
@@ -23,56 +35,122 @@ impl<T: ?Sized> Default for TraitImplRegistry<T> {
         Self {
             components: vec![],
             meta: vec![],
-            table_components: vec![],
-            table_meta: vec![],
-            sparse_components: vec![],
-            sparse_meta: vec![],
+            storage_types: vec![],
+            registrars: vec![],
+            untyped_ctors: vec![],
             sealed: false,
         }
     }
 }
 
 impl<Trait: ?Sized + TraitQuery> TraitImplRegistry<Trait> {
-    pub(crate) fn register<C: Component>(
+    /// Registers `component` as implementing the trait.
+    ///
+    /// Both static and dynamic registration go through this method,
+    /// so a component is only ever registered once, however it was registered.
+    pub(crate) fn register(
         &mut self,
         component: ComponentId,
+        storage_type: StorageType,
         meta: TraitImplMeta<Trait>,
+        registrar: Option<fn(&mut World)>,
+        untyped_ctor: Option<UntypedCtor>,
     ) {
+        if let Err(error) =
+            self.try_register(component, storage_type, meta, registrar, untyped_ctor)
+        {
+            // It is not possible to update the `FetchState` for a given system after the game has started,
+            // so for explicitness, let's panic instead of having a trait impl silently get forgotten.
+            panic!("{error}");
+        }
+    }
+
+    /// Registers `component` as implementing the trait,
+    /// returning an error instead of panicking if the registry has been sealed.
+    pub(crate) fn try_register(
+        &mut self,
+        component: ComponentId,
+        storage_type: StorageType,
+        meta: TraitImplMeta<Trait>,
+        registrar: Option<fn(&mut World)>,
+        untyped_ctor: Option<UntypedCtor>,
+    ) -> Result<(), RegistrySealedError> {
         // Don't register the same component multiple times.
         if self.components.contains(&component) {
-            return;
+            return Ok(());
         }
 
         if self.sealed {
-            // It is not possible to update the `FetchState` for a given system after the game has started,
-            // so for explicitness, let's panic instead of having a trait impl silently get forgotten.
-            panic!("Cannot register new trait impls after the game has started");
+            return Err(RegistrySealedError(std::any::type_name::<Trait>()));
         }
 
         self.components.push(component);
         self.meta.push(meta);
+        self.storage_types.push(storage_type);
+        self.registrars.push(registrar);
+        self.untyped_ctors.push(untyped_ctor);
+        Ok(())
+    }
 
-        match <C as Component>::STORAGE_TYPE {
-            StorageType::Table => {
-                self.table_components.push(component);
-                self.table_meta.push(meta);
-            }
-            StorageType::SparseSet => {
-                self.sparse_components.push(component);
-                self.sparse_meta.push(meta);
-            }
-        }
+    /// Returns the registered components with the given storage type, in registration order.
+    pub(crate) fn components_with_storage(
+        &self,
+        storage_type: StorageType,
+    ) -> impl Iterator<Item = ComponentId> + '_ {
+        self.components
+            .iter()
+            .zip(&self.storage_types)
+            .filter(move |&(_, &storage)| storage == storage_type)
+            .map(|(&component, _)| component)
+    }
+
+    /// Returns `true` if the trait has exactly one registered impl, which is stored in a table.
+    pub(crate) fn single_table(&self) -> bool {
+        matches!(self.storage_types[..], [StorageType::Table])
+    }
+
+    /// Returns the [`ComponentId`] of the component `C`, if it is registered.
+    pub(crate) fn component_id_of<C: Component>(&self) -> Option<ComponentId> {
+        let index = self
+            .meta
+            .iter()
+            .position(|meta| meta.type_id == Some(TypeId::of::<C>()))?;
+        Some(self.components[index])
     }
 
     pub(crate) fn seal(&mut self) {
         self.sealed = true;
     }
+
+    /// Sorts the registered impls by the type name of each component,
+    /// so that their order does not depend on the order they were registered in.
+    pub(crate) fn sort_by_name(&mut self, components: &Components) {
+        fn permute<T: Copy>(items: &[T], order: &[usize]) -> Vec<T> {
+            order.iter().map(|&i| items[i]).collect()
+        }
+
+        let name = |component| {
+            components
+                .get_info(component)
+                .map_or("", |info| info.name())
+        };
+        let mut order: Vec<usize> = (0..self.components.len()).collect();
+        order.sort_by_key(|&i| name(self.components[i]));
+
+        self.components = permute(&self.components, &order);
+        self.meta = permute(&self.meta, &order);
+        self.storage_types = permute(&self.storage_types, &order);
+        self.registrars = permute(&self.registrars, &order);
+        self.untyped_ctors = permute(&self.untyped_ctors, &order);
+    }
 }
 
 /// Stores data about an impl of a trait
-pub(crate) struct TraitImplMeta<Trait: ?Sized> {
+pub struct TraitImplMeta<Trait: ?Sized> {
     pub(crate) size_bytes: usize,
     pub(crate) dyn_ctor: DynCtor<Trait>,
+    // This is `None` for components without a corresponding Rust type.
+    pub(crate) type_id: Option<TypeId>,
 }
 
 impl<T: ?Sized> Copy for TraitImplMeta<T> {}
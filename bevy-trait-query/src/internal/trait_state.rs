@@ -1,8 +1,8 @@
 use bevy_ecs::component::ComponentId;
-use bevy_ecs::prelude::World;
+use bevy_ecs::prelude::{Mut, World};
 
 use crate::{
-    trait_registry::{TraitImplMeta, TraitImplRegistry},
+    trait_registry::{DeterministicTraitQueries, TraitImplMeta, TraitImplRegistry},
     TraitQuery,
 };
 
@@ -10,10 +10,16 @@ use crate::{
 pub struct TraitQueryState<Trait: ?Sized> {
     pub(crate) components: Box<[ComponentId]>,
     pub(crate) meta: Box<[TraitImplMeta<Trait>]>,
+    /// Whether the trait has exactly one registered impl, which is stored in a table.
+    /// This allows trait queries to skip searching for the components present in a table.
+    pub(crate) single_table: bool,
 }
 
 impl<Trait: ?Sized + TraitQuery> TraitQueryState<Trait> {
-    pub(crate) fn init(world: &mut World) -> Self {
+    /// Initializes the state for a trait query, sealing the trait's registry.
+    ///
+    /// No more impls can be registered for the trait after this is called.
+    pub fn init(world: &mut World) -> Self {
         #[cold]
         fn missing_registry<T: ?Sized + 'static>() -> TraitImplRegistry<T> {
             tracing::warn!(
@@ -23,32 +29,129 @@ impl<Trait: ?Sized + TraitQuery> TraitQueryState<Trait> {
             TraitImplRegistry::<T>::default()
         }
 
-        let mut registry = world.get_resource_or_insert_with(missing_registry);
-        registry.seal();
-        Self {
-            components: registry.components.clone().into_boxed_slice(),
-            meta: registry.meta.clone().into_boxed_slice(),
-        }
+        world.get_resource_or_insert_with(missing_registry);
+        let deterministic = world.contains_resource::<DeterministicTraitQueries>();
+        world.resource_scope(|world, mut registry: Mut<TraitImplRegistry<Trait>>| {
+            if deterministic && !registry.sealed {
+                registry.sort_by_name(world.components());
+            }
+            registry.seal();
+            Self {
+                components: registry.components.clone().into_boxed_slice(),
+                meta: registry.meta.clone().into_boxed_slice(),
+                single_table: registry.single_table(),
+            }
+        })
     }
 
+    /// The components registered as implementing the trait.
     #[inline]
-    pub(crate) fn matches_component_set_any(
+    pub fn components(&self) -> &[ComponentId] {
+        &self.components
+    }
+
+    /// Returns `true` if any of the components implementing the trait are in the set.
+    ///
+    /// This is the matching logic used by [`All`](crate::All), exposed for use in custom query adapters.
+    ///
+    /// ```
+    /// # use bevy::prelude::*;
+    /// # use bevy::ecs::{
+    /// #     archetype::Archetype,
+    /// #     component::{ComponentId, Components, Tick},
+    /// #     query::{FilteredAccess, QueryFilter, WorldQuery},
+    /// #     storage::{Table, TableRow},
+    /// #     world::unsafe_world_cell::UnsafeWorldCell,
+    /// # };
+    /// # use bevy_trait_query::*;
+    /// #
+    /// /// Filters for entities with at least one impl of a trait.
+    /// pub struct WithAnyImpl<Trait: ?Sized>(std::marker::PhantomData<&'static Trait>);
+    ///
+    /// // SAFETY: This does not access any component data.
+    /// unsafe impl<Trait: ?Sized + TraitQuery> WorldQuery for WithAnyImpl<Trait> {
+    ///     type Item<'w> = ();
+    ///     type Fetch<'w> = ();
+    ///     type State = TraitQueryState<Trait>;
+    ///
+    ///     fn shrink<'wlong: 'wshort, 'wshort>(_: ()) {}
+    ///     fn shrink_fetch<'wlong: 'wshort, 'wshort>(_: ()) {}
+    ///     unsafe fn init_fetch(_: UnsafeWorldCell<'_>, _: &Self::State, _: Tick, _: Tick) {}
+    ///     const IS_DENSE: bool = false;
+    ///     unsafe fn set_archetype(_: &mut (), _: &Self::State, _: &Archetype, _: &Table) {}
+    ///     unsafe fn set_table(_: &mut (), _: &Self::State, _: &Table) {}
+    ///     unsafe fn fetch(_: &mut (), _: Entity, _: TableRow) {}
+    ///     fn update_component_access(state: &Self::State, access: &mut FilteredAccess<ComponentId>) {
+    ///         // The first impl constrains the existing access,
+    ///         // and each of the others adds another branch to the `OR`.
+    ///         let mut new_access = access.clone();
+    ///         let mut not_first = false;
+    ///         for &component in state.components() {
+    ///             if not_first {
+    ///                 let mut intermediate = access.clone();
+    ///                 intermediate.and_with(component);
+    ///                 new_access.append_or(&intermediate);
+    ///             } else {
+    ///                 new_access.and_with(component);
+    ///                 not_first = true;
+    ///             }
+    ///         }
+    ///         *access = new_access;
+    ///     }
+    ///     fn init_state(world: &mut World) -> Self::State {
+    ///         TraitQueryState::init(world)
+    ///     }
+    ///     fn get_state(_: &Components) -> Option<Self::State> {
+    ///         None
+    ///     }
+    ///     fn matches_component_set(
+    ///         state: &Self::State,
+    ///         set_contains_id: &impl Fn(ComponentId) -> bool,
+    ///     ) -> bool {
+    ///         state.matches_component_set_any(set_contains_id)
+    ///     }
+    /// }
+    ///
+    /// unsafe impl<Trait: ?Sized + TraitQuery> QueryFilter for WithAnyImpl<Trait> {
+    ///     const IS_ARCHETYPAL: bool = true;
+    ///     unsafe fn filter_fetch(_: &mut (), _: Entity, _: TableRow) -> bool {
+    ///         true
+    ///     }
+    /// }
+    /// ```
+    #[inline]
+    pub fn matches_component_set_any(
         &self,
         set_contains_id: &impl Fn(ComponentId) -> bool,
     ) -> bool {
         self.components.iter().copied().any(set_contains_id)
     }
 
+    /// Returns `true` if exactly one of the components implementing the trait is in the set.
+    ///
+    /// This is the matching logic used by [`One`](crate::One), exposed for use in custom query adapters.
+    #[inline]
+    pub fn matches_component_set_one(
+        &self,
+        set_contains_id: &impl Fn(ComponentId) -> bool,
+    ) -> bool {
+        self.matches_component_set_exact(set_contains_id, 1)
+    }
+
+    /// Returns `true` if exactly `n` of the components implementing the trait are in the set.
+    ///
+    /// This is the matching logic used by [`Exactly`](crate::Exactly), exposed for use in custom query adapters.
     #[inline]
-    pub(crate) fn matches_component_set_one(
+    pub fn matches_component_set_exact(
         &self,
         set_contains_id: &impl Fn(ComponentId) -> bool,
+        n: usize,
     ) -> bool {
         let match_count = self
             .components
             .iter()
             .filter(|&&c| set_contains_id(c))
             .count();
-        match_count == 1
+        match_count == n
     }
 }
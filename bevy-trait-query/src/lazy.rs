@@ -0,0 +1,80 @@
+use bevy_app::{App, PreUpdate};
+use bevy_ecs::{
+    component::Component, schedule::common_conditions::run_once, schedule::IntoSystemConfigs,
+    world::World,
+};
+
+use crate::{TraitQuery, TraitQueryMarker, TraitRegistryExt};
+
+/// Extension methods for registering components with trait queries after the app has started.
+pub trait LazyRegisterExt {
+    /// Registers `C` as an impl of `Trait` at the start of the first [`PreUpdate`],
+    /// as with [`RegisterExt::register_component_as`](crate::RegisterExt::register_component_as).
+    ///
+    /// Registration is only possible until a trait query for `Trait` is initialized,
+    /// which seals the registry. This relies on Bevy initializing the systems in a schedule
+    /// when that schedule first runs: systems in [`Update`](bevy_app::Update) and later
+    /// schedules can still see the impl, even if it is only known after startup systems
+    /// have run, such as once a scene has been loaded.
+    ///
+    /// # Panics
+    /// At the start of the first [`PreUpdate`], if a trait query for `Trait` has already been
+    /// initialized, such as by a system in one of the startup schedules or in [`First`](bevy_app::First).
+    /// Those queries would otherwise silently miss `C`.
+    ///
+    /// ```
+    /// # use bevy::prelude::*;
+    /// # use bevy_trait_query::*;
+    /// #
+    /// #[bevy_trait_query::queryable]
+    /// pub trait Tooltip {
+    ///     fn tooltip(&self) -> &str;
+    /// }
+    ///
+    /// #[derive(Component)]
+    /// struct SceneProp(String);
+    ///
+    /// impl Tooltip for SceneProp {
+    ///     fn tooltip(&self) -> &str {
+    ///         &self.0
+    ///     }
+    /// }
+    ///
+    /// fn show_tooltips(tooltips: Query<&dyn Tooltip>) {
+    ///     for tooltip in tooltips.iter().flatten() {
+    ///         println!("Tooltip: {}", tooltip.tooltip());
+    ///     }
+    /// }
+    ///
+    /// App::new()
+    ///     .register_component_as_lazy::<dyn Tooltip, SceneProp>()
+    ///     .add_systems(Update, show_tooltips)
+    ///     .update();
+    /// ```
+    fn register_component_as_lazy<Trait: ?Sized + TraitQuery, C: Component>(&mut self) -> &mut Self
+    where
+        (C,): TraitQueryMarker<Trait, Covered = C>;
+}
+
+impl LazyRegisterExt for App {
+    fn register_component_as_lazy<Trait: ?Sized + TraitQuery, C: Component>(&mut self) -> &mut Self
+    where
+        (C,): TraitQueryMarker<Trait, Covered = C>,
+    {
+        self.add_systems(PreUpdate, register_lazily::<Trait, C>.run_if(run_once))
+    }
+}
+
+fn register_lazily<Trait: ?Sized + TraitQuery, C: Component>(world: &mut World)
+where
+    (C,): TraitQueryMarker<Trait, Covered = C>,
+{
+    if world.try_register_component_as::<Trait, C>().is_err() {
+        panic!(
+            "Cannot lazily register `{}` as an impl of `{}`: trait queries for it were \
+             initialized before the first `PreUpdate`.",
+            std::any::type_name::<C>(),
+            std::any::type_name::<Trait>(),
+        );
+    }
+}
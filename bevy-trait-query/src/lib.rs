@@ -246,6 +246,35 @@
 //! ```
 //! Note in the above example how [`OneChanged`](crate::one::OneChanged) does *not* take a reference to the trait object!
 //!
+//! As with any other query, components inserted using `Commands` will not show up in trait queries
+//! until the commands have been applied, such as at the next sync point in the schedule.
+//!
+//! Trait queries can be wrapped in a custom [`SystemParam`](bevy_ecs::system::SystemParam).
+//! Since the struct names the query's lifetimes explicitly, the trait object has to be spelled
+//! `&'static dyn Trait`, just like a concrete component would be:
+//! ```
+//! # use bevy::prelude::*;
+//! # use bevy::ecs::system::SystemParam;
+//! # use bevy_trait_query::*;
+//! #
+//! # #[bevy_trait_query::queryable]
+//! # pub trait Tooltip {
+//! #     fn tooltip(&self) -> &str;
+//! # }
+//! #
+//! #[derive(SystemParam)]
+//! struct Tooltips<'w, 's> {
+//!     query: Query<'w, 's, &'static dyn Tooltip>,
+//! }
+//!
+//! fn show_tooltips(tooltips: Tooltips) {
+//!     for tooltip in tooltips.query.iter().flatten() {
+//!         println!("Tooltip: {}", tooltip.tooltip());
+//!     }
+//! }
+//! # bevy_ecs::system::assert_is_system(show_tooltips);
+//! ```
+//!
 //! # Performance
 //!
 //! The performance of trait queries is quite competitive. Here are some benchmarks for simple cases:
@@ -257,16 +286,47 @@
 //! | 1-2 matches       | -              | 14.619 µs           | 92.876 µs         |
 //!
 
+// Allows the code generated by `#[queryable]` to refer to this crate by name from within it.
+extern crate self as bevy_trait_query;
+
+mod commands;
+mod conditions;
+mod count_changes;
+mod either;
+mod exact;
+mod existence;
 mod internal;
+#[cfg(feature = "bevy_app")]
+mod lazy;
+mod memo;
+mod query_ext;
+mod report;
+mod resource;
 #[cfg(test)]
 mod tests;
+mod world_ext;
 
 pub mod all;
+pub mod matches;
 pub mod one;
 
 pub use all::*;
+pub use commands::*;
+pub use conditions::*;
+pub use count_changes::*;
+pub use either::*;
+pub use exact::*;
+pub use existence::*;
 pub use internal::*;
+#[cfg(feature = "bevy_app")]
+pub use lazy::*;
+pub use matches::*;
+pub use memo::*;
 pub use one::*;
+pub use query_ext::*;
+pub use report::*;
+pub use resource::*;
+pub use world_ext::*;
 
 pub use bevy_trait_query_impl::queryable;
 
@@ -276,6 +336,7 @@ pub use bevy_trait_query_impl::queryable;
 pub mod imports {
     pub use bevy_ecs::{
         archetype::{Archetype, ArchetypeComponentId},
+        change_detection::Ref,
         component::Tick,
         component::{Component, ComponentId, Components},
         entity::Entity,
@@ -284,6 +345,7 @@ pub mod imports {
             ReadOnlyQueryData, WorldQuery,
         },
         storage::{Table, TableRow},
+        system::Resource,
         world::{unsafe_world_cell::UnsafeWorldCell, World},
     };
 }
@@ -291,13 +353,36 @@ pub mod imports {
 #[track_caller]
 #[inline(always)]
 unsafe fn debug_unreachable() -> ! {
-    #[cfg(debug_assertions)]
+    #[cfg(any(debug_assertions, feature = "paranoid"))]
     unreachable!();
 
-    #[cfg(not(debug_assertions))]
+    #[cfg(not(any(debug_assertions, feature = "paranoid")))]
     std::hint::unreachable_unchecked();
 }
 
+/// With the `paranoid` feature enabled, checks that `table_row` is in bounds for a table
+/// before we do any pointer arithmetic with it.
+/// This relies on invariants upheld by bevy, so it should never fail.
+#[track_caller]
+#[inline(always)]
+fn paranoid_check_row(_entity_count: usize, _table_row: bevy_ecs::storage::TableRow) {
+    #[cfg(feature = "paranoid")]
+    check_row(_entity_count, _table_row);
+}
+
+/// Panics if `table_row` is out of bounds for a table with `entity_count` entities.
+#[cfg(any(test, feature = "paranoid"))]
+#[track_caller]
+#[inline(always)]
+fn check_row(entity_count: usize, table_row: bevy_ecs::storage::TableRow) {
+    assert!(
+        table_row.as_usize() < entity_count,
+        "table row {} is out of bounds for a table with {} entities",
+        table_row.as_usize(),
+        entity_count,
+    );
+}
+
 #[inline(never)]
 #[cold]
 fn trait_registry_error() -> ! {
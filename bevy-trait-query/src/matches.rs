@@ -0,0 +1,122 @@
+use std::marker::PhantomData;
+
+use bevy_ecs::{
+    archetype::Archetype,
+    component::{ComponentId, Components, Tick},
+    prelude::{Entity, World},
+    query::{FilteredAccess, QueryData, QueryItem, ReadOnlyQueryData, WorldQuery},
+    storage::{Table, TableRow},
+    world::unsafe_world_cell::UnsafeWorldCell,
+};
+
+use crate::{TraitQuery, TraitQueryState};
+
+/// [`WorldQuery`] that yields `true` if an entity has at least one component implementing a trait.
+///
+/// This is the trait query equivalent of bevy's `Has<T>`: unlike the [`WithOne`](crate::WithOne)
+/// and [`WithoutAny`](crate::WithoutAny) filters, it never filters out any entities.
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_trait_query::*;
+/// #
+/// # #[bevy_trait_query::queryable]
+/// # pub trait Tooltip {
+/// #     fn tooltip(&self) -> &str;
+/// # }
+/// #
+/// fn count_tooltips(query: Query<(Entity, Matches<dyn Tooltip>)>) {
+///     for (entity, has_tooltip) in &query {
+///         println!("{entity}: {has_tooltip}");
+///     }
+/// }
+/// # bevy_ecs::system::assert_is_system(count_tooltips);
+/// ```
+pub struct Matches<Trait: ?Sized + TraitQuery>(PhantomData<&'static Trait>);
+
+// this takes inspiration from `Has` in bevy's main repo
+unsafe impl<Trait: ?Sized + TraitQuery> WorldQuery for Matches<Trait> {
+    type Item<'w> = bool;
+    type Fetch<'w> = bool;
+    type State = TraitQueryState<Trait>;
+
+    #[inline]
+    fn shrink<'wlong: 'wshort, 'wshort>(item: QueryItem<'wlong, Self>) -> QueryItem<'wshort, Self> {
+        item
+    }
+
+    #[inline]
+    unsafe fn init_fetch(
+        _world: UnsafeWorldCell<'_>,
+        _state: &Self::State,
+        _last_run: Tick,
+        _this_run: Tick,
+    ) -> bool {
+        false
+    }
+
+    const IS_DENSE: bool = false;
+
+    #[inline]
+    unsafe fn set_archetype<'w>(
+        fetch: &mut bool,
+        state: &Self::State,
+        archetype: &'w Archetype,
+        _table: &'w Table,
+    ) {
+        *fetch = state.matches_component_set_any(&|id| archetype.contains(id));
+    }
+
+    #[inline]
+    unsafe fn set_table(fetch: &mut bool, state: &Self::State, table: &Table) {
+        *fetch = state.matches_component_set_any(&|id| table.has_column(id));
+    }
+
+    #[inline(always)]
+    unsafe fn fetch<'w>(
+        fetch: &mut Self::Fetch<'w>,
+        _entity: Entity,
+        _table_row: TableRow,
+    ) -> Self::Item<'w> {
+        *fetch
+    }
+
+    #[inline]
+    fn update_component_access(state: &Self::State, access: &mut FilteredAccess<ComponentId>) {
+        // We only ever look at the archetype, never at the component data.
+        for &component in &*state.components {
+            access.access_mut().add_archetypal(component);
+        }
+    }
+
+    #[inline]
+    fn init_state(world: &mut World) -> Self::State {
+        TraitQueryState::init(world)
+    }
+
+    #[inline]
+    fn get_state(_: &Components) -> Option<Self::State> {
+        // TODO: fix this https://github.com/bevyengine/bevy/issues/13798
+        panic!("transmuting and any other operations concerning the state of a query are currently broken and shouldn't be used. See https://github.com/JoJoJet/bevy-trait-query/issues/59");
+    }
+
+    #[inline]
+    fn matches_component_set(
+        _state: &Self::State,
+        _set_contains_id: &impl Fn(ComponentId) -> bool,
+    ) -> bool {
+        // `Matches` yields a value for every entity, so it matches all archetypes.
+        true
+    }
+
+    #[inline]
+    fn shrink_fetch<'wlong: 'wshort, 'wshort>(fetch: Self::Fetch<'wlong>) -> Self::Fetch<'wshort> {
+        fetch
+    }
+}
+
+unsafe impl<Trait: ?Sized + TraitQuery> QueryData for Matches<Trait> {
+    type ReadOnly = Self;
+}
+/// SAFETY: read-only access
+unsafe impl<Trait: ?Sized + TraitQuery> ReadOnlyQueryData for Matches<Trait> {}
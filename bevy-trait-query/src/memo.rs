@@ -0,0 +1,142 @@
+use std::collections::HashMap;
+
+use bevy_ecs::{
+    archetype::Archetype,
+    change_detection::DetectChanges,
+    component::{ComponentId, Tick},
+    entity::Entity,
+    query::QueryState,
+    system::{Query, ReadOnlySystemParam, SystemMeta, SystemParam},
+    world::{unsafe_world_cell::UnsafeWorldCell, World},
+};
+
+use crate::{All, TraitQuery};
+
+/// [`SystemParam`] that caches a value computed from each impl of `Trait`,
+/// only recomputing it when the impl has changed.
+///
+/// Values are cached per entity and per component, and are kept between runs of the system.
+/// Call [`update`](Self::update) to bring the cache up to date before reading from it.
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_trait_query::*;
+/// #
+/// #[bevy_trait_query::queryable]
+/// pub trait Shape {
+///     fn vertices(&self) -> &[Vec2];
+/// }
+///
+/// fn print_areas(mut areas: MemoizedTrait<dyn Shape, f32>) {
+///     // This is expensive, so we only want to do it when a shape changes.
+///     areas.update(|shape| {
+///         let vertices = shape.vertices();
+///         let mut area = 0.0;
+///         for (i, a) in vertices.iter().enumerate() {
+///             let b = vertices[(i + 1) % vertices.len()];
+///             area += a.perp_dot(b);
+///         }
+///         area.abs() / 2.0
+///     });
+///     for (entity, _, area) in areas.iter() {
+///         println!("{entity}: {area}");
+///     }
+/// }
+/// # bevy_ecs::system::assert_is_system(print_areas);
+/// ```
+pub struct MemoizedTrait<'w, 's, Trait: ?Sized + TraitQuery, V: Send + Sync + 'static> {
+    query: Query<'w, 's, All<&'static Trait>>,
+    cache: &'s mut HashMap<(Entity, ComponentId), (Tick, V)>,
+}
+
+#[doc(hidden)]
+pub struct MemoState<Trait: ?Sized + TraitQuery, V> {
+    query: QueryState<All<&'static Trait>>,
+    // The cached value for each impl, along with the tick that the impl was last changed
+    // at when the value was computed.
+    cache: HashMap<(Entity, ComponentId), (Tick, V)>,
+}
+
+// SAFETY: The access of the inner query is registered by delegating to its impl,
+// and the cache is only stored in the param's own state.
+unsafe impl<Trait: ?Sized + TraitQuery, V: Send + Sync + 'static> SystemParam
+    for MemoizedTrait<'_, '_, Trait, V>
+{
+    type State = MemoState<Trait, V>;
+    type Item<'w, 's> = MemoizedTrait<'w, 's, Trait, V>;
+
+    fn init_state(world: &mut World, system_meta: &mut SystemMeta) -> Self::State {
+        MemoState {
+            query: <Query<All<&'static Trait>> as SystemParam>::init_state(world, system_meta),
+            cache: HashMap::new(),
+        }
+    }
+
+    unsafe fn new_archetype(
+        state: &mut Self::State,
+        archetype: &Archetype,
+        system_meta: &mut SystemMeta,
+    ) {
+        <Query<All<&'static Trait>> as SystemParam>::new_archetype(
+            &mut state.query,
+            archetype,
+            system_meta,
+        );
+    }
+
+    #[inline]
+    unsafe fn get_param<'w, 's>(
+        state: &'s mut Self::State,
+        system_meta: &SystemMeta,
+        world: UnsafeWorldCell<'w>,
+        change_tick: Tick,
+    ) -> Self::Item<'w, 's> {
+        MemoizedTrait {
+            query: <Query<All<&'static Trait>> as SystemParam>::get_param(
+                &mut state.query,
+                system_meta,
+                world,
+                change_tick,
+            ),
+            cache: &mut state.cache,
+        }
+    }
+}
+
+// SAFETY: The inner query is read-only, and the cache is only stored in the param's own state.
+unsafe impl<Trait: ?Sized + TraitQuery, V: Send + Sync + 'static> ReadOnlySystemParam
+    for MemoizedTrait<'_, '_, Trait, V>
+{
+}
+
+impl<Trait: ?Sized + TraitQuery, V: Send + Sync + 'static> MemoizedTrait<'_, '_, Trait, V> {
+    /// Computes the value for each impl of `Trait` that was added or changed since its value
+    /// was last computed, and discards the values of impls that no longer exist.
+    pub fn update(&mut self, mut f: impl FnMut(&Trait) -> V) {
+        let mut old = std::mem::take(self.cache);
+        for traits in &self.query {
+            let entity = traits.entity();
+            for (component, item) in traits.iter_with_id() {
+                let last_changed = item.last_changed();
+                let value = match old.remove(&(entity, component)) {
+                    Some((tick, value)) if tick == last_changed => value,
+                    _ => f(&*item),
+                };
+                self.cache
+                    .insert((entity, component), (last_changed, value));
+            }
+        }
+    }
+
+    /// Returns the cached value for the impl `component` of `entity`, if any.
+    pub fn get(&self, entity: Entity, component: ComponentId) -> Option<&V> {
+        self.cache.get(&(entity, component)).map(|(_, value)| value)
+    }
+
+    /// Iterates over the cached values, along with the entity and component of each impl.
+    pub fn iter(&self) -> impl Iterator<Item = (Entity, ComponentId, &V)> {
+        self.cache
+            .iter()
+            .map(|(&(entity, component), (_, value))| (entity, component, value))
+    }
+}
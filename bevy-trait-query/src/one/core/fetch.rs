@@ -1,12 +1,15 @@
 use std::cell::UnsafeCell;
 
 use bevy_ecs::{
+    archetype::Archetype,
+    change_detection::{Mut, Ref},
     component::Tick,
-    ptr::{Ptr, ThinSlicePtr},
-    storage::{ComponentSparseSet, SparseSets},
+    entity::Entity,
+    ptr::{Ptr, ThinSlicePtr, UnsafeCellDeref},
+    storage::{ComponentSparseSet, SparseSets, Table, TableRow},
 };
 
-use crate::TraitImplMeta;
+use crate::{debug_unreachable, zip_exact, TraitImplMeta, TraitQuery, TraitQueryState};
 
 pub struct OneTraitFetch<'w, Trait: ?Sized> {
     // While we have shared access to all sparse set components,
@@ -18,6 +21,9 @@ pub struct OneTraitFetch<'w, Trait: ?Sized> {
     pub(crate) storage: FetchStorage<'w, Trait>,
     pub(crate) last_run: Tick,
     pub(crate) this_run: Tick,
+    // The number of entities in the current table, used to check rows with the `paranoid` feature.
+    #[cfg(feature = "paranoid")]
+    pub(crate) table_entity_count: usize,
 }
 
 impl<Trait: ?Sized> Clone for OneTraitFetch<'_, Trait> {
@@ -28,8 +34,15 @@ impl<Trait: ?Sized> Clone for OneTraitFetch<'_, Trait> {
 
 impl<Trait: ?Sized> Copy for OneTraitFetch<'_, Trait> {}
 
-pub(crate) enum FetchStorage<'w, Trait: ?Sized> {
+/// The storage of a single trait impl within an archetype.
+///
+/// This is what [`One`](crate::One) uses to fetch its items, and can be obtained for
+/// an archetype using [`select_one_storage`].
+#[doc(hidden)]
+pub enum FetchStorage<'w, Trait: ?Sized> {
+    /// No storage has been selected yet.
     Uninit,
+    /// The trait impl is stored in a table.
     Table {
         /// This points to one of the component table columns,
         /// corresponding to one of the `ComponentId`s in the fetch state.
@@ -40,6 +53,7 @@ pub(crate) enum FetchStorage<'w, Trait: ?Sized> {
         changed_ticks: ThinSlicePtr<'w, UnsafeCell<Tick>>,
         meta: TraitImplMeta<Trait>,
     },
+    /// The trait impl is stored in a sparse set.
     SparseSet {
         /// This gives us access to one of the components implementing the trait.
         /// The fetch impl registers access for all components implementing the trait,
@@ -55,3 +69,363 @@ impl<Trait: ?Sized> Clone for FetchStorage<'_, Trait> {
     }
 }
 impl<Trait: ?Sized> Copy for FetchStorage<'_, Trait> {}
+
+/// Finds the storage of the first trait impl present in `archetype`.
+///
+/// Table components are checked first, since it is faster to retrieve data of this type.
+/// Returns [`FetchStorage::Uninit`] if none of the impls registered in `state` are present.
+///
+/// This is the storage selection logic used by [`One`](crate::One), exposed so that
+/// custom query adapters don't have to duplicate it.
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy::ecs::{
+/// #     archetype::Archetype,
+/// #     component::{ComponentId, Components, Tick},
+/// #     query::{FilteredAccess, QueryData, ReadOnlyQueryData, WorldQuery},
+/// #     storage::{Table, TableRow},
+/// #     world::unsafe_world_cell::UnsafeWorldCell,
+/// # };
+/// # use bevy_trait_query::*;
+/// #
+/// /// Yields the first impl of a trait for entities with at least one impl.
+/// pub struct FirstImpl<Trait: ?Sized>(std::marker::PhantomData<&'static Trait>);
+///
+/// // SAFETY: We only read the components registered in `TraitQueryState`,
+/// // and read access is registered for all of them.
+/// unsafe impl<Trait: ?Sized + TraitQuery> WorldQuery for FirstImpl<Trait> {
+///     type Item<'w> = Ref<'w, Trait>;
+///     type Fetch<'w> = (&'w bevy::ecs::storage::SparseSets, FetchStorage<'w, Trait>, Tick, Tick);
+///     type State = TraitQueryState<Trait>;
+///
+///     fn shrink<'wlong: 'wshort, 'wshort>(item: Self::Item<'wlong>) -> Self::Item<'wshort> {
+///         item
+///     }
+///     fn shrink_fetch<'wlong: 'wshort, 'wshort>(fetch: Self::Fetch<'wlong>) -> Self::Fetch<'wshort> {
+///         fetch
+///     }
+///     unsafe fn init_fetch<'w>(
+///         world: UnsafeWorldCell<'w>,
+///         _state: &Self::State,
+///         last_run: Tick,
+///         this_run: Tick,
+///     ) -> Self::Fetch<'w> {
+///         (&world.storages().sparse_sets, FetchStorage::Uninit, last_run, this_run)
+///     }
+///     const IS_DENSE: bool = false;
+///     unsafe fn set_archetype<'w>(
+///         fetch: &mut Self::Fetch<'w>,
+///         state: &Self::State,
+///         archetype: &'w Archetype,
+///         table: &'w Table,
+///     ) {
+///         fetch.1 = select_one_storage(state, archetype, table, fetch.0);
+///     }
+///     unsafe fn set_table<'w>(_: &mut Self::Fetch<'w>, _: &Self::State, _: &'w Table) {
+///         unreachable!("`IS_DENSE` is false")
+///     }
+///     unsafe fn fetch<'w>(
+///         fetch: &mut Self::Fetch<'w>,
+///         entity: Entity,
+///         table_row: TableRow,
+///     ) -> Self::Item<'w> {
+///         fetch.1.fetch_ref(entity, table_row, fetch.2, fetch.3)
+///     }
+///     fn update_component_access(state: &Self::State, access: &mut FilteredAccess<ComponentId>) {
+///         // Reads any one of the registered components, just like `One`.
+///         <One<&Trait> as WorldQuery>::update_component_access(state, access);
+///     }
+///     fn init_state(world: &mut World) -> Self::State {
+///         TraitQueryState::init(world)
+///     }
+///     fn get_state(_: &Components) -> Option<Self::State> {
+///         None
+///     }
+///     fn matches_component_set(
+///         state: &Self::State,
+///         set_contains_id: &impl Fn(ComponentId) -> bool,
+///     ) -> bool {
+///         state.components().iter().any(|&c| set_contains_id(c))
+///     }
+/// }
+///
+/// unsafe impl<Trait: ?Sized + TraitQuery> QueryData for FirstImpl<Trait> {
+///     type ReadOnly = Self;
+/// }
+/// unsafe impl<Trait: ?Sized + TraitQuery> ReadOnlyQueryData for FirstImpl<Trait> {}
+/// ```
+///
+/// # Safety
+/// `table` must be the table corresponding to `archetype`,
+/// and `sparse_sets` must belong to the same world as `archetype`.
+#[inline]
+pub unsafe fn select_one_storage<'w, Trait: ?Sized + TraitQuery>(
+    state: &TraitQueryState<Trait>,
+    archetype: &'w Archetype,
+    table: &'w Table,
+    sparse_sets: &'w SparseSets,
+) -> FetchStorage<'w, Trait> {
+    // Search for a registered trait impl that is present in the archetype.
+    // We check the table components first since it is faster to retrieve data of this type.
+    if let Some(storage) = select_table_storage(state, table) {
+        return storage;
+    }
+    for (&component, &meta) in zip_exact(&*state.components, &*state.meta) {
+        if !archetype.contains(component) {
+            continue;
+        }
+        if let Some(sparse_set) = sparse_sets.get(component) {
+            return FetchStorage::SparseSet {
+                components: sparse_set,
+                meta,
+            };
+        }
+    }
+    FetchStorage::Uninit
+}
+
+/// Finds the storage of the first trait impl present in `table`, if any.
+///
+/// # Safety
+/// `table` must belong to the same world that `state` was initialized with.
+#[inline]
+pub(crate) unsafe fn select_table_storage<'w, Trait: ?Sized + TraitQuery>(
+    state: &TraitQueryState<Trait>,
+    table: &'w Table,
+) -> Option<FetchStorage<'w, Trait>> {
+    // without loss of generality we use the zero-th row since we only care about whether the
+    // component exists in the table
+    let row = TableRow::from_usize(0);
+    for (&component, &meta) in zip_exact(&*state.components, &*state.meta) {
+        if let Some((ptr, added, changed)) = table.get_component(component, row).and_then(|ptr| {
+            let added = table.get_added_ticks_slice_for(component)?;
+            let changed = table.get_changed_ticks_slice_for(component)?;
+            Some((ptr, added, changed))
+        }) {
+            #[cfg(debug_assertions)]
+            debug_check_size(table, component, &meta);
+            return Some(FetchStorage::Table {
+                column: ptr,
+                added_ticks: added.into(),
+                changed_ticks: changed.into(),
+                meta,
+            });
+        }
+    }
+    None
+}
+
+/// Checks that the registered size of a trait impl matches the item size of its table column.
+///
+/// Fetching from a table relies on this for pointer arithmetic, so a mismatch
+/// (such as from a faulty registration) would otherwise lead to undefined behavior.
+#[cfg(debug_assertions)]
+#[track_caller]
+fn debug_check_size<Trait: ?Sized>(
+    table: &Table,
+    component: bevy_ecs::component::ComponentId,
+    meta: &TraitImplMeta<Trait>,
+) {
+    let Some(column) = table.get_column(component) else {
+        return;
+    };
+    assert_eq!(
+        column.item_layout().size(),
+        meta.size_bytes,
+        "the registered size of {component:?} as an impl of `{}` does not match its actual size",
+        std::any::type_name::<Trait>(),
+    );
+}
+
+impl<'w, Trait: ?Sized + TraitQuery> OneTraitFetch<'w, Trait> {
+    /// Selects the storage of the first trait impl in `archetype`.
+    ///
+    /// # Safety
+    /// `table` must be the table corresponding to `archetype`.
+    #[inline]
+    pub(crate) unsafe fn set_archetype(
+        &mut self,
+        state: &TraitQueryState<Trait>,
+        archetype: &'w Archetype,
+        table: &'w Table,
+    ) {
+        self.storage = select_one_storage(state, archetype, table, self.sparse_sets);
+        #[cfg(feature = "paranoid")]
+        {
+            self.table_entity_count = table.entity_count();
+        }
+        if let FetchStorage::Uninit = self.storage {
+            // At least one of the components must be present in the table/sparse set.
+            debug_unreachable()
+        }
+    }
+
+    /// Selects the storage of the first trait impl in `table`.
+    ///
+    /// # Safety
+    /// `table` must belong to the same world that `state` was initialized with.
+    #[inline]
+    pub(crate) unsafe fn set_table(&mut self, state: &TraitQueryState<Trait>, table: &'w Table) {
+        #[cfg(feature = "paranoid")]
+        {
+            self.table_entity_count = table.entity_count();
+        }
+        // At least one of the components must be present in the table.
+        self.storage = select_table_storage(state, table).unwrap_or_else(|| debug_unreachable());
+    }
+}
+
+impl<'w, Trait: ?Sized> FetchStorage<'w, Trait> {
+    /// Returns the metadata of the selected trait impl.
+    ///
+    /// # Safety
+    /// A storage must have been selected, i.e. this must not be [`FetchStorage::Uninit`].
+    #[inline]
+    pub(crate) unsafe fn meta(self) -> TraitImplMeta<Trait> {
+        match self {
+            FetchStorage::Uninit => debug_unreachable(),
+            FetchStorage::Table { meta, .. } | FetchStorage::SparseSet { meta, .. } => meta,
+        }
+    }
+
+    /// Fetches the trait impl for the given entity with shared access.
+    ///
+    /// # Safety
+    /// - This storage must have been selected for the archetype containing `entity`,
+    ///   and `table_row` must be the row of `entity` in its table.
+    /// - Read access must have been registered for the component.
+    #[inline]
+    pub unsafe fn fetch_ref(
+        self,
+        entity: Entity,
+        table_row: TableRow,
+        last_run: Tick,
+        this_run: Tick,
+    ) -> Ref<'w, Trait> {
+        let table_row = table_row.as_usize();
+        let dyn_ctor;
+        let (ptr, added, changed) = match self {
+            // SAFETY: This function must have been called after `set_archetype`,
+            // so we know that `self.storage` has been initialized.
+            FetchStorage::Uninit => debug_unreachable(),
+            FetchStorage::Table {
+                column,
+                added_ticks,
+                changed_ticks,
+                meta,
+            } => {
+                dyn_ctor = meta.dyn_ctor;
+                let ptr = column.byte_add(table_row * meta.size_bytes);
+                (
+                    ptr,
+                    // SAFETY: We have read access to the component, so by extension
+                    // we have access to the corresponding `ComponentTicks`.
+                    added_ticks.get(table_row).deref(),
+                    changed_ticks.get(table_row).deref(),
+                )
+            }
+            FetchStorage::SparseSet { components, meta } => {
+                dyn_ctor = meta.dyn_ctor;
+                let (ptr, ticks, _) = components
+                    .get_with_ticks(entity)
+                    .unwrap_or_else(|| debug_unreachable());
+                (
+                    ptr,
+                    // SAFETY: We have read access to the component, so by extension
+                    // we have access to the corresponding `ComponentTicks`.
+                    ticks.added.deref(),
+                    ticks.changed.deref(),
+                )
+            }
+        };
+
+        Ref::new(dyn_ctor.cast(ptr), added, changed, last_run, this_run)
+    }
+
+    /// Fetches the trait impl for the given entity with shared access, without reading its ticks.
+    ///
+    /// # Safety
+    /// - This storage must have been selected for the archetype containing `entity`,
+    ///   and `table_row` must be the row of `entity` in its table.
+    /// - Read access must have been registered for the component.
+    #[inline]
+    pub unsafe fn fetch_plain(self, entity: Entity, table_row: TableRow) -> &'w Trait {
+        match self {
+            // SAFETY: This function must have been called after `set_archetype`,
+            // so we know that `self.storage` has been initialized.
+            FetchStorage::Uninit => debug_unreachable(),
+            FetchStorage::Table { column, meta, .. } => {
+                let ptr = column.byte_add(table_row.as_usize() * meta.size_bytes);
+                meta.dyn_ctor.cast(ptr)
+            }
+            FetchStorage::SparseSet { components, meta } => {
+                let ptr = components
+                    .get(entity)
+                    .unwrap_or_else(|| debug_unreachable());
+                meta.dyn_ctor.cast(ptr)
+            }
+        }
+    }
+
+    /// Fetches the trait impl for the given entity with exclusive access.
+    ///
+    /// # Safety
+    /// - This storage must have been selected for the archetype containing `entity`,
+    ///   and `table_row` must be the row of `entity` in its table.
+    /// - Write access must have been registered for the component,
+    ///   and this must not be called twice for the same entity while the result is alive.
+    #[inline]
+    pub unsafe fn fetch_mut(
+        self,
+        entity: Entity,
+        table_row: TableRow,
+        last_run: Tick,
+        this_run: Tick,
+    ) -> Mut<'w, Trait> {
+        let table_row = table_row.as_usize();
+        let dyn_ctor;
+        let (ptr, added, changed) = match self {
+            // SAFETY: This function must have been called after `set_archetype`,
+            // so we know that `self.storage` has been initialized.
+            FetchStorage::Uninit => debug_unreachable(),
+            FetchStorage::Table {
+                column,
+                added_ticks,
+                changed_ticks,
+                meta,
+            } => {
+                dyn_ctor = meta.dyn_ctor;
+                let ptr = column.byte_add(table_row * meta.size_bytes);
+                (
+                    // SAFETY: `column` allows for shared mutable access.
+                    // So long as the caller does not invoke this function twice with the same archetype_index,
+                    // this pointer will never be aliased.
+                    ptr.assert_unique(),
+                    // SAFETY: We have exclusive access to the component, so by extension
+                    // we have exclusive access to the corresponding `ComponentTicks`.
+                    added_ticks.get(table_row).deref_mut(),
+                    changed_ticks.get(table_row).deref_mut(),
+                )
+            }
+            FetchStorage::SparseSet { components, meta } => {
+                dyn_ctor = meta.dyn_ctor;
+                let (ptr, ticks, _) = components
+                    .get_with_ticks(entity)
+                    .unwrap_or_else(|| debug_unreachable());
+                (
+                    // SAFETY: We have exclusive access to the sparse set `components`.
+                    // So long as the caller does not invoke this function twice with the same archetype_index,
+                    // this pointer will never be aliased.
+                    ptr.assert_unique(),
+                    // SAFETY: We have exclusive access to the component, so by extension
+                    // we have exclusive access to the corresponding `ComponentTicks`.
+                    ticks.added.deref_mut(),
+                    ticks.changed.deref_mut(),
+                )
+            }
+        };
+
+        Mut::new(dyn_ctor.cast_mut(ptr), added, changed, last_run, this_run)
+    }
+}
@@ -1,11 +1,17 @@
 mod one;
 mod one_added;
 mod one_changed;
+mod one_plain;
+mod one_typed;
+mod with_any;
 mod with_one;
 mod without_any;
 
 pub use one::One;
 pub use one_added::OneAdded;
 pub use one_changed::OneChanged;
+pub use one_plain::OnePlain;
+pub use one_typed::{OneTyped, OneTypedItem};
+pub use with_any::WithAny;
 pub use with_one::WithOne;
 pub use without_any::WithoutAny;
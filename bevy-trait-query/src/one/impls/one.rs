@@ -1,17 +1,14 @@
 use bevy_ecs::change_detection::{Mut, Ref};
 use bevy_ecs::entity::Entity;
 use bevy_ecs::prelude::World;
-use bevy_ecs::ptr::UnsafeCellDeref;
 use bevy_ecs::{
     component::{ComponentId, Components, Tick},
-    query::{QueryData, QueryItem, ReadOnlyQueryData, WorldQuery},
+    query::{Access, QueryData, QueryItem, ReadOnlyQueryData, WorldQuery},
     storage::TableRow,
     world::unsafe_world_cell::UnsafeWorldCell,
 };
 
-use crate::{
-    debug_unreachable, one::FetchStorage, zip_exact, OneTraitFetch, TraitQuery, TraitQueryState,
-};
+use crate::{FetchStorage, OneTraitFetch, TraitQuery, TraitQueryState};
 
 /// [`WorldQuery`] adapter that fetches entities with exactly one component implementing a trait.
 ///
@@ -55,6 +52,8 @@ unsafe impl<Trait: ?Sized + TraitQuery> WorldQuery for One<&Trait> {
             last_run: Tick::new(0),
             sparse_sets: &world.storages().sparse_sets,
             this_run: Tick::new(0),
+            #[cfg(feature = "paranoid")]
+            table_entity_count: 0,
         }
     }
 
@@ -65,43 +64,10 @@ unsafe impl<Trait: ?Sized + TraitQuery> WorldQuery for One<&Trait> {
     unsafe fn set_archetype<'w>(
         fetch: &mut OneTraitFetch<'w, Trait>,
         state: &Self::State,
-        _archetype: &'w bevy_ecs::archetype::Archetype,
+        archetype: &'w bevy_ecs::archetype::Archetype,
         table: &'w bevy_ecs::storage::Table,
     ) {
-        // Search for a registered trait impl that is present in the archetype.
-        // We check the table components first since it is faster to retrieve data of this type.
-        //
-        // without loss of generality we use the zero-th row since we only care about whether the
-        // component exists in the table
-        let row = TableRow::from_usize(0);
-        for (&component, &meta) in zip_exact(&*state.components, &*state.meta) {
-            if let Some((ptr, added, changed)) =
-                table.get_component(component, row).and_then(|ptr| {
-                    let added = table.get_added_ticks_slice_for(component)?;
-                    let changed = table.get_changed_ticks_slice_for(component)?;
-                    Some((ptr, added, changed))
-                })
-            {
-                fetch.storage = FetchStorage::Table {
-                    column: ptr,
-                    added_ticks: added.into(),
-                    changed_ticks: changed.into(),
-                    meta,
-                };
-                return;
-            }
-        }
-        for (&component, &meta) in zip_exact(&*state.components, &*state.meta) {
-            if let Some(sparse_set) = fetch.sparse_sets.get(component) {
-                fetch.storage = FetchStorage::SparseSet {
-                    components: sparse_set,
-                    meta,
-                };
-                return;
-            }
-        }
-        // At least one of the components must be present in the table/sparse set.
-        debug_unreachable()
+        fetch.set_archetype(state, archetype, table);
     }
 
     #[inline]
@@ -110,29 +76,7 @@ unsafe impl<Trait: ?Sized + TraitQuery> WorldQuery for One<&Trait> {
         state: &Self::State,
         table: &'w bevy_ecs::storage::Table,
     ) {
-        // Search for a registered trait impl that is present in the table.
-        //
-        // without loss of generality we use the zero-th row since we only care about whether the
-        // component exists in the table
-        let row = TableRow::from_usize(0);
-        for (&component, &meta) in std::iter::zip(&*state.components, &*state.meta) {
-            if let Some((ptr, added, changed)) =
-                table.get_component(component, row).and_then(|ptr| {
-                    let added = table.get_added_ticks_slice_for(component)?;
-                    let changed = table.get_changed_ticks_slice_for(component)?;
-                    Some((ptr, added, changed))
-                })
-            {
-                fetch.storage = FetchStorage::Table {
-                    column: ptr,
-                    added_ticks: added.into(),
-                    changed_ticks: changed.into(),
-                    meta,
-                }
-            }
-        }
-        // At least one of the components must be present in the table.
-        debug_unreachable()
+        fetch.set_table(state, table);
     }
 
     #[inline]
@@ -141,50 +85,11 @@ unsafe impl<Trait: ?Sized + TraitQuery> WorldQuery for One<&Trait> {
         entity: Entity,
         table_row: TableRow,
     ) -> Self::Item<'w> {
-        let table_row = table_row.as_usize();
-        let dyn_ctor;
-        let (ptr, added, changed) = match fetch.storage {
-            // SAFETY: This function must have been called after `set_archetype`,
-            // so we know that `self.storage` has been initialized.
-            FetchStorage::Uninit => debug_unreachable(),
-            FetchStorage::Table {
-                column,
-                added_ticks,
-                changed_ticks,
-                meta,
-            } => {
-                dyn_ctor = meta.dyn_ctor;
-                let ptr = column.byte_add(table_row * meta.size_bytes);
-                (
-                    ptr,
-                    // SAFETY: We have read access to the component, so by extension
-                    // we have access to the corresponding `ComponentTicks`.
-                    added_ticks.get(table_row).deref(),
-                    changed_ticks.get(table_row).deref(),
-                )
-            }
-            FetchStorage::SparseSet { components, meta } => {
-                dyn_ctor = meta.dyn_ctor;
-                let (ptr, ticks, _) = components
-                    .get_with_ticks(entity)
-                    .unwrap_or_else(|| debug_unreachable());
-                (
-                    ptr,
-                    // SAFETY: We have read access to the component, so by extension
-                    // we have access to the corresponding `ComponentTicks`.
-                    ticks.added.deref(),
-                    ticks.changed.deref(),
-                )
-            }
-        };
-
-        Ref::new(
-            dyn_ctor.cast(ptr),
-            added,
-            changed,
-            fetch.last_run,
-            fetch.this_run,
-        )
+        #[cfg(feature = "paranoid")]
+        crate::paranoid_check_row(fetch.table_entity_count, table_row);
+        fetch
+            .storage
+            .fetch_ref(entity, table_row, fetch.last_run, fetch.this_run)
     }
 
     #[inline]
@@ -193,6 +98,10 @@ unsafe impl<Trait: ?Sized + TraitQuery> WorldQuery for One<&Trait> {
         access: &mut bevy_ecs::query::FilteredAccess<ComponentId>,
     ) {
         let mut new_access = access.clone();
+        // Each branch of the `OR` only needs the filters of the original access,
+        // so avoid copying its component access for every registered impl.
+        let mut filters = access.clone();
+        *filters.access_mut() = Access::default();
         let mut not_first = false;
         for &component in &*state.components {
             assert!(
@@ -201,15 +110,14 @@ unsafe impl<Trait: ?Sized + TraitQuery> WorldQuery for One<&Trait> {
                 std::any::type_name::<Trait>(),
             );
             if not_first {
-                let mut intermediate = access.clone();
-                intermediate.add_component_read(component);
+                let mut intermediate = filters.clone();
+                intermediate.and_with(component);
                 new_access.append_or(&intermediate);
-                new_access.extend_access(&intermediate);
             } else {
                 new_access.and_with(component);
-                new_access.access_mut().add_component_read(component);
                 not_first = true;
             }
+            new_access.access_mut().add_component_read(component);
         }
         *access = new_access;
     }
@@ -263,6 +171,8 @@ unsafe impl<Trait: ?Sized + TraitQuery> WorldQuery for One<&mut Trait> {
             sparse_sets: &world.storages().sparse_sets,
             last_run,
             this_run,
+            #[cfg(feature = "paranoid")]
+            table_entity_count: 0,
         }
     }
 
@@ -272,42 +182,10 @@ unsafe impl<Trait: ?Sized + TraitQuery> WorldQuery for One<&mut Trait> {
     unsafe fn set_archetype<'w>(
         fetch: &mut OneTraitFetch<'w, Trait>,
         state: &Self::State,
-        _archetype: &'w bevy_ecs::archetype::Archetype,
+        archetype: &'w bevy_ecs::archetype::Archetype,
         table: &'w bevy_ecs::storage::Table,
     ) {
-        // Search for a registered trait impl that is present in the archetype.
-        //
-        // without loss of generality we use the zero-th row since we only care about whether the
-        // component exists in the table
-        let row = TableRow::from_usize(0);
-        for (&component, &meta) in zip_exact(&*state.components, &*state.meta) {
-            if let Some((ptr, added, changed)) =
-                table.get_component(component, row).and_then(|ptr| {
-                    let added = table.get_added_ticks_slice_for(component)?;
-                    let changed = table.get_changed_ticks_slice_for(component)?;
-                    Some((ptr, added, changed))
-                })
-            {
-                fetch.storage = FetchStorage::Table {
-                    column: ptr,
-                    added_ticks: added.into(),
-                    changed_ticks: changed.into(),
-                    meta,
-                };
-                return;
-            }
-        }
-        for (&component, &meta) in zip_exact(&*state.components, &*state.meta) {
-            if let Some(sparse_set) = fetch.sparse_sets.get(component) {
-                fetch.storage = FetchStorage::SparseSet {
-                    components: sparse_set,
-                    meta,
-                };
-                return;
-            }
-        }
-        // At least one of the components must be present in the table/sparse set.
-        debug_unreachable()
+        fetch.set_archetype(state, archetype, table);
     }
 
     #[inline]
@@ -316,30 +194,7 @@ unsafe impl<Trait: ?Sized + TraitQuery> WorldQuery for One<&mut Trait> {
         state: &Self::State,
         table: &'w bevy_ecs::storage::Table,
     ) {
-        // Search for a registered trait impl that is present in the table.
-        //
-        // without loss of generality we use the zero-th row since we only care about whether the
-        // component exists in the table
-        let row = TableRow::from_usize(0);
-        for (&component, &meta) in std::iter::zip(&*state.components, &*state.meta) {
-            if let Some((ptr, added, changed)) =
-                table.get_component(component, row).and_then(|ptr| {
-                    let added = table.get_added_ticks_slice_for(component)?;
-                    let changed = table.get_changed_ticks_slice_for(component)?;
-                    Some((ptr, added, changed))
-                })
-            {
-                fetch.storage = FetchStorage::Table {
-                    column: ptr,
-                    added_ticks: added.into(),
-                    changed_ticks: changed.into(),
-                    meta,
-                };
-                return;
-            }
-        }
-        // At least one of the components must be present in the table.
-        debug_unreachable()
+        fetch.set_table(state, table);
     }
 
     #[inline]
@@ -348,56 +203,11 @@ unsafe impl<Trait: ?Sized + TraitQuery> WorldQuery for One<&mut Trait> {
         entity: Entity,
         table_row: TableRow,
     ) -> Mut<'w, Trait> {
-        let table_row = table_row.as_usize();
-        let dyn_ctor;
-        let (ptr, added, changed) = match fetch.storage {
-            // SAFETY: This function must have been called after `set_archetype`,
-            // so we know that `self.storage` has been initialized.
-            FetchStorage::Uninit => debug_unreachable(),
-            FetchStorage::Table {
-                column,
-                added_ticks,
-                changed_ticks,
-                meta,
-            } => {
-                dyn_ctor = meta.dyn_ctor;
-                let ptr = column.byte_add(table_row * meta.size_bytes);
-                (
-                    // SAFETY: `column` allows for shared mutable access.
-                    // So long as the caller does not invoke this function twice with the same archetype_index,
-                    // this pointer will never be aliased.
-                    ptr.assert_unique(),
-                    // SAFETY: We have exclusive access to the component, so by extension
-                    // we have exclusive access to the corresponding `ComponentTicks`.
-                    added_ticks.get(table_row).deref_mut(),
-                    changed_ticks.get(table_row).deref_mut(),
-                )
-            }
-            FetchStorage::SparseSet { components, meta } => {
-                dyn_ctor = meta.dyn_ctor;
-                let (ptr, ticks, _) = components
-                    .get_with_ticks(entity)
-                    .unwrap_or_else(|| debug_unreachable());
-                (
-                    // SAFETY: We have exclusive access to the sparse set `components`.
-                    // So long as the caller does not invoke this function twice with the same archetype_index,
-                    // this pointer will never be aliased.
-                    ptr.assert_unique(),
-                    // SAFETY: We have exclusive access to the component, so by extension
-                    // we have exclusive access to the corresponding `ComponentTicks`.
-                    ticks.added.deref_mut(),
-                    ticks.changed.deref_mut(),
-                )
-            }
-        };
-
-        Mut::new(
-            dyn_ctor.cast_mut(ptr),
-            added,
-            changed,
-            fetch.last_run,
-            fetch.this_run,
-        )
+        #[cfg(feature = "paranoid")]
+        crate::paranoid_check_row(fetch.table_entity_count, table_row);
+        fetch
+            .storage
+            .fetch_mut(entity, table_row, fetch.last_run, fetch.this_run)
     }
 
     #[inline]
@@ -406,23 +216,26 @@ unsafe impl<Trait: ?Sized + TraitQuery> WorldQuery for One<&mut Trait> {
         access: &mut bevy_ecs::query::FilteredAccess<ComponentId>,
     ) {
         let mut new_access = access.clone();
+        // Each branch of the `OR` only needs the filters of the original access,
+        // so avoid copying its component access for every registered impl.
+        let mut filters = access.clone();
+        *filters.access_mut() = Access::default();
         let mut not_first = false;
         for &component in &*state.components {
             assert!(
-                !access.access().has_component_write(component),
+                !access.access().has_component_read(component),
                 "&mut {} conflicts with a previous access in this query. Mutable component access must be unique.",
                 std::any::type_name::<Trait>(),
             );
             if not_first {
-                let mut intermediate = access.clone();
-                intermediate.add_component_write(component);
+                let mut intermediate = filters.clone();
+                intermediate.and_with(component);
                 new_access.append_or(&intermediate);
-                new_access.extend_access(&intermediate);
             } else {
                 new_access.and_with(component);
-                new_access.access_mut().add_component_write(component);
                 not_first = true;
             }
+            new_access.access_mut().add_component_write(component);
         }
         *access = new_access;
     }
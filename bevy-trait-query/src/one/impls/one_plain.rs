@@ -0,0 +1,136 @@
+use bevy_ecs::entity::Entity;
+use bevy_ecs::prelude::World;
+use bevy_ecs::{
+    component::{ComponentId, Components, Tick},
+    query::{QueryData, QueryItem, ReadOnlyQueryData, WorldQuery},
+    storage::TableRow,
+    world::unsafe_world_cell::UnsafeWorldCell,
+};
+
+use crate::{FetchStorage, One, OneTraitFetch, TraitQuery, TraitQueryState};
+
+/// [`WorldQuery`] adapter that fetches entities with exactly one component implementing a trait,
+/// without change detection.
+///
+/// This behaves like [`One<&dyn Trait>`](One), but yields a plain `&dyn Trait` instead of a
+/// [`Ref`](bevy_ecs::change_detection::Ref), so no change ticks are read. This makes it slightly
+/// faster for read-only systems that don't need change detection.
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_trait_query::*;
+/// #
+/// # #[bevy_trait_query::queryable]
+/// # pub trait Tooltip {
+/// #     fn tooltip(&self) -> &str;
+/// # }
+/// #
+/// fn show_tooltips(tooltips: Query<OnePlain<&dyn Tooltip>>) {
+///     for tooltip in &tooltips {
+///         println!("Tooltip: {}", tooltip.tooltip());
+///     }
+/// }
+/// # bevy_ecs::system::assert_is_system(show_tooltips);
+/// ```
+pub struct OnePlain<T>(pub T);
+
+unsafe impl<T: ?Sized + TraitQuery> QueryData for OnePlain<&T> {
+    type ReadOnly = Self;
+}
+unsafe impl<T: ?Sized + TraitQuery> ReadOnlyQueryData for OnePlain<&T> {}
+
+// SAFETY: We only access the components registered in TraitQueryState.
+// Archetype matching and world access are the same as `One<&Trait>`, which this delegates to.
+unsafe impl<Trait: ?Sized + TraitQuery> WorldQuery for OnePlain<&Trait> {
+    type Item<'w> = &'w Trait;
+    type Fetch<'w> = OneTraitFetch<'w, Trait>;
+    type State = TraitQueryState<Trait>;
+
+    #[inline]
+    fn shrink<'wlong: 'wshort, 'wshort>(item: QueryItem<'wlong, Self>) -> QueryItem<'wshort, Self> {
+        item
+    }
+
+    #[inline]
+    unsafe fn init_fetch<'w>(
+        world: UnsafeWorldCell<'w>,
+        _state: &Self::State,
+        _last_run: Tick,
+        _this_run: Tick,
+    ) -> OneTraitFetch<'w, Trait> {
+        OneTraitFetch {
+            storage: FetchStorage::Uninit,
+            sparse_sets: &world.storages().sparse_sets,
+            // Ticks are never read by this query.
+            last_run: Tick::new(0),
+            this_run: Tick::new(0),
+            #[cfg(feature = "paranoid")]
+            table_entity_count: 0,
+        }
+    }
+
+    const IS_DENSE: bool = false;
+
+    #[inline]
+    unsafe fn set_archetype<'w>(
+        fetch: &mut OneTraitFetch<'w, Trait>,
+        state: &Self::State,
+        archetype: &'w bevy_ecs::archetype::Archetype,
+        table: &'w bevy_ecs::storage::Table,
+    ) {
+        fetch.set_archetype(state, archetype, table);
+    }
+
+    #[inline]
+    unsafe fn set_table<'w>(
+        fetch: &mut OneTraitFetch<'w, Trait>,
+        state: &Self::State,
+        table: &'w bevy_ecs::storage::Table,
+    ) {
+        fetch.set_table(state, table);
+    }
+
+    #[inline]
+    unsafe fn fetch<'w>(
+        fetch: &mut Self::Fetch<'w>,
+        entity: Entity,
+        table_row: TableRow,
+    ) -> Self::Item<'w> {
+        #[cfg(feature = "paranoid")]
+        crate::paranoid_check_row(fetch.table_entity_count, table_row);
+        fetch.storage.fetch_plain(entity, table_row)
+    }
+
+    #[inline]
+    fn update_component_access(
+        state: &Self::State,
+        access: &mut bevy_ecs::query::FilteredAccess<ComponentId>,
+    ) {
+        // Register the same read access as `One`, so that conflicts are detected the same way.
+        <One<&Trait> as WorldQuery>::update_component_access(state, access);
+    }
+
+    #[inline]
+    fn init_state(world: &mut World) -> Self::State {
+        TraitQueryState::init(world)
+    }
+
+    #[inline]
+    fn get_state(_: &Components) -> Option<Self::State> {
+        // TODO: fix this https://github.com/bevyengine/bevy/issues/13798
+        panic!("transmuting and any other operations concerning the state of a query are currently broken and shouldn't be used. See https://github.com/JoJoJet/bevy-trait-query/issues/59");
+    }
+
+    #[inline]
+    fn matches_component_set(
+        state: &Self::State,
+        set_contains_id: &impl Fn(ComponentId) -> bool,
+    ) -> bool {
+        state.matches_component_set_one(set_contains_id)
+    }
+
+    #[inline]
+    fn shrink_fetch<'wlong: 'wshort, 'wshort>(fetch: Self::Fetch<'wlong>) -> Self::Fetch<'wshort> {
+        fetch
+    }
+}
@@ -0,0 +1,224 @@
+use std::any::TypeId;
+
+use bevy_ecs::change_detection::Ref;
+use bevy_ecs::entity::Entity;
+use bevy_ecs::prelude::World;
+use bevy_ecs::{
+    component::{ComponentId, Components, Tick},
+    query::{QueryData, QueryItem, ReadOnlyQueryData, WorldQuery},
+    storage::TableRow,
+    world::unsafe_world_cell::UnsafeWorldCell,
+};
+
+use crate::{debug_unreachable, FetchStorage, One, OneTraitFetch, TraitQuery, TraitQueryState};
+
+/// [`WorldQuery`] adapter that fetches entities with exactly one component implementing a trait,
+/// along with the [`TypeId`] of that component.
+///
+/// This behaves like [`One<&dyn Trait>`](One), but also yields the concrete type that implements
+/// the trait as part of a [`OneTypedItem`], so that systems can branch on it or downcast to it.
+/// The [`TypeId`] is looked up once per archetype,
+/// so this is as fast as [`One`].
+///
+/// Components registered with
+/// [`register_component_as_dynamic`](crate::TraitRegistryExt::register_component_as_dynamic)
+/// which have no corresponding Rust type are never matched by this query.
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_trait_query::*;
+/// #
+/// # #[bevy_trait_query::queryable]
+/// # pub trait Tooltip {
+/// #     fn tooltip(&self) -> &str;
+/// # }
+/// #
+/// #[derive(Component)]
+/// struct Warning(String);
+/// # impl Tooltip for Warning {
+/// #     fn tooltip(&self) -> &str {
+/// #         &self.0
+/// #     }
+/// # }
+///
+/// fn show_tooltips(tooltips: Query<OneTyped<&dyn Tooltip>>) {
+///     for tooltip in &tooltips {
+///         if tooltip.is::<Warning>() {
+///             println!("Warning: {}", tooltip.tooltip());
+///         } else {
+///             println!("Tooltip: {}", tooltip.tooltip());
+///         }
+///     }
+/// }
+/// # bevy_ecs::system::assert_is_system(show_tooltips);
+/// ```
+pub struct OneTyped<T>(pub T);
+
+/// The item yielded by [`OneTyped`]: a trait object along with the concrete type behind it.
+///
+/// This derefs to the trait object, and can be safely downcast to its concrete component.
+pub struct OneTypedItem<'w, Trait: ?Sized> {
+    type_id: TypeId,
+    item: Ref<'w, Trait>,
+}
+
+impl<'w, Trait: ?Sized> OneTypedItem<'w, Trait> {
+    /// Returns the [`TypeId`] of the component behind this trait object.
+    #[inline]
+    pub fn type_id(&self) -> TypeId {
+        self.type_id
+    }
+
+    /// Returns `true` if the component behind this trait object is of type `C`.
+    #[inline]
+    pub fn is<C: 'static>(&self) -> bool {
+        self.type_id == TypeId::of::<C>()
+    }
+
+    /// Returns the concrete component behind this trait object,
+    /// or `None` if it is not of type `C`.
+    #[inline]
+    pub fn downcast<C: 'static>(self) -> Option<Ref<'w, C>> {
+        if !self.is::<C>() {
+            return None;
+        }
+        // SAFETY: `type_id` was recorded when the component was registered for `Trait`,
+        // and the trait object points to that component, so it points to a value of type `C`.
+        Some(
+            self.item
+                .map(|item| unsafe { &*(item as *const Trait as *const C) }),
+        )
+    }
+
+    /// Returns the underlying [`Ref`] to the trait object.
+    #[inline]
+    pub fn into_inner(self) -> Ref<'w, Trait> {
+        self.item
+    }
+}
+
+impl<Trait: ?Sized> std::ops::Deref for OneTypedItem<'_, Trait> {
+    type Target = Trait;
+
+    #[inline]
+    fn deref(&self) -> &Trait {
+        &self.item
+    }
+}
+
+unsafe impl<T: ?Sized + TraitQuery> QueryData for OneTyped<&T> {
+    type ReadOnly = Self;
+}
+unsafe impl<T: ?Sized + TraitQuery> ReadOnlyQueryData for OneTyped<&T> {}
+
+// SAFETY: We only access the components registered in TraitQueryState.
+// World access is the same as `One<&Trait>`, which this delegates to,
+// and this only matches a subset of the archetypes that `One<&Trait>` matches.
+unsafe impl<Trait: ?Sized + TraitQuery> WorldQuery for OneTyped<&Trait> {
+    type Item<'w> = OneTypedItem<'w, Trait>;
+    type Fetch<'w> = OneTraitFetch<'w, Trait>;
+    type State = TraitQueryState<Trait>;
+
+    #[inline]
+    fn shrink<'wlong: 'wshort, 'wshort>(item: QueryItem<'wlong, Self>) -> QueryItem<'wshort, Self> {
+        item
+    }
+
+    #[inline]
+    unsafe fn init_fetch<'w>(
+        world: UnsafeWorldCell<'w>,
+        _state: &Self::State,
+        last_run: Tick,
+        this_run: Tick,
+    ) -> OneTraitFetch<'w, Trait> {
+        OneTraitFetch {
+            storage: FetchStorage::Uninit,
+            sparse_sets: &world.storages().sparse_sets,
+            last_run,
+            this_run,
+            #[cfg(feature = "paranoid")]
+            table_entity_count: 0,
+        }
+    }
+
+    const IS_DENSE: bool = false;
+
+    #[inline]
+    unsafe fn set_archetype<'w>(
+        fetch: &mut OneTraitFetch<'w, Trait>,
+        state: &Self::State,
+        archetype: &'w bevy_ecs::archetype::Archetype,
+        table: &'w bevy_ecs::storage::Table,
+    ) {
+        fetch.set_archetype(state, archetype, table);
+    }
+
+    #[inline]
+    unsafe fn set_table<'w>(
+        fetch: &mut OneTraitFetch<'w, Trait>,
+        state: &Self::State,
+        table: &'w bevy_ecs::storage::Table,
+    ) {
+        fetch.set_table(state, table);
+    }
+
+    #[inline]
+    unsafe fn fetch<'w>(
+        fetch: &mut Self::Fetch<'w>,
+        entity: Entity,
+        table_row: TableRow,
+    ) -> Self::Item<'w> {
+        #[cfg(feature = "paranoid")]
+        crate::paranoid_check_row(fetch.table_entity_count, table_row);
+        // SAFETY: `matches_component_set` only matches archetypes whose impl has a `TypeId`.
+        let type_id = fetch
+            .storage
+            .meta()
+            .type_id
+            .unwrap_or_else(|| debug_unreachable());
+        let item = fetch
+            .storage
+            .fetch_ref(entity, table_row, fetch.last_run, fetch.this_run);
+        OneTypedItem { type_id, item }
+    }
+
+    #[inline]
+    fn update_component_access(
+        state: &Self::State,
+        access: &mut bevy_ecs::query::FilteredAccess<ComponentId>,
+    ) {
+        <One<&Trait> as WorldQuery>::update_component_access(state, access);
+    }
+
+    #[inline]
+    fn init_state(world: &mut World) -> Self::State {
+        TraitQueryState::init(world)
+    }
+
+    #[inline]
+    fn get_state(_: &Components) -> Option<Self::State> {
+        // TODO: fix this https://github.com/bevyengine/bevy/issues/13798
+        panic!("transmuting and any other operations concerning the state of a query are currently broken and shouldn't be used. See https://github.com/JoJoJet/bevy-trait-query/issues/59");
+    }
+
+    #[inline]
+    fn matches_component_set(
+        state: &Self::State,
+        set_contains_id: &impl Fn(ComponentId) -> bool,
+    ) -> bool {
+        let mut present = state
+            .components
+            .iter()
+            .zip(&*state.meta)
+            .filter(|&(&component, _)| set_contains_id(component));
+        matches!(
+            (present.next(), present.next()),
+            (Some((_, meta)), None) if meta.type_id.is_some()
+        )
+    }
+
+    #[inline]
+    fn shrink_fetch<'wlong: 'wshort, 'wshort>(fetch: Self::Fetch<'wlong>) -> Self::Fetch<'wshort> {
+        fetch
+    }
+}
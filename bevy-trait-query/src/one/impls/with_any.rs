@@ -0,0 +1,136 @@
+use std::marker::PhantomData;
+
+use bevy_ecs::{
+    component::{ComponentId, Components, Tick},
+    prelude::{Entity, World},
+    query::{ArchetypeFilter, QueryFilter, QueryItem, WorldQuery},
+    storage::TableRow,
+    world::unsafe_world_cell::UnsafeWorldCell,
+};
+
+use crate::{TraitQuery, TraitQueryState};
+
+/// [`WorldQuery`] filter for entities with at least one component implementing a trait.
+///
+/// This is the filter counterpart of [`All`](crate::All), and the opposite of
+/// [`WithoutAny`](crate::WithoutAny). Unlike [`WithOne`](crate::WithOne), entities with
+/// several impls of the trait are also matched.
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_trait_query::*;
+/// #
+/// # #[bevy_trait_query::queryable]
+/// # pub trait Tooltip {
+/// #     fn tooltip(&self) -> &str;
+/// # }
+/// #
+/// fn count_hoverable(hoverable: Query<Entity, WithAny<dyn Tooltip>>) {
+///     println!("{} entities have tooltips", hoverable.iter().count());
+/// }
+/// # bevy_ecs::system::assert_is_system(count_hoverable);
+/// ```
+pub struct WithAny<Trait: ?Sized + TraitQuery>(PhantomData<&'static Trait>);
+
+// SAFETY: This does not access any component data.
+unsafe impl<Trait: ?Sized + TraitQuery> WorldQuery for WithAny<Trait> {
+    type Item<'w> = ();
+    type Fetch<'w> = ();
+    type State = TraitQueryState<Trait>;
+
+    #[inline]
+    fn shrink<'wlong: 'wshort, 'wshort>(item: QueryItem<'wlong, Self>) -> QueryItem<'wshort, Self> {
+        item
+    }
+
+    #[inline]
+    unsafe fn init_fetch(
+        _world: UnsafeWorldCell<'_>,
+        _state: &Self::State,
+        _last_run: Tick,
+        _this_run: Tick,
+    ) {
+    }
+
+    const IS_DENSE: bool = false;
+
+    #[inline]
+    unsafe fn set_archetype<'w>(
+        _fetch: &mut (),
+        _state: &Self::State,
+        _archetype: &'w bevy_ecs::archetype::Archetype,
+        _table: &'w bevy_ecs::storage::Table,
+    ) {
+    }
+
+    #[inline]
+    unsafe fn set_table(_fetch: &mut (), _state: &Self::State, _table: &bevy_ecs::storage::Table) {}
+
+    #[inline]
+    unsafe fn fetch<'w>(
+        _fetch: &mut Self::Fetch<'w>,
+        _entity: Entity,
+        _table_row: TableRow,
+    ) -> Self::Item<'w> {
+    }
+
+    #[inline]
+    fn update_component_access(
+        state: &Self::State,
+        access: &mut bevy_ecs::query::FilteredAccess<ComponentId>,
+    ) {
+        // The first impl constrains the existing access,
+        // and each of the others adds another branch to the `OR`.
+        let mut new_access = access.clone();
+        let mut not_first = false;
+        for &component in &*state.components {
+            if not_first {
+                let mut intermediate = access.clone();
+                intermediate.and_with(component);
+                new_access.append_or(&intermediate);
+            } else {
+                new_access.and_with(component);
+                not_first = true;
+            }
+        }
+        *access = new_access;
+    }
+
+    #[inline]
+    fn init_state(world: &mut World) -> Self::State {
+        TraitQueryState::init(world)
+    }
+
+    #[inline]
+    fn get_state(_: &Components) -> Option<Self::State> {
+        // TODO: fix this https://github.com/bevyengine/bevy/issues/13798
+        panic!("transmuting and any other operations concerning the state of a query are currently broken and shouldn't be used. See https://github.com/JoJoJet/bevy-trait-query/issues/59");
+    }
+
+    #[inline]
+    fn matches_component_set(
+        state: &Self::State,
+        set_contains_id: &impl Fn(ComponentId) -> bool,
+    ) -> bool {
+        state.matches_component_set_any(set_contains_id)
+    }
+
+    #[inline]
+    fn shrink_fetch<'wlong: 'wshort, 'wshort>(fetch: Self::Fetch<'wlong>) -> Self::Fetch<'wshort> {
+        fetch
+    }
+}
+
+/// SAFETY: read-only access
+unsafe impl<Trait: ?Sized + TraitQuery> QueryFilter for WithAny<Trait> {
+    const IS_ARCHETYPAL: bool = true;
+    unsafe fn filter_fetch(
+        _fetch: &mut Self::Fetch<'_>,
+        _entity: Entity,
+        _table_row: TableRow,
+    ) -> bool {
+        true
+    }
+}
+
+impl<Trait: ?Sized + TraitQuery> ArchetypeFilter for WithAny<Trait> {}
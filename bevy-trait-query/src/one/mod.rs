@@ -3,5 +3,8 @@ mod impls;
 
 pub use impls::*;
 
-pub use core::{change_detection::ChangeDetectionFetch, fetch::OneTraitFetch};
-pub(crate) use core::{change_detection::ChangeDetectionStorage, fetch::FetchStorage};
+pub(crate) use core::change_detection::ChangeDetectionStorage;
+pub use core::{
+    change_detection::ChangeDetectionFetch,
+    fetch::{select_one_storage, FetchStorage, OneTraitFetch},
+};
@@ -0,0 +1,400 @@
+use std::{collections::HashMap, fmt};
+
+use bevy_ecs::{
+    change_detection::{Mut, Ref},
+    component::ComponentId,
+    entity::{Entity, EntityHashMap},
+    event::{Event, EventWriter},
+    query::{QueryData, QueryFilter, QueryState, WorldQuery},
+    system::{Commands, Query},
+    world::World,
+};
+
+use crate::{ReadTraits, TraitQuery, WriteTraits};
+
+#[cfg(feature = "bevy_hierarchy")]
+use bevy_hierarchy::{Children, HierarchyQueryExt};
+
+/// Extension methods for queries over all the impls of a trait,
+/// such as `Query<&dyn Trait>` or `Query<&mut dyn Trait>`.
+pub trait DynQueryExt<Trait: ?Sized + TraitQuery> {
+    /// Collects every matched entity along with all of its trait impls.
+    ///
+    /// Note that the items of a trait query only borrow the world, not the query iterator,
+    /// so `query.iter().collect::<Vec<ReadTraits<_>>>()` works as well.
+    /// This method additionally materializes the impls of each entity, which is convenient
+    /// when the results need to be inspected more than once.
+    fn collect_all(&self) -> Vec<(Entity, Vec<Ref<'_, Trait>>)>;
+
+    /// Iterates over every matched entity along with its trait impls.
+    fn iter_with_entity(&self) -> impl Iterator<Item = (Entity, ReadTraits<'_, Trait>)>;
+
+    /// Iterates over every trait impl of every matched entity, along with an index
+    /// and the entity it belongs to.
+    ///
+    /// Unlike the position in [`Query::iter`], which depends on how entities are laid out
+    /// in storage, impls are indexed in order of their entity. This means that the index
+    /// of an impl doesn't change between frames, as long as no impls are added or removed
+    /// on entities that come before it.
+    ///
+    /// All of the impls are collected and sorted before iteration starts.
+    fn iter_indexed(&self) -> impl Iterator<Item = (usize, Entity, Ref<'_, Trait>)>;
+
+    /// Iterates over the first `n` trait impls among all matched entities.
+    ///
+    /// This stops as soon as `n` impls have been visited, without fetching the rest
+    /// of the matched entities.
+    fn take_impls(&self, n: usize) -> impl Iterator<Item = Ref<'_, Trait>>;
+
+    /// Collects the trait impls of every matched entity, grouped by their component.
+    fn group_by_component(&self) -> HashMap<ComponentId, Vec<Ref<'_, Trait>>>;
+
+    /// Builds a map from each matched entity to its first trait impl.
+    ///
+    /// This is meant for traits with at most one impl per entity. For entities with multiple impls,
+    /// the first one is used, in the same order as [`ReadTraits::iter`]; the rest are ignored.
+    fn map_one(&self) -> EntityHashMap<Ref<'_, Trait>>;
+
+    /// Iterates over every matched entity along with the number of trait impls it has.
+    fn impl_counts(&self) -> impl Iterator<Item = (Entity, usize)>;
+
+    /// Iterates over the trait impls of `root` and each of its descendants,
+    /// along with the entity that each impl belongs to.
+    ///
+    /// The hierarchy is walked breadth-first using `children`, starting with `root` itself.
+    /// Entities in the hierarchy that are not matched by this query are skipped,
+    /// but their descendants are still visited.
+    ///
+    /// ```
+    /// # use bevy::prelude::*;
+    /// # use bevy_trait_query::*;
+    /// #
+    /// # #[bevy_trait_query::queryable]
+    /// # pub trait Tooltip {
+    /// #     fn tooltip(&self) -> &str;
+    /// # }
+    /// #
+    /// # #[derive(Component)]
+    /// # struct Panel;
+    /// #
+    /// fn print_panel_tooltips(
+    ///     panels: Query<Entity, With<Panel>>,
+    ///     tooltips: Query<&dyn Tooltip>,
+    ///     children: Query<&Children>,
+    /// ) {
+    ///     for panel in &panels {
+    ///         for (entity, tooltip) in tooltips.iter_descendant_traits(panel, &children) {
+    ///             println!("{entity}: {}", tooltip.tooltip());
+    ///         }
+    ///     }
+    /// }
+    /// # bevy_ecs::system::assert_is_system(print_panel_tooltips);
+    /// ```
+    #[cfg(feature = "bevy_hierarchy")]
+    fn iter_descendant_traits<'a>(
+        &'a self,
+        root: Entity,
+        children: &'a Query<&Children>,
+    ) -> impl Iterator<Item = (Entity, Ref<'a, Trait>)> + 'a;
+
+    /// Calls `f` for every trait impl of every matched entity.
+    ///
+    /// Since `f` is an `FnMut`, it can capture other system params mutably,
+    /// such as `Gizmos` for a trait with a debug drawing method:
+    ///
+    /// ```
+    /// # use bevy::prelude::*;
+    /// # use bevy_trait_query::*;
+    /// #
+    /// # #[derive(Resource, Default)]
+    /// # struct DebugLines(Vec<(Vec2, Vec2)>);
+    /// #
+    /// #[bevy_trait_query::queryable]
+    /// pub trait Debuggable {
+    ///     fn debug_draw(&self, lines: &mut DebugLines);
+    /// }
+    ///
+    /// fn draw_debuggables(debuggables: Query<&dyn Debuggable>, mut lines: ResMut<DebugLines>) {
+    ///     debuggables.for_each_trait(|debuggable| debuggable.debug_draw(&mut lines));
+    /// }
+    /// # bevy_ecs::system::assert_is_system(draw_debuggables);
+    /// ```
+    fn for_each_trait(&self, f: impl FnMut(&Trait));
+
+    /// Calls `f` for every trait impl of every matched entity, sending each event that it returns.
+    fn write_events<E: Event>(
+        &self,
+        writer: &mut EventWriter<E>,
+        f: impl FnMut(Entity, &Trait) -> Option<E>,
+    );
+
+    /// Queues the removal of every trait impl of every matched entity for which `pred`
+    /// returns `false`, such as to clean up components that are no longer alive.
+    ///
+    /// Only the failing components are removed, so other impls on the same entity are kept.
+    /// The removals take effect once `commands` are applied.
+    ///
+    /// ```
+    /// # use bevy::prelude::*;
+    /// # use bevy_trait_query::*;
+    /// #
+    /// #[bevy_trait_query::queryable]
+    /// pub trait Alive {
+    ///     fn is_alive(&self) -> bool;
+    /// }
+    ///
+    /// fn remove_dead(mut commands: Commands, alive: Query<&dyn Alive>) {
+    ///     alive.remove_impls_where(&mut commands, |alive| alive.is_alive());
+    /// }
+    /// # bevy_ecs::system::assert_is_system(remove_dead);
+    /// ```
+    fn remove_impls_where(&self, commands: &mut Commands, pred: impl Fn(&Trait) -> bool);
+}
+
+impl<Trait, D, F> DynQueryExt<Trait> for Query<'_, '_, D, F>
+where
+    Trait: ?Sized + TraitQuery,
+    D: QueryData,
+    D::ReadOnly: for<'a> WorldQuery<Item<'a> = ReadTraits<'a, Trait>>,
+    F: QueryFilter,
+{
+    fn collect_all(&self) -> Vec<(Entity, Vec<Ref<'_, Trait>>)> {
+        self.iter()
+            .map(|traits| (traits.entity(), traits.into_iter().collect()))
+            .collect()
+    }
+
+    fn iter_with_entity(&self) -> impl Iterator<Item = (Entity, ReadTraits<'_, Trait>)> {
+        self.iter().map(|traits| (traits.entity(), traits))
+    }
+
+    fn iter_indexed(&self) -> impl Iterator<Item = (usize, Entity, Ref<'_, Trait>)> {
+        let mut items: Vec<_> = self
+            .iter_with_entity()
+            .flat_map(|(entity, traits)| traits.into_iter().map(move |item| (entity, item)))
+            .collect();
+        // The sort is stable, so the impls of each entity stay in registration order.
+        items.sort_by_key(|&(entity, _)| entity);
+        items
+            .into_iter()
+            .enumerate()
+            .map(|(index, (entity, item))| (index, entity, item))
+    }
+
+    fn take_impls(&self, n: usize) -> impl Iterator<Item = Ref<'_, Trait>> {
+        self.iter().flatten().take(n)
+    }
+
+    fn group_by_component(&self) -> HashMap<ComponentId, Vec<Ref<'_, Trait>>> {
+        let mut groups = HashMap::<_, Vec<_>>::new();
+        for traits in self.iter() {
+            for (component, item) in traits.iter_with_id() {
+                groups.entry(component).or_default().push(item);
+            }
+        }
+        groups
+    }
+
+    fn map_one(&self) -> EntityHashMap<Ref<'_, Trait>> {
+        self.iter_with_entity()
+            .filter_map(|(entity, traits)| Some((entity, traits.into_iter().next()?)))
+            .collect()
+    }
+
+    fn impl_counts(&self) -> impl Iterator<Item = (Entity, usize)> {
+        self.iter_with_entity()
+            .map(|(entity, traits)| (entity, traits.len()))
+    }
+
+    #[cfg(feature = "bevy_hierarchy")]
+    fn iter_descendant_traits<'a>(
+        &'a self,
+        root: Entity,
+        children: &'a Query<&Children>,
+    ) -> impl Iterator<Item = (Entity, Ref<'a, Trait>)> + 'a {
+        std::iter::once(root)
+            .chain(children.iter_descendants(root))
+            .filter_map(|entity| Some((entity, self.get(entity).ok()?)))
+            .flat_map(|(entity, traits)| traits.into_iter().map(move |item| (entity, item)))
+    }
+
+    fn for_each_trait(&self, mut f: impl FnMut(&Trait)) {
+        for item in self.iter().flatten() {
+            f(&*item);
+        }
+    }
+
+    fn write_events<E: Event>(
+        &self,
+        writer: &mut EventWriter<E>,
+        mut f: impl FnMut(Entity, &Trait) -> Option<E>,
+    ) {
+        for (entity, traits) in self.iter_with_entity() {
+            for item in traits {
+                if let Some(event) = f(entity, &*item) {
+                    writer.send(event);
+                }
+            }
+        }
+    }
+
+    fn remove_impls_where(&self, commands: &mut Commands, pred: impl Fn(&Trait) -> bool) {
+        for (entity, traits) in self.iter_with_entity() {
+            for (component, item) in traits.iter_with_id() {
+                if !pred(&*item) {
+                    commands.entity(entity).remove_by_id(component);
+                }
+            }
+        }
+    }
+}
+
+/// Extension methods for queries with mutable access to all the impls of a trait,
+/// such as `Query<&mut dyn Trait>`.
+///
+/// This is only implemented for queries over [`All`](crate::All) impls of a trait.
+/// Queries such as `Query<One<&mut dyn Trait>>` already yield a single impl per entity,
+/// so `Query::single_mut` can be used for those instead.
+pub trait DynQueryMutExt<Trait: ?Sized + TraitQuery> {
+    /// Returns the only impl of `Trait` among all entities matched by the query.
+    ///
+    /// Unlike `Query::single_mut`, the error distinguishes between the query matching
+    /// no entities, multiple entities, or a single entity with multiple impls.
+    /// A `Query<One<&mut dyn Trait>>` cannot distinguish the last case, since it only
+    /// fetches one impl per entity, so this method is not available for it.
+    fn single_trait_mut(&mut self) -> Result<Mut<'_, Trait>, SingleTraitError>;
+}
+
+impl<Trait, D, F> DynQueryMutExt<Trait> for Query<'_, '_, D, F>
+where
+    Trait: ?Sized + TraitQuery,
+    D: QueryData + for<'a> WorldQuery<Item<'a> = WriteTraits<'a, Trait>>,
+    F: QueryFilter,
+{
+    fn single_trait_mut(&mut self) -> Result<Mut<'_, Trait>, SingleTraitError> {
+        let trait_name = std::any::type_name::<Trait>();
+        let mut iter = self.iter_mut();
+        let traits = iter
+            .next()
+            .ok_or(SingleTraitError::NoEntities(trait_name))?;
+        if iter.next().is_some() {
+            return Err(SingleTraitError::MultipleEntities(trait_name));
+        }
+        let entity = traits.entity();
+        let mut impls = traits.into_iter();
+        let first = impls
+            .next()
+            .ok_or(SingleTraitError::NoEntities(trait_name))?;
+        if impls.next().is_some() {
+            return Err(SingleTraitError::MultipleImpls(entity, trait_name));
+        }
+        Ok(first)
+    }
+}
+
+/// An error from [`DynQueryMutExt::single_trait_mut`].
+///
+/// Each variant carries the name of the trait.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SingleTraitError {
+    /// No entities have a component implementing the trait.
+    NoEntities(&'static str),
+    /// More than one entity has a component implementing the trait.
+    MultipleEntities(&'static str),
+    /// The only matching entity has more than one component implementing the trait.
+    MultipleImpls(Entity, &'static str),
+}
+
+impl fmt::Display for SingleTraitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoEntities(name) => {
+                write!(f, "no entities have a component implementing `{name}`")
+            }
+            Self::MultipleEntities(name) => {
+                write!(
+                    f,
+                    "multiple entities have a component implementing `{name}`"
+                )
+            }
+            Self::MultipleImpls(entity, name) => write!(
+                f,
+                "entity {entity} has multiple components implementing `{name}`"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SingleTraitError {}
+
+/// Lazily-initialized [`QueryState`], for caching trait queries outside of systems.
+///
+/// Building the state for a trait query requires a [`World`], so it cannot be done up front
+/// in contexts such as [`Default`] impls. This wrapper defers that until the first call to
+/// [`get_or_init`](Self::get_or_init), and re-uses the state afterwards.
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_trait_query::*;
+/// #
+/// # #[bevy_trait_query::queryable]
+/// # pub trait Tooltip {
+/// #     fn tooltip(&self) -> &str;
+/// # }
+/// #
+/// #[derive(Resource, Default)]
+/// struct TooltipQuery(DynQueryCache<&'static dyn Tooltip>);
+///
+/// fn show_tooltips(world: &mut World) {
+///     world.resource_scope(|world, mut cache: Mut<TooltipQuery>| {
+///         for tooltips in cache.0.get_or_init(world).iter(world) {
+///             for tooltip in tooltips {
+///                 println!("{}", tooltip.tooltip());
+///             }
+///         }
+///     });
+/// }
+/// ```
+pub struct DynQueryCache<D: QueryData, F: QueryFilter = ()> {
+    state: Option<QueryState<D, F>>,
+    // The number of times the state has been built, so tests can check that it is re-used.
+    #[cfg(test)]
+    pub(crate) inits: usize,
+}
+
+impl<D: QueryData, F: QueryFilter> Default for DynQueryCache<D, F> {
+    fn default() -> Self {
+        Self {
+            state: None,
+            #[cfg(test)]
+            inits: 0,
+        }
+    }
+}
+
+impl<D: QueryData, F: QueryFilter> DynQueryCache<D, F> {
+    /// Returns the cached [`QueryState`], initializing it from `world` if this is the first call.
+    ///
+    /// # Panics
+    /// If the state was already initialized with a different [`World`].
+    pub fn get_or_init(&mut self, world: &mut World) -> &mut QueryState<D, F> {
+        let state = self.state.get_or_insert_with(|| {
+            #[cfg(test)]
+            {
+                self.inits += 1;
+            }
+            QueryState::new(world)
+        });
+        assert_eq!(
+            state.world_id(),
+            world.id(),
+            "`DynQueryCache` was used with a different world than it was initialized with"
+        );
+        state
+    }
+
+    /// Returns `true` if the [`QueryState`] has been initialized.
+    pub fn is_initialized(&self) -> bool {
+        self.state.is_some()
+    }
+}
@@ -0,0 +1,107 @@
+use std::fmt;
+
+use bevy_ecs::{component::StorageType, world::World};
+
+use crate::{TraitImplRegistry, TraitQuery};
+
+/// A summary of how the impls of a trait are laid out in a [`World`],
+/// for diagnosing slow trait queries.
+///
+/// Created by [`TraitWorldExt::trait_query_report`](crate::TraitWorldExt::trait_query_report).
+/// The [`Display`](fmt::Display) impl prints the report along with a warning if the world
+/// is [fragmented](Self::is_fragmented).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TraitQueryReport {
+    /// The number of archetypes containing at least one component implementing the trait.
+    pub matched_archetypes: usize,
+    /// The number of entities with at least one component implementing the trait.
+    pub matched_entities: usize,
+    /// The number of components registered as implementing the trait.
+    pub registered_impls: usize,
+    /// The number of registered impls which are stored in tables.
+    pub table_impls: usize,
+    /// The number of registered impls which are stored in sparse sets.
+    pub sparse_impls: usize,
+}
+
+impl TraitQueryReport {
+    /// Worlds with fewer matched archetypes than this are never considered fragmented.
+    pub const FRAGMENTED_MIN_ARCHETYPES: usize = 16;
+    /// Worlds with fewer matched entities per archetype than this, on average,
+    /// are considered fragmented.
+    pub const FRAGMENTED_MAX_ENTITIES_PER_ARCHETYPE: usize = 8;
+
+    pub(crate) fn new<Trait: ?Sized + TraitQuery>(world: &World) -> Self {
+        let Some(registry) = world.get_resource::<TraitImplRegistry<Trait>>() else {
+            return Self::default();
+        };
+        let mut report = Self {
+            registered_impls: registry.components.len(),
+            table_impls: registry.components_with_storage(StorageType::Table).count(),
+            sparse_impls: registry
+                .components_with_storage(StorageType::SparseSet)
+                .count(),
+            ..Self::default()
+        };
+        for archetype in world.archetypes().iter() {
+            if registry
+                .components
+                .iter()
+                .any(|&component| archetype.contains(component))
+            {
+                report.matched_archetypes += 1;
+                report.matched_entities += archetype.len();
+            }
+        }
+        report
+    }
+
+    /// The average number of matched entities in each matched archetype.
+    ///
+    /// Returns `0.0` if no archetypes were matched.
+    pub fn entities_per_archetype(&self) -> f32 {
+        if self.matched_archetypes == 0 {
+            return 0.0;
+        }
+        self.matched_entities as f32 / self.matched_archetypes as f32
+    }
+
+    /// Returns `true` if the matched entities are spread across many small archetypes.
+    ///
+    /// Trait queries have a fixed cost for every archetype they visit, so they are slowest
+    /// when there are many archetypes with few entities each. This is a heuristic:
+    /// the world is considered fragmented if there are at least
+    /// [`FRAGMENTED_MIN_ARCHETYPES`](Self::FRAGMENTED_MIN_ARCHETYPES) matched archetypes,
+    /// with fewer than [`FRAGMENTED_MAX_ENTITIES_PER_ARCHETYPE`](Self::FRAGMENTED_MAX_ENTITIES_PER_ARCHETYPE)
+    /// entities in each on average.
+    pub fn is_fragmented(&self) -> bool {
+        self.matched_archetypes >= Self::FRAGMENTED_MIN_ARCHETYPES
+            && self.matched_entities
+                < self.matched_archetypes * Self::FRAGMENTED_MAX_ENTITIES_PER_ARCHETYPE
+    }
+}
+
+impl fmt::Display for TraitQueryReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} registered impls ({} in tables, {} in sparse sets)",
+            self.registered_impls, self.table_impls, self.sparse_impls
+        )?;
+        write!(
+            f,
+            "{} matched entities in {} archetypes ({:.1} per archetype)",
+            self.matched_entities,
+            self.matched_archetypes,
+            self.entities_per_archetype()
+        )?;
+        if self.is_fragmented() {
+            write!(
+                f,
+                "\nwarning: the matched entities are fragmented across many small archetypes, \
+                 which makes trait queries slower"
+            )?;
+        }
+        Ok(())
+    }
+}
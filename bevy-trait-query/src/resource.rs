@@ -0,0 +1,109 @@
+use bevy_ecs::{component::ComponentId, system::Resource, world::World};
+
+use crate::{dyn_constructor::DynCtor, TraitQuery, TraitResourceMarker};
+
+/// Stores the resources registered as implementing a trait.
+#[derive(Resource)]
+pub(crate) struct ResourceTraitRegistry<Trait: ?Sized> {
+    resources: Vec<ComponentId>,
+    dyn_ctors: Vec<DynCtor<Trait>>,
+}
+
+impl<T: ?Sized> Default for ResourceTraitRegistry<T> {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            resources: vec![],
+            dyn_ctors: vec![],
+        }
+    }
+}
+
+/// Extension methods for registering resources with a trait,
+/// so that they can be accessed with [`ResourceTraitExt::resource_trait`].
+pub trait RegisterResourceExt {
+    /// Allows a resource to be accessed as an impl of `Trait`.
+    /// Calling this multiple times with the same arguments will do nothing on subsequent calls.
+    fn register_resource_as<Trait: ?Sized + TraitQuery, R: Resource>(&mut self) -> &mut Self
+    where
+        (R,): TraitResourceMarker<Trait, Covered = R>;
+}
+
+impl RegisterResourceExt for World {
+    fn register_resource_as<Trait: ?Sized + TraitQuery, R: Resource>(&mut self) -> &mut Self
+    where
+        (R,): TraitResourceMarker<Trait, Covered = R>,
+    {
+        let resource_id = self.register_resource::<R>();
+        let mut registry =
+            self.get_resource_or_insert_with::<ResourceTraitRegistry<Trait>>(Default::default);
+        // Don't register the same resource multiple times.
+        if !registry.resources.contains(&resource_id) {
+            registry.resources.push(resource_id);
+            registry.dyn_ctors.push(DynCtor { cast: <(R,)>::cast });
+        }
+        self
+    }
+}
+
+#[cfg(feature = "bevy_app")]
+impl RegisterResourceExt for bevy_app::App {
+    fn register_resource_as<Trait: ?Sized + TraitQuery, R: Resource>(&mut self) -> &mut Self
+    where
+        (R,): TraitResourceMarker<Trait, Covered = R>,
+    {
+        self.world_mut().register_resource_as::<Trait, R>();
+        self
+    }
+}
+
+/// Extension methods for accessing resources as trait objects from a [`World`].
+pub trait ResourceTraitExt {
+    /// Returns the resource registered as implementing `Trait`.
+    ///
+    /// If several registered resources are present in the world,
+    /// the one that was registered first is returned.
+    /// Returns `None` if none of them are present.
+    ///
+    /// ```
+    /// # use bevy::prelude::*;
+    /// # use bevy_trait_query::*;
+    /// #
+    /// #[bevy_trait_query::queryable]
+    /// pub trait Difficulty {
+    ///     fn damage_multiplier(&self) -> f32;
+    /// }
+    ///
+    /// #[derive(Resource)]
+    /// struct Hard;
+    ///
+    /// impl Difficulty for Hard {
+    ///     fn damage_multiplier(&self) -> f32 {
+    ///         2.0
+    ///     }
+    /// }
+    ///
+    /// let mut world = World::new();
+    /// world.register_resource_as::<dyn Difficulty, Hard>();
+    /// world.insert_resource(Hard);
+    ///
+    /// let difficulty = world.resource_trait::<dyn Difficulty>().unwrap();
+    /// assert_eq!(difficulty.damage_multiplier(), 2.0);
+    /// ```
+    fn resource_trait<Trait: ?Sized + TraitQuery>(&self) -> Option<&Trait>;
+}
+
+impl ResourceTraitExt for World {
+    fn resource_trait<Trait: ?Sized + TraitQuery>(&self) -> Option<&Trait> {
+        let registry = self.get_resource::<ResourceTraitRegistry<Trait>>()?;
+        registry
+            .resources
+            .iter()
+            .zip(&registry.dyn_ctors)
+            .find_map(|(&resource_id, &dyn_ctor)| {
+                let ptr = self.get_resource_by_id(resource_id)?;
+                // SAFETY: `dyn_ctor` was registered for the type of this resource.
+                Some(unsafe { dyn_ctor.cast(ptr) })
+            })
+    }
+}
@@ -2,9 +2,6 @@ use super::*;
 use bevy_ecs::prelude::*;
 use std::fmt::{Debug, Display};
 
-// Required for proc macros.
-use crate as bevy_trait_query;
-
 #[derive(Resource, Default)]
 pub struct Output(Vec<String>);
 
@@ -656,6 +653,48 @@ fn spawn_sparse(mut commands: Commands) {
     }
 }
 
+#[queryable]
+pub trait Label {
+    fn label(&self) -> &'static str;
+}
+
+#[derive(Component)]
+#[component(storage = "SparseSet")]
+pub struct SparseA;
+
+#[derive(Component)]
+#[component(storage = "SparseSet")]
+pub struct SparseB;
+
+impl Label for SparseA {
+    fn label(&self) -> &'static str {
+        "A"
+    }
+}
+
+impl Label for SparseB {
+    fn label(&self) -> &'static str {
+        "B"
+    }
+}
+
+// The sparse set for `SparseA` exists world-wide once any entity has it,
+// so `One` must not select it for an archetype that only contains `SparseB`.
+#[test]
+fn one_sparse_other_archetype() {
+    let mut world = World::new();
+    world
+        .register_component_as::<dyn Label, SparseA>()
+        .register_component_as::<dyn Label, SparseB>();
+
+    world.spawn(SparseA);
+    world.spawn(SparseB);
+
+    let mut query = world.query::<One<&dyn Label>>();
+    let labels: Vec<_> = query.iter(&world).map(|l| l.label()).collect();
+    assert_eq!(labels, ["A", "B"]);
+}
+
 // Make sure it works correctly when components are registered multiple times.
 #[test]
 fn multi_register() {
@@ -779,3 +818,3402 @@ fn transmute_panics_if_trait_touched() {
 
     schedule.run(&mut world);
 }
+
+#[test]
+fn matches_data() {
+    let mut world = World::new();
+    world.init_resource::<Output>();
+    world
+        .register_component_as::<dyn Person, Human>()
+        .register_component_as::<dyn Person, Dolphin>();
+
+    world.spawn(Human("Henry".to_owned(), 22));
+    world.spawn((Human("Henry".to_owned(), 22), Dolphin(22)));
+    world.spawn(Dolphin(22));
+    world.spawn(Fem);
+
+    let mut schedule = Schedule::default();
+    schedule.add_systems(print_matches_info);
+
+    schedule.run(&mut world);
+
+    assert_eq!(
+        world.resource::<Output>().0,
+        &["0v1: true", "1v1: true", "2v1: true", "3v1: false"]
+    );
+}
+
+// Prints whether every entity has a component implementing the trait
+fn print_matches_info(people: Query<(Entity, Matches<dyn Person>)>, mut output: ResMut<Output>) {
+    let mut people: Vec<_> = people.iter().collect();
+    people.sort_by_key(|(entity, _)| *entity);
+    for (entity, is_person) in people {
+        output.0.push(format!("{entity}: {is_person}"));
+    }
+}
+
+#[test]
+fn collect_all() {
+    let mut world = World::new();
+    world.init_resource::<Output>();
+    world
+        .register_component_as::<dyn Person, Human>()
+        .register_component_as::<dyn Person, Dolphin>();
+
+    world.spawn(Human("Henry".to_owned(), 22));
+    world.spawn((Human("Eliza".to_owned(), 31), Dolphin(6)));
+
+    let mut schedule = Schedule::default();
+    schedule.add_systems(print_collected_info);
+
+    schedule.run(&mut world);
+
+    assert_eq!(
+        world.resource::<Output>().0,
+        &[
+            "0v1: Henry",
+            "1v1: Eliza, Reginald",
+            "2 entities",
+            "Henry",
+            "Eliza",
+            "Reginald",
+        ]
+    );
+}
+
+// Collects every `Person` up front, and only uses them afterwards.
+fn print_collected_info(people: Query<&dyn Person>, mut output: ResMut<Output>) {
+    let collected = people.collect_all();
+    let traits: Vec<ReadTraits<dyn Person>> = people.iter().collect();
+
+    for (entity, people) in &collected {
+        let names: Vec<_> = people.iter().map(|p| p.name()).collect();
+        output.0.push(format!("{entity}: {}", names.join(", ")));
+    }
+    output.0.push(format!("{} entities", traits.len()));
+    for person in traits.iter().flatten() {
+        output.0.push(person.name().to_owned());
+    }
+}
+
+#[queryable]
+pub trait Describe {
+    fn describe(&self) -> String;
+}
+
+#[derive(Component)]
+pub struct Wrapper<T: Send + Sync + 'static>(T);
+
+impl<T: Debug + Send + Sync + 'static> Describe for Wrapper<T> {
+    fn describe(&self) -> String {
+        format!("{:?}", self.0)
+    }
+}
+
+#[test]
+fn generic_components() {
+    let mut world = World::new();
+    world.init_resource::<Output>();
+    world.register_component_as::<dyn Describe, Wrapper<u8>>();
+    crate::register_generic!(world, dyn Describe, Wrapper<[i32, f32, String]>);
+
+    world.spawn(Wrapper(1u8));
+    world.spawn(Wrapper(2i32));
+    world.spawn((Wrapper(3.5f32), Wrapper("four".to_owned())));
+    // Not registered, so it should not show up.
+    world.spawn(Wrapper(5u64));
+
+    let mut schedule = Schedule::default();
+    schedule.add_systems(print_descriptions);
+
+    schedule.run(&mut world);
+
+    assert_eq!(
+        world.resource::<Output>().0,
+        &["1", "2", "3.5", r#""four""#]
+    );
+}
+
+fn print_descriptions(q: Query<&dyn Describe>, mut output: ResMut<Output>) {
+    for all in &q {
+        for d in all {
+            output.0.push(d.describe());
+        }
+    }
+}
+
+#[test]
+fn single_table_ptr() {
+    let mut world = World::new();
+    world
+        .register_component_as::<dyn Person, Human>()
+        .register_component_as::<dyn Person, Dolphin>()
+        .register_component_as::<dyn Messages, RecA>()
+        .register_component_as::<dyn Messages, RecB>();
+
+    let human = world.spawn(Human("Henry".to_owned(), 22)).id();
+    let both = world
+        .spawn((Human("Eliza".to_owned(), 31), Dolphin(6)))
+        .id();
+    let table_only = world.spawn(RecA(vec!["Hi".to_owned()])).id();
+    let mixed = world.spawn((RecA(vec![]), RecB(vec![]))).id();
+    let sparse_only = world.spawn(RecB(vec![])).id();
+
+    let human_id = world.component_id::<Human>().unwrap();
+    let rec_a_id = world.component_id::<RecA>().unwrap();
+
+    let mut people = world.query::<&dyn Person>();
+    let (id, ptr) = people
+        .get(&world, human)
+        .unwrap()
+        .single_table_ptr()
+        .unwrap();
+    assert_eq!(id, human_id);
+    // SAFETY: the component id tells us this is a `Human`.
+    assert_eq!(unsafe { ptr.deref::<Human>() }.0, "Henry");
+    assert!(people
+        .get(&world, both)
+        .unwrap()
+        .single_table_ptr()
+        .is_none());
+
+    let mut messages = world.query::<&dyn Messages>();
+    let (id, ptr) = messages
+        .get(&world, table_only)
+        .unwrap()
+        .single_table_ptr()
+        .unwrap();
+    assert_eq!(id, rec_a_id);
+    // SAFETY: the component id tells us this is a `RecA`.
+    assert_eq!(unsafe { ptr.deref::<RecA>() }.0, &["Hi"]);
+    assert!(messages
+        .get(&world, mixed)
+        .unwrap()
+        .single_table_ptr()
+        .is_none());
+    assert!(messages
+        .get(&world, sparse_only)
+        .unwrap()
+        .single_table_ptr()
+        .is_none());
+}
+
+#[test]
+#[should_panic(expected = "Mutable component access must be unique")]
+fn double_mut_access_conflicts() {
+    let mut world = World::new();
+    world
+        .register_component_as::<dyn Person, Human>()
+        .register_component_as::<dyn Person, Dolphin>();
+
+    world.query::<(&mut dyn Person, &mut dyn Person)>();
+}
+
+#[test]
+#[should_panic(expected = "Mutable component access must be unique")]
+fn shared_then_mut_access_conflicts() {
+    let mut world = World::new();
+    world
+        .register_component_as::<dyn Person, Human>()
+        .register_component_as::<dyn Person, Dolphin>();
+
+    world.query::<(&dyn Person, &mut dyn Person)>();
+}
+
+#[test]
+#[should_panic(expected = "Shared access cannot coincide with exclusive access")]
+fn mut_then_shared_access_conflicts() {
+    let mut world = World::new();
+    world
+        .register_component_as::<dyn Person, Human>()
+        .register_component_as::<dyn Person, Dolphin>();
+
+    world.query::<(&mut dyn Person, &dyn Person)>();
+}
+
+#[test]
+#[should_panic(expected = "Mutable component access must be unique")]
+fn one_shared_then_mut_access_conflicts() {
+    let mut world = World::new();
+    world
+        .register_component_as::<dyn Person, Human>()
+        .register_component_as::<dyn Person, Dolphin>();
+
+    world.query::<(One<&dyn Person>, One<&mut dyn Person>)>();
+}
+
+#[derive(Component)]
+#[component(on_add = hooked_on_add, on_remove = hooked_on_remove)]
+pub struct Hooked(String);
+
+impl Messages for Hooked {
+    fn send(&mut self, m: &dyn Display) {
+        self.0 = format!("Hooked: {m}");
+    }
+    fn read(&self) -> &[String] {
+        std::slice::from_ref(&self.0)
+    }
+}
+
+fn hooked_on_add(
+    mut world: bevy_ecs::world::DeferredWorld,
+    _: Entity,
+    _: bevy_ecs::component::ComponentId,
+) {
+    world.resource_mut::<Output>().0.push("on_add".to_owned());
+}
+
+fn hooked_on_remove(
+    mut world: bevy_ecs::world::DeferredWorld,
+    _: Entity,
+    _: bevy_ecs::component::ComponentId,
+) {
+    world
+        .resource_mut::<Output>()
+        .0
+        .push("on_remove".to_owned());
+}
+
+#[test]
+fn component_hooks() {
+    let mut world = World::new();
+    world.init_resource::<Output>();
+    world
+        .register_component_as::<dyn Messages, Hooked>()
+        .register_component_as::<dyn Messages, RecA>();
+
+    let entity = world.spawn((Hooked("Hi".to_owned()), RecA(vec![]))).id();
+
+    let mut schedule = Schedule::default();
+    schedule.add_systems(print_messages);
+    schedule.run(&mut world);
+
+    world.entity_mut(entity).remove::<Hooked>();
+    schedule.run(&mut world);
+
+    assert_eq!(
+        world.resource::<Output>().0,
+        &[
+            "on_add",
+            "New frame:",
+            r#"0: ["Hi"]"#,
+            "0: []",
+            "on_remove",
+            "New frame:",
+            "0: []",
+        ]
+    );
+}
+
+#[test]
+fn iter_mut_where() {
+    let mut world = World::new();
+    world.init_resource::<Output>();
+    world
+        .register_component_as::<dyn Person, Human>()
+        .register_component_as::<dyn Person, Dolphin>();
+
+    world.spawn((Human("Henry".to_owned(), 22), Dolphin(6)));
+
+    let mut schedule = Schedule::default();
+    schedule.add_systems((print_changed_all_info, age_up_adults).chain());
+
+    schedule.run(&mut world);
+    schedule.run(&mut world);
+
+    assert_eq!(
+        world.resource::<Output>().0,
+        &[
+            "Changed people:",
+            "Henry: 22",
+            "Reginald: 6",
+            "",
+            // Only the adult was touched, so the dolphin should not be flagged as changed.
+            "Changed people:",
+            "Henry: 23",
+            "",
+        ]
+    );
+}
+
+fn age_up_adults(mut q: Query<&mut dyn Person>) {
+    for mut all in &mut q {
+        for mut p in all.iter_mut_where(|p| p.age() >= 18) {
+            let age = p.age();
+            p.set_age(age + 1);
+        }
+    }
+}
+
+#[test]
+fn dyn_query_cache() {
+    #[derive(Resource, Default)]
+    struct PeopleQuery(DynQueryCache<&'static dyn Person>);
+
+    fn count_people(world: &mut World) {
+        world.resource_scope(|world, mut cache: Mut<PeopleQuery>| {
+            let count = cache.0.get_or_init(world).iter(world).flatten().count();
+            world
+                .resource_mut::<Output>()
+                .0
+                .push(format!("{count} people"));
+        });
+    }
+
+    let mut world = World::new();
+    world.init_resource::<Output>();
+    world
+        .register_component_as::<dyn Person, Human>()
+        .register_component_as::<dyn Person, Dolphin>();
+    world.init_resource::<PeopleQuery>();
+    assert!(!world.resource::<PeopleQuery>().0.is_initialized());
+
+    world.spawn(Human("Henry".to_owned(), 22));
+
+    let mut schedule = Schedule::default();
+    schedule.add_systems(count_people);
+
+    schedule.run(&mut world);
+    assert!(world.resource::<PeopleQuery>().0.is_initialized());
+    assert_eq!(world.resource::<PeopleQuery>().0.inits, 1);
+
+    // New archetypes should still be picked up by the cached state.
+    world.spawn((Human("Eliza".to_owned(), 31), Dolphin(6)));
+    schedule.run(&mut world);
+
+    assert_eq!(world.resource::<Output>().0, &["1 people", "3 people"]);
+    // The state should have been re-used rather than built again.
+    assert_eq!(world.resource::<PeopleQuery>().0.inits, 1);
+}
+
+#[test]
+fn count_matching_archetypes() {
+    let mut world = World::new();
+    assert_eq!(world.count_matching_archetypes::<dyn Person>(), 0);
+
+    world
+        .register_component_as::<dyn Person, Human>()
+        .register_component_as::<dyn Person, Dolphin>();
+
+    world.spawn(Human("Henry".to_owned(), 22));
+    world.spawn(Human("Eliza".to_owned(), 31));
+    world.spawn((Human("Garbanzo".to_owned(), 17), Fem));
+    world.spawn((Human("Garbanzo".to_owned(), 17), Dolphin(17)));
+    world.spawn(Dolphin(27));
+    world.spawn(Fem);
+
+    assert_eq!(world.count_matching_archetypes::<dyn Person>(), 4);
+    assert_eq!(world.count_matching_archetypes::<dyn Messages>(), 0);
+}
+
+#[queryable(prelude)]
+pub trait Greeter {
+    fn greet(&self) -> String;
+}
+
+impl Greeter for Human {
+    fn greet(&self) -> String {
+        format!("Hi, I'm {}", self.0)
+    }
+}
+
+impl Greeter for Dolphin {
+    fn greet(&self) -> String {
+        "*clicks*".to_owned()
+    }
+}
+
+#[test]
+fn prelude_aliases() {
+    let mut world = World::new();
+    world.init_resource::<Output>();
+    world
+        .register_component_as::<dyn Greeter, Human>()
+        .register_component_as::<dyn Greeter, Dolphin>();
+
+    world.spawn(Human("Henry".to_owned(), 22));
+    world.spawn((Human("Eliza".to_owned(), 31), Dolphin(6)));
+    world.spawn(Dolphin(27));
+
+    let mut schedule = Schedule::default();
+    schedule.add_systems((print_one_greetings, print_all_greetings).chain());
+
+    schedule.run(&mut world);
+
+    assert_eq!(
+        world.resource::<Output>().0,
+        &[
+            "Hi, I'm Henry",
+            "*clicks*",
+            "Hi, I'm Henry",
+            "Hi, I'm Eliza",
+            "*clicks*",
+            "*clicks*",
+        ]
+    );
+}
+
+fn print_one_greetings(
+    q: Query<greeter_query::One, greeter_query::WithOne>,
+    mut output: ResMut<Output>,
+) {
+    for greeter in &q {
+        output.0.push(greeter.greet());
+    }
+}
+
+fn print_all_greetings(q: Query<greeter_query::All>, mut output: ResMut<Output>) {
+    for greeter in q.iter().flatten() {
+        output.0.push(greeter.greet());
+    }
+}
+
+#[test]
+fn check_row_bounds() {
+    use bevy_ecs::storage::TableRow;
+
+    check_row(3, TableRow::from_u32(0));
+    check_row(3, TableRow::from_u32(2));
+}
+
+#[test]
+#[should_panic = "table row 3 is out of bounds for a table with 3 entities"]
+fn check_row_out_of_bounds() {
+    check_row(3, bevy_ecs::storage::TableRow::from_u32(3));
+}
+
+// With the `paranoid` feature, every fetch checks its table row before touching any data.
+// None of these checks should ever fail.
+#[test]
+fn paranoid_checks_pass() {
+    let mut world = World::new();
+    world
+        .register_component_as::<dyn Messages, RecA>()
+        .register_component_as::<dyn Messages, RecB>();
+
+    world.spawn(RecA(vec![]));
+    world.spawn((RecA(vec![]), RecB(vec![])));
+    world.spawn(RecB(vec![]));
+
+    let mut all = world.query::<&mut dyn Messages>();
+    for mut msgs in all.iter_mut(&mut world) {
+        for mut msg in &mut msgs {
+            msg.send(&"Hi");
+        }
+    }
+    let mut one = world.query::<One<&dyn Messages>>();
+    assert_eq!(one.iter(&world).count(), 2);
+}
+
+pub trait Species: Send + Sync + 'static {
+    const NAME: &'static str;
+}
+
+pub struct Cat;
+pub struct Dog;
+
+impl Species for Cat {
+    const NAME: &'static str = "Cat";
+}
+
+impl Species for Dog {
+    const NAME: &'static str = "Dog";
+}
+
+#[derive(Component)]
+pub struct Pet<S: Species>(u32, std::marker::PhantomData<S>);
+
+// A blanket impl covering every monomorphization of `Pet`.
+impl<S: Species> Person for Pet<S> {
+    fn name(&self) -> &str {
+        S::NAME
+    }
+    fn age(&self) -> u32 {
+        self.0
+    }
+    fn set_age(&mut self, age: u32) {
+        self.0 = age;
+    }
+}
+
+#[test]
+fn blanket_impl_generic_components() {
+    let mut world = World::new();
+    world.init_resource::<Output>();
+    world
+        .register_component_as::<dyn Person, Pet<Cat>>()
+        .register_component_as::<dyn Person, Pet<Dog>>();
+
+    world.spawn(Pet::<Cat>(3, Default::default()));
+    world.spawn((
+        Pet::<Cat>(5, Default::default()),
+        Pet::<Dog>(7, Default::default()),
+    ));
+
+    let mut schedule = Schedule::default();
+    schedule.add_systems((print_all_info, age_up_not).chain());
+
+    schedule.run(&mut world);
+    schedule.run(&mut world);
+
+    assert_eq!(
+        world.resource::<Output>().0,
+        &[
+            "All people:",
+            "Cat: 3",
+            "Cat: 5",
+            "Dog: 7",
+            "",
+            "All people:",
+            "Cat: 4",
+            "Cat: 6",
+            "Dog: 8",
+            "",
+        ]
+    );
+}
+
+#[test]
+fn clone_read_traits_iter() {
+    let mut world = World::new();
+    world
+        .register_component_as::<dyn Messages, RecA>()
+        .register_component_as::<dyn Messages, RecB>();
+
+    let entity = world
+        .spawn((RecA(vec!["A".to_owned()]), RecB(vec!["B".to_owned()])))
+        .id();
+
+    let mut query = world.query::<&dyn Messages>();
+    let traits = query.get(&world, entity).unwrap();
+    let mut iter = traits.iter();
+    let first = iter.next().unwrap();
+    assert_eq!(first.read(), &["A"]);
+
+    // Cloning mid-iteration should resume from the same position.
+    let rest: Vec<_> = iter.clone().map(|m| m.read().to_vec()).collect();
+    assert_eq!(rest, &[vec!["B".to_owned()]]);
+    let rest: Vec<_> = iter.map(|m| m.read().to_vec()).collect();
+    assert_eq!(rest, &[vec!["B".to_owned()]]);
+}
+
+#[test]
+fn single_table_impl() {
+    let mut world = World::new();
+    world.init_resource::<Output>();
+    world.register_component_as::<dyn Person, Human>();
+
+    world.spawn(Human("Henry".to_owned(), 22));
+    world.spawn((Human("Eliza".to_owned(), 31), Fem));
+    // Not registered, so it should be ignored.
+    world.spawn(Dolphin(27));
+
+    let mut schedule = Schedule::default();
+    schedule.add_systems((print_changed_all_info, age_up_fem).chain());
+
+    schedule.run(&mut world);
+    schedule.run(&mut world);
+
+    assert_eq!(
+        world.resource::<Output>().0,
+        &[
+            "Changed people:",
+            "Henry: 22",
+            "Eliza: 31",
+            "",
+            "Changed people:",
+            "Eliza: 32",
+            "",
+        ]
+    );
+}
+
+#[derive(Component, Default)]
+#[require(Inbox)]
+pub struct Mailbox(Vec<String>);
+
+#[derive(Component, Default)]
+pub struct Inbox(Vec<String>);
+
+impl Messages for Mailbox {
+    fn send(&mut self, m: &dyn Display) {
+        self.0.push(format!("Mailbox: {m}"));
+    }
+    fn read(&self) -> &[String] {
+        &self.0
+    }
+}
+
+impl Messages for Inbox {
+    fn send(&mut self, m: &dyn Display) {
+        self.0.push(format!("Inbox: {m}"));
+    }
+    fn read(&self) -> &[String] {
+        &self.0
+    }
+}
+
+#[test]
+fn warn_unregistered_required_impls() {
+    let mut world = World::new();
+    world.register_component_as::<dyn Messages, Mailbox>();
+
+    // `Inbox` is required by `Mailbox`, but has not been registered.
+    assert!(world.warn_unregistered_required_impls::<dyn Messages, Inbox>());
+    // `RecA` is not required by anything.
+    assert!(!world.warn_unregistered_required_impls::<dyn Messages, RecA>());
+
+    world.register_component_as::<dyn Messages, Inbox>();
+    assert!(!world.warn_unregistered_required_impls::<dyn Messages, Inbox>());
+}
+
+fn print_one_messages(q: Query<One<&dyn Messages>>, mut output: ResMut<Output>) {
+    output.0.push("New frame:".to_owned());
+    for (i, data) in q.iter().enumerate() {
+        output.0.push(format!("{i}: {:?}", data.read()));
+    }
+}
+
+fn send_one_messages(mut q: Query<One<&mut dyn Messages>>) {
+    for mut data in &mut q {
+        data.send(&"Hi");
+    }
+}
+
+#[test]
+fn one_table_and_sparse() {
+    let mut world = World::new();
+    world.init_resource::<Output>();
+    world
+        .register_component_as::<dyn Messages, RecA>()
+        .register_component_as::<dyn Messages, RecB>();
+
+    world.spawn(RecA(vec![]));
+    world.spawn(RecB(vec![]));
+
+    let mut schedule = Schedule::default();
+    schedule.add_systems((print_one_messages, send_one_messages, print_one_messages).chain());
+    schedule.run(&mut world);
+
+    assert_eq!(
+        world.resource::<Output>().0,
+        &[
+            "New frame:",
+            "0: []",
+            "1: []",
+            "New frame:",
+            r#"0: ["RecA: Hi"]"#,
+            r#"1: ["RecB: Hi"]"#,
+        ]
+    );
+}
+
+#[test]
+fn insert_trait_impl_dynamic() {
+    let mut world = World::new();
+    world.init_resource::<Output>();
+    world.register_component_as::<dyn Person, Dolphin>();
+    let dolphin = world.components().component_id::<Dolphin>().unwrap();
+
+    let entity = world.spawn_empty().id();
+    // SAFETY: `Dolphin` is a `u32` newtype.
+    unsafe {
+        world
+            .commands()
+            .entity(entity)
+            .insert_trait_impl_dynamic::<dyn Person>(dolphin, 27u32.to_ne_bytes().to_vec());
+    }
+    world.flush();
+
+    let mut schedule = Schedule::default();
+    schedule.add_systems(print_info);
+    schedule.run(&mut world);
+
+    assert_eq!(
+        world.resource::<Output>().0,
+        &["All people:", "Reginald: 27", ""]
+    );
+}
+
+#[test]
+#[should_panic]
+fn insert_unregistered_trait_impl_dynamic() {
+    let mut world = World::new();
+    world.register_component_as::<dyn Person, Human>();
+    let dolphin = world.register_component::<Dolphin>();
+
+    let entity = world.spawn_empty().id();
+    unsafe {
+        world
+            .commands()
+            .entity(entity)
+            .insert_trait_impl_dynamic::<dyn Person>(dolphin, 27u32.to_ne_bytes().to_vec());
+    }
+    world.flush();
+}
+
+fn message_component_name(
+    components: &bevy_ecs::component::Components,
+    id: bevy_ecs::component::ComponentId,
+) -> &'static str {
+    if components.component_id::<RecA>() == Some(id) {
+        "RecA"
+    } else if components.component_id::<RecB>() == Some(id) {
+        "RecB"
+    } else {
+        "unknown"
+    }
+}
+
+fn print_changed_with_id(
+    q: Query<&dyn Messages>,
+    components: &bevy_ecs::component::Components,
+    mut output: ResMut<Output>,
+) {
+    output.0.push("Changed:".to_owned());
+    for traits in &q {
+        for (id, data) in traits.iter_changed_with_id() {
+            let name = message_component_name(components, id);
+            output.0.push(format!("{name}: {:?}", data.read()));
+        }
+    }
+}
+
+fn print_added_with_id(
+    q: Query<&dyn Messages>,
+    components: &bevy_ecs::component::Components,
+    mut output: ResMut<Output>,
+) {
+    output.0.push("Added:".to_owned());
+    for traits in &q {
+        for (id, data) in traits.iter_added_with_id() {
+            assert!(data.is_added());
+            output
+                .0
+                .push(message_component_name(components, id).to_owned());
+        }
+    }
+}
+
+fn send_rec_a(mut q: Query<&mut RecA>) {
+    for mut rec in &mut q {
+        rec.send(&"Hello");
+    }
+}
+
+#[test]
+fn iter_changed_with_id() {
+    let mut world = World::new();
+    world.init_resource::<Output>();
+    world
+        .register_component_as::<dyn Messages, RecA>()
+        .register_component_as::<dyn Messages, RecB>();
+
+    world.spawn((RecA(vec![]), RecB(vec![])));
+
+    let mut schedule = Schedule::default();
+    schedule.add_systems((print_added_with_id, print_changed_with_id, send_rec_a).chain());
+
+    schedule.run(&mut world);
+    schedule.run(&mut world);
+
+    assert_eq!(
+        world.resource::<Output>().0,
+        &[
+            "Added:",
+            "RecA",
+            "RecB",
+            "Changed:",
+            "RecA: []",
+            "RecB: []",
+            "Added:",
+            "Changed:",
+            r#"RecA: ["RecA: Hello"]"#,
+        ]
+    );
+}
+
+#[test]
+fn trait_impl_storage_breakdown() {
+    let mut world = World::new();
+    assert_eq!(world.trait_impl_storage_breakdown::<dyn Messages>(), (0, 0));
+
+    world
+        .register_component_as::<dyn Messages, RecA>()
+        .register_component_as::<dyn Messages, RecB>()
+        .register_component_as::<dyn Messages, Hooked>();
+
+    assert_eq!(world.trait_impl_storage_breakdown::<dyn Messages>(), (2, 1));
+}
+
+fn age_up_unchecked(people: Query<(Entity, &mut dyn Person)>) {
+    // SAFETY: Each entity is only yielded once, and the items do not outlive the loop body.
+    for (_, people) in unsafe { people.iter_unchecked() } {
+        for mut person in people {
+            let age = person.age();
+            person.set_age(age + 1);
+        }
+    }
+    // SAFETY: The items from the previous iterator have been dropped,
+    // and we only hold one entity's `WriteTraits` at a time.
+    for entity in people.iter().map(|(entity, _)| entity).collect::<Vec<_>>() {
+        let (_, people) = unsafe { people.get_unchecked(entity) }.unwrap();
+        for mut person in people {
+            let age = person.age();
+            person.set_age(age * 2);
+        }
+    }
+}
+
+#[test]
+fn unchecked_access() {
+    let mut world = World::new();
+    world.init_resource::<Output>();
+    world
+        .register_component_as::<dyn Person, Human>()
+        .register_component_as::<dyn Person, Dolphin>();
+
+    world.spawn(Human("Henry".to_owned(), 22));
+    world.spawn((Human("Eliza".to_owned(), 31), Dolphin(6)));
+
+    let mut schedule = Schedule::default();
+    schedule.add_systems((age_up_unchecked, print_all_info).chain());
+    schedule.run(&mut world);
+
+    assert_eq!(
+        world.resource::<Output>().0,
+        &["All people:", "Henry: 46", "Eliza: 64", "Reginald: 14", ""]
+    );
+}
+
+fn print_typed_people(people: Query<&dyn Person>, mut output: ResMut<Output>) {
+    for person in &people {
+        // Components that aren't registered as impls of the trait are never returned.
+        assert!(person.get_as::<Fem>().is_none());
+        if let Some((human, dolphin)) = person.try_as::<(Human, Dolphin)>() {
+            output.0.push(format!("{} and {}", human.0, dolphin.0));
+        } else if let Some((human,)) = person.try_as::<(Human,)>() {
+            output.0.push(human.0.clone());
+        }
+    }
+}
+
+#[test]
+fn try_as_tuple() {
+    let mut world = World::new();
+    world.init_resource::<Output>();
+    world
+        .register_component_as::<dyn Person, Human>()
+        .register_component_as::<dyn Person, Dolphin>();
+
+    world.spawn((Human("Henry".to_owned(), 22), Fem));
+    world.spawn((Human("Eliza".to_owned(), 31), Dolphin(6)));
+    world.spawn(Dolphin(27));
+
+    let mut schedule = Schedule::default();
+    schedule.add_systems(print_typed_people);
+    schedule.run(&mut world);
+
+    assert_eq!(world.resource::<Output>().0, &["Henry", "Eliza and 6"]);
+}
+
+#[derive(Component)]
+struct Nickname(&'static str);
+
+fn print_with_entity(
+    people: Query<&dyn Person>,
+    nicknames: Query<&Nickname>,
+    mut output: ResMut<Output>,
+) {
+    for (entity, traits) in people.iter_with_entity() {
+        let nickname = nicknames.get(entity).unwrap().0;
+        for person in traits {
+            output.0.push(format!("{nickname}: {}", person.age()));
+        }
+    }
+}
+
+#[test]
+fn iter_with_entity() {
+    let mut world = World::new();
+    world.init_resource::<Output>();
+    world
+        .register_component_as::<dyn Person, Human>()
+        .register_component_as::<dyn Person, Dolphin>();
+
+    world.spawn((Nickname("Hank"), Human("Henry".to_owned(), 22)));
+    world.spawn((Nickname("Flipper"), Dolphin(6)));
+
+    let mut schedule = Schedule::default();
+    schedule.add_systems(print_with_entity);
+    schedule.run(&mut world);
+
+    assert_eq!(world.resource::<Output>().0, &["Hank: 22", "Flipper: 6"]);
+}
+
+fn log_people(mut output: ResMut<Output>) {
+    output.0.push("people".to_owned());
+}
+
+fn log_no_people(mut output: ResMut<Output>) {
+    output.0.push("no people".to_owned());
+}
+
+#[test]
+fn trait_run_conditions() {
+    let mut world = World::new();
+    world.init_resource::<Output>();
+    world
+        .register_component_as::<dyn Person, Human>()
+        .register_component_as::<dyn Person, Dolphin>();
+
+    let mut schedule = Schedule::default();
+    schedule.add_systems(
+        (
+            log_people.run_if(any_with_trait::<dyn Person>()),
+            log_no_people.run_if(no_entity_with_trait::<dyn Person>()),
+        )
+            .chain(),
+    );
+
+    schedule.run(&mut world);
+    let entity = world.spawn(Dolphin(27)).id();
+    schedule.run(&mut world);
+    world.despawn(entity);
+    schedule.run(&mut world);
+    // Entities with more than one impl count as well.
+    let entity = world
+        .spawn((Human("Eliza".to_owned(), 31), Dolphin(6)))
+        .id();
+    schedule.run(&mut world);
+    world.despawn(entity);
+    schedule.run(&mut world);
+
+    assert_eq!(
+        world.resource::<Output>().0,
+        &["no people", "people", "no people", "people", "no people"]
+    );
+}
+
+#[test]
+fn iter_sorted_by_id() {
+    let mut world = World::new();
+    // Make sure that the component ids are in a different order than the registrations.
+    let human = world.register_component::<Human>();
+    let dolphin = world.register_component::<Dolphin>();
+    assert!(human < dolphin);
+    world
+        .register_component_as::<dyn Person, Dolphin>()
+        .register_component_as::<dyn Person, Human>();
+
+    let entity = world
+        .spawn((Human("Eliza".to_owned(), 31), Dolphin(6)))
+        .id();
+
+    let traits = world.get_traits::<dyn Person>(entity).unwrap();
+    let registration_order: Vec<_> = traits.iter_with_id().map(|(id, _)| id).collect();
+    assert_eq!(registration_order, [dolphin, human]);
+
+    let sorted: Vec<_> = traits
+        .iter_sorted_by_id()
+        .map(|(id, person)| (id, person.name().to_owned()))
+        .collect();
+    assert_eq!(
+        sorted,
+        [
+            (human, "Eliza".to_owned()),
+            (dolphin, "Reginald".to_owned())
+        ]
+    );
+}
+
+fn count_cached(people: CachedDynExistence<dyn Person>, mut output: ResMut<Output>) {
+    output
+        .0
+        .push(format!("{} {}", people.is_empty(), people.count()));
+}
+
+#[test]
+fn cached_existence() {
+    let mut world = World::new();
+    world.init_resource::<Output>();
+    world
+        .register_component_as::<dyn Person, Human>()
+        .register_component_as::<dyn Person, Dolphin>();
+
+    let mut schedule = Schedule::default();
+    schedule.add_systems(count_cached);
+
+    schedule.run(&mut world);
+    world.spawn(Human("Henry".to_owned(), 22));
+    schedule.run(&mut world);
+    // New archetypes should be picked up.
+    let entity = world
+        .spawn((Human("Eliza".to_owned(), 31), Dolphin(6)))
+        .id();
+    world.spawn(Fem);
+    schedule.run(&mut world);
+    world.despawn(entity);
+    schedule.run(&mut world);
+
+    assert_eq!(
+        world.resource::<Output>().0,
+        &["true 0", "false 1", "false 2", "false 1"]
+    );
+}
+
+#[test]
+fn extract_owned() {
+    #[derive(Component, Clone)]
+    struct Bell(u32);
+
+    #[derive(Component, Clone)]
+    #[component(storage = "SparseSet")]
+    struct Horn(String);
+
+    impl Sound for Bell {
+        fn sound(&self) -> &str {
+            "ding"
+        }
+    }
+
+    impl Sound for Horn {
+        fn sound(&self) -> &str {
+            &self.0
+        }
+    }
+
+    let mut world = World::new();
+    world
+        .register_component_as::<dyn Person, Human>()
+        .register_component_as::<dyn Sound, Bell>()
+        .register_component_as::<dyn Sound, Horn>();
+
+    let entity = world
+        .spawn((
+            Human("Eliza".to_owned(), 31),
+            Bell(3),
+            Horn("toot".to_owned()),
+        ))
+        .id();
+
+    let sounds = world.get_traits::<dyn Sound>(entity).unwrap();
+    assert_eq!(sounds.extract::<Bell>().unwrap().0, 3);
+    assert_eq!(sounds.extract::<Horn>().unwrap().0, "toot");
+
+    // Not registered for `dyn Person`.
+    let people = world.get_traits::<dyn Person>(entity).unwrap();
+    assert!(people.extract::<Bell>().is_none());
+}
+
+// Only mutates Henry, but reads everyone.
+fn age_up_henry(mut people: Query<One<&mut dyn Person>>, mut output: ResMut<Output>) {
+    for mut person in &mut people {
+        if person.name() == "Henry" {
+            let age = person.age();
+            person.set_age(age + 1);
+        }
+        output
+            .0
+            .push(format!("{}: {}", person.name(), person.is_changed()));
+    }
+}
+
+fn age_up_eliza_all(mut people: Query<&mut dyn Person>, mut output: ResMut<Output>) {
+    for people in &mut people {
+        for mut person in people {
+            if person.name() == "Eliza" {
+                let age = person.age();
+                person.set_age(age + 1);
+            }
+            output
+                .0
+                .push(format!("{}: {}", person.name(), person.is_changed()));
+        }
+    }
+}
+
+#[test]
+fn mut_deref_change_detection() {
+    let mut world = World::new();
+    world.init_resource::<Output>();
+    world
+        .register_component_as::<dyn Person, Human>()
+        .register_component_as::<dyn Person, Dolphin>();
+
+    world.spawn(Human("Henry".to_owned(), 22));
+    world.spawn(Human("Eliza".to_owned(), 31));
+    world.spawn(Dolphin(27));
+
+    let mut schedule = Schedule::default();
+    schedule.add_systems((age_up_henry, age_up_eliza_all).chain());
+
+    schedule.run(&mut world);
+    world.resource_mut::<Output>().0.clear();
+    schedule.run(&mut world);
+
+    // Only calling `&mut self` methods should flag a change.
+    assert_eq!(
+        world.resource::<Output>().0,
+        &[
+            "Henry: true",
+            "Eliza: false",
+            "Reginald: false",
+            "Henry: true",
+            "Eliza: true",
+            "Reginald: false",
+        ]
+    );
+}
+
+fn spawn_with_commands(
+    mut commands: Commands,
+    people: Query<&dyn Person>,
+    mut output: ResMut<Output>,
+) {
+    commands.spawn(Human("Henry".to_owned(), 22));
+    commands.spawn(Dolphin(27));
+    // The commands have not been applied yet.
+    output.0.push(format!("{} people", people.iter().count()));
+}
+
+#[test]
+fn query_after_commands_applied() {
+    let mut world = World::new();
+    world.init_resource::<Output>();
+    world
+        .register_component_as::<dyn Person, Human>()
+        .register_component_as::<dyn Person, Dolphin>();
+
+    let mut schedule = Schedule::default();
+    // Chaining inserts a sync point between the systems, which applies the commands.
+    schedule.add_systems((spawn_with_commands, print_added_all_info).chain());
+    schedule.run(&mut world);
+
+    assert_eq!(
+        world.resource::<Output>().0,
+        &["0 people", "Added people:", "Henry: 22", "Reginald: 27", "",]
+    );
+}
+
+fn check_entities(
+    read: Query<(Entity, &dyn Person)>,
+    mut write: Query<(Entity, &mut dyn Person)>,
+    mut output: ResMut<Output>,
+) {
+    for (entity, traits) in &read {
+        assert_eq!(entity, traits.entity());
+    }
+    for (entity, traits) in &mut write {
+        assert_eq!(entity, traits.entity());
+    }
+    output.0.push(format!("checked {}", read.iter().count()));
+}
+
+#[test]
+fn traits_entity() {
+    let mut world = World::new();
+    world.init_resource::<Output>();
+    world
+        .register_component_as::<dyn Person, Human>()
+        .register_component_as::<dyn Person, Dolphin>()
+        .register_component_as::<dyn Messages, RecB>();
+
+    world.spawn(Human("Henry".to_owned(), 22));
+    world.spawn((Human("Eliza".to_owned(), 31), Dolphin(6)));
+    world.spawn((Dolphin(27), RecB(vec![])));
+
+    let mut schedule = Schedule::default();
+    schedule.add_systems(check_entities);
+    schedule.run(&mut world);
+
+    assert_eq!(world.resource::<Output>().0, &["checked 3"]);
+}
+
+classify_trait! {
+    pub enum PersonKind for dyn Person {
+        Human(Human),
+        Dolphin(Dolphin),
+    }
+}
+
+fn print_classified(people: Query<&dyn Person>, mut output: ResMut<Output>) {
+    for person in &people {
+        match PersonKind::classify(&person) {
+            Some(PersonKind::Human(human)) => output.0.push(format!("Human: {}", human.0)),
+            Some(PersonKind::Dolphin(dolphin)) => output.0.push(format!("Dolphin: {}", dolphin.0)),
+            None => output.0.push("Unknown".to_owned()),
+        }
+    }
+}
+
+#[test]
+fn classify() {
+    let mut world = World::new();
+    world.init_resource::<Output>();
+    world
+        .register_component_as::<dyn Person, Human>()
+        .register_component_as::<dyn Person, Dolphin>();
+
+    world.spawn(Human("Henry".to_owned(), 22));
+    world.spawn(Dolphin(27));
+
+    let mut schedule = Schedule::default();
+    schedule.add_systems(print_classified);
+    schedule.run(&mut world);
+
+    assert_eq!(
+        world.resource::<Output>().0,
+        &["Human: Henry", "Dolphin: 27"]
+    );
+}
+
+fn print_classified_one(people: Query<OneTyped<&dyn Person>>, mut output: ResMut<Output>) {
+    for person in &people {
+        match PersonKind::classify_one(person) {
+            Some(PersonKind::Human(human)) => output.0.push(format!("Human: {}", human.0)),
+            Some(PersonKind::Dolphin(dolphin)) => output.0.push(format!("Dolphin: {}", dolphin.0)),
+            None => output.0.push("Unknown".to_owned()),
+        }
+    }
+}
+
+#[test]
+fn classify_one() {
+    let mut world = World::new();
+    world.init_resource::<Output>();
+    world
+        .register_component_as::<dyn Person, Human>()
+        .register_component_as::<dyn Person, Dolphin>();
+
+    world.spawn(Human("Henry".to_owned(), 22));
+    world.spawn(Dolphin(27));
+    // Has more than one impl, so it isn't matched by `OneTyped`.
+    world.spawn((Human("Eliza".to_owned(), 31), Dolphin(6)));
+
+    let mut schedule = Schedule::default();
+    schedule.add_systems(print_classified_one);
+    schedule.run(&mut world);
+
+    assert_eq!(
+        world.resource::<Output>().0,
+        &["Human: Henry", "Dolphin: 27"]
+    );
+}
+
+#[derive(Resource, Default)]
+struct SingleResult(Option<Result<String, SingleTraitError>>);
+
+fn age_up_single(mut people: Query<&mut dyn Person>, mut result: ResMut<SingleResult>) {
+    result.0 = Some(people.single_trait_mut().map(|mut person| {
+        let age = person.age();
+        person.set_age(age + 1);
+        assert!(person.is_changed());
+        format!("{}: {}", person.name(), person.age())
+    }));
+}
+
+fn run_single_trait_mut(world: &mut World) -> Result<String, SingleTraitError> {
+    world.init_resource::<SingleResult>();
+    let mut schedule = Schedule::default();
+    schedule.add_systems(age_up_single);
+    schedule.run(world);
+    world.resource_mut::<SingleResult>().0.take().unwrap()
+}
+
+#[test]
+fn single_trait_mut() {
+    let mut world = World::new();
+    world
+        .register_component_as::<dyn Person, Human>()
+        .register_component_as::<dyn Person, Dolphin>();
+
+    assert_eq!(
+        run_single_trait_mut(&mut world),
+        Err(SingleTraitError::NoEntities(std::any::type_name::<
+            dyn Person,
+        >()))
+    );
+
+    let entity = world.spawn(Human("Henry".to_owned(), 22)).id();
+    assert_eq!(run_single_trait_mut(&mut world), Ok("Henry: 23".to_owned()));
+
+    world.entity_mut(entity).insert(Dolphin(27));
+    assert_eq!(
+        run_single_trait_mut(&mut world),
+        Err(SingleTraitError::MultipleImpls(
+            entity,
+            std::any::type_name::<dyn Person>()
+        ))
+    );
+
+    world.spawn(Dolphin(6));
+    assert_eq!(
+        run_single_trait_mut(&mut world),
+        Err(SingleTraitError::MultipleEntities(std::any::type_name::<
+            dyn Person,
+        >()))
+    );
+}
+
+fn print_optional(
+    people: Query<(Option<&Nickname>, Option<&dyn Person>)>,
+    mut output: ResMut<Output>,
+) {
+    for (nickname, people) in &people {
+        let nickname = nickname.map_or("?", |n| n.0);
+        match people {
+            Some(people) => {
+                let ages: Vec<_> = people.iter().map(|p| p.age().to_string()).collect();
+                output.0.push(format!("{nickname}: {}", ages.join(", ")));
+            }
+            None => output.0.push(format!("{nickname}: None")),
+        }
+    }
+}
+
+fn age_up_optional(mut people: Query<Option<&mut dyn Person>>) {
+    for people in people.iter_mut().flatten() {
+        for mut person in people {
+            let age = person.age();
+            person.set_age(age + 1);
+        }
+    }
+}
+
+#[test]
+fn optional_trait_query() {
+    let mut world = World::new();
+    world.init_resource::<Output>();
+    world
+        .register_component_as::<dyn Person, Human>()
+        .register_component_as::<dyn Person, Dolphin>();
+
+    world.spawn((Nickname("Hank"), Human("Henry".to_owned(), 22)));
+    world.spawn((Nickname("Both"), Human("Eliza".to_owned(), 31), Dolphin(6)));
+    world.spawn((Nickname("Rock"), Fem));
+    world.spawn(Nickname("Nobody"));
+
+    let mut schedule = Schedule::default();
+    schedule.add_systems((print_optional, age_up_optional, print_optional).chain());
+    schedule.run(&mut world);
+
+    assert_eq!(
+        world.resource::<Output>().0,
+        &[
+            "Hank: 22",
+            "Both: 31, 6",
+            "Rock: None",
+            "Nobody: None",
+            "Hank: 23",
+            "Both: 32, 7",
+            "Rock: None",
+            "Nobody: None",
+        ]
+    );
+}
+
+#[derive(Event, Debug, PartialEq)]
+struct Birthday(Entity, u32);
+
+fn send_birthdays(
+    people: Query<&dyn Person, OneChanged<dyn Person>>,
+    mut writer: EventWriter<Birthday>,
+) {
+    people.write_events(&mut writer, |entity, person| {
+        Some(Birthday(entity, person.age()))
+    });
+}
+
+#[test]
+fn write_events() {
+    let mut world = World::new();
+    world.init_resource::<Events<Birthday>>();
+    world
+        .register_component_as::<dyn Person, Human>()
+        .register_component_as::<dyn Person, Dolphin>();
+
+    let henry = world.spawn(Human("Henry".to_owned(), 22)).id();
+    let eliza = world.spawn((Human("Eliza".to_owned(), 31), Fem)).id();
+
+    let mut schedule = Schedule::default();
+    schedule.add_systems((send_birthdays, age_up_fem).chain());
+
+    schedule.run(&mut world);
+    let events: Vec<_> = world.resource_mut::<Events<Birthday>>().drain().collect();
+    assert_eq!(events, [Birthday(henry, 22), Birthday(eliza, 31)]);
+
+    // Only Eliza was aged up.
+    schedule.run(&mut world);
+    let events: Vec<_> = world.resource_mut::<Events<Birthday>>().drain().collect();
+    assert_eq!(events, [Birthday(eliza, 32)]);
+}
+
+#[derive(Default)]
+struct AgeTotal(u32);
+
+impl AgeTotal {
+    fn add(&mut self, person: &dyn Person) {
+        self.0 += person.age();
+    }
+}
+
+fn total_ages(people: Query<&dyn Person>, mut output: ResMut<Output>) {
+    let mut total = AgeTotal::default();
+    people.for_each_trait(|person| total.add(person));
+    output.0.push(format!("Total: {}", total.0));
+}
+
+#[test]
+fn for_each_trait() {
+    let mut world = World::new();
+    world.init_resource::<Output>();
+    world
+        .register_component_as::<dyn Person, Human>()
+        .register_component_as::<dyn Person, Dolphin>();
+
+    world.spawn(Human("Henry".to_owned(), 22));
+    world.spawn((Human("Eliza".to_owned(), 31), Dolphin(6)));
+
+    let mut schedule = Schedule::default();
+    schedule.add_systems(total_ages);
+    schedule.run(&mut world);
+
+    assert_eq!(world.resource::<Output>().0, &["Total: 59"]);
+}
+
+fn ages(traits: ReadTraits<dyn Person>) -> (Entity, Vec<u32>) {
+    (traits.entity(), traits.iter().map(|p| p.age()).collect())
+}
+
+#[test]
+fn manual_batches() {
+    let mut world = World::new();
+    world
+        .register_component_as::<dyn Person, Human>()
+        .register_component_as::<dyn Person, Dolphin>();
+
+    for i in 0..10 {
+        world.spawn(Human(format!("Human {i}"), i));
+        world.spawn((Dolphin(i), Fem));
+        world.spawn((Human(format!("Both {i}"), i), Dolphin(i + 100)));
+    }
+    world.spawn(Fem);
+
+    let mut expected: Vec<_> = world
+        .query::<&dyn Person>()
+        .iter(&world)
+        .map(ages)
+        .collect();
+    expected.sort();
+
+    let state = TraitQueryState::<dyn Person>::init(&mut world);
+    let batches = TraitBatches::new(&world, &state, 3);
+    assert!(batches.batches().iter().all(|batch| batch.len() <= 3));
+
+    let mut actual: Vec<_> = std::thread::scope(|scope| {
+        let handles: Vec<_> = batches
+            .batches()
+            .iter()
+            .map(|batch| {
+                let batches = &batches;
+                scope.spawn(move || {
+                    (0..batch.len())
+                        // SAFETY: `batch` came from `batches`, and `index` is in bounds.
+                        .map(|index| ages(unsafe { batches.fetch_at(batch, index) }))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect()
+    });
+    actual.sort();
+
+    assert_eq!(actual, expected);
+}
+
+mod nested {
+    use super::*;
+
+    // Would shadow the crate if the generated code used a relative path.
+    #[allow(dead_code)]
+    mod bevy_trait_query {}
+
+    #[queryable]
+    pub trait Greeting {
+        fn greet(&self) -> String;
+    }
+
+    impl Greeting for Human {
+        fn greet(&self) -> String {
+            format!("Hello, {}", self.0)
+        }
+    }
+}
+
+#[test]
+fn nested_module_trait() {
+    use nested::Greeting;
+
+    let mut world = World::new();
+    world.register_component_as::<dyn Greeting, Human>();
+    world.spawn(Human("Henry".to_owned(), 22));
+
+    let greetings: Vec<_> = world
+        .query::<One<&dyn Greeting>>()
+        .iter(&world)
+        .map(|greeting| greeting.greet())
+        .collect();
+    assert_eq!(greetings, ["Hello, Henry"]);
+}
+
+fn memoize_ages(mut ages: MemoizedTrait<dyn Person, u32>, mut output: ResMut<Output>) {
+    ages.update(|person| {
+        output.0.push(format!("computed {}", person.name()));
+        person.age()
+    });
+    let total: u32 = ages.iter().map(|(_, _, age)| age).sum();
+    output.0.push(format!("total {total}"));
+}
+
+#[test]
+fn memoized_trait() {
+    let mut world = World::new();
+    world.init_resource::<Output>();
+    world
+        .register_component_as::<dyn Person, Human>()
+        .register_component_as::<dyn Person, Dolphin>();
+
+    let henry = world.spawn(Human("Henry".to_owned(), 22)).id();
+    let flipper = world.spawn(Dolphin(6)).id();
+
+    let mut schedule = Schedule::default();
+    schedule.add_systems(memoize_ages);
+
+    schedule.run(&mut world);
+    assert_eq!(
+        std::mem::take(&mut world.resource_mut::<Output>().0),
+        &["computed Henry", "computed Reginald", "total 28"]
+    );
+
+    // Nothing changed, so no values should be recomputed.
+    schedule.run(&mut world);
+    assert_eq!(
+        std::mem::take(&mut world.resource_mut::<Output>().0),
+        &["total 28"]
+    );
+
+    world.get_mut::<Human>(henry).unwrap().1 = 23;
+    schedule.run(&mut world);
+    assert_eq!(
+        std::mem::take(&mut world.resource_mut::<Output>().0),
+        &["computed Henry", "total 29"]
+    );
+
+    world.despawn(flipper);
+    schedule.run(&mut world);
+    assert_eq!(
+        std::mem::take(&mut world.resource_mut::<Output>().0),
+        &["total 23"]
+    );
+}
+
+fn print_plain(people: Query<OnePlain<&dyn Person>>, mut output: ResMut<Output>) {
+    for person in &people {
+        output
+            .0
+            .push(format!("{}: {}", person.name(), person.age()));
+    }
+}
+
+#[test]
+fn one_plain() {
+    let mut world = World::new();
+    world.init_resource::<Output>();
+    world
+        .register_component_as::<dyn Person, Human>()
+        .register_component_as::<dyn Person, Dolphin>();
+
+    world.spawn(Human("Garbanzo".to_owned(), 7));
+    world.spawn((Human("Garbanzo".to_owned(), 14), Fem));
+    world.spawn(Dolphin(27));
+
+    let mut schedule = Schedule::default();
+    schedule.add_systems(print_plain);
+    schedule.run(&mut world);
+
+    assert_eq!(
+        world.resource::<Output>().0,
+        &["Garbanzo: 7", "Garbanzo: 14", "Reginald: 27"]
+    );
+}
+
+#[test]
+#[should_panic(expected = "Mutable component access must be unique")]
+fn one_plain_then_mut_access_conflicts() {
+    let mut world = World::new();
+    world
+        .register_component_as::<dyn Person, Human>()
+        .register_component_as::<dyn Person, Dolphin>();
+
+    world.query::<(OnePlain<&dyn Person>, &mut dyn Person)>();
+}
+
+fn print_impl_counts(messages: Query<&dyn Messages>, mut output: ResMut<Output>) {
+    for (entity, count) in messages.impl_counts() {
+        output.0.push(format!("{entity}: {count}"));
+    }
+}
+
+#[test]
+fn impl_counts() {
+    let mut world = World::new();
+    world.init_resource::<Output>();
+    world
+        .register_component_as::<dyn Messages, RecA>()
+        .register_component_as::<dyn Messages, RecB>();
+
+    let a = world.spawn(RecA(vec![])).id();
+    let b = world.spawn(RecB(vec![])).id();
+    let both = world.spawn((RecA(vec![]), RecB(vec![]))).id();
+
+    let mut schedule = Schedule::default();
+    schedule.add_systems(print_impl_counts);
+    schedule.run(&mut world);
+
+    assert_eq!(
+        world.resource::<Output>().0,
+        &[format!("{a}: 1"), format!("{b}: 1"), format!("{both}: 2")]
+    );
+}
+
+fn cast_human(ptr: *mut u8) -> *mut dyn Person {
+    ptr as *mut Human
+}
+
+#[test]
+fn register_static_and_dynamic() {
+    for dynamic_first in [false, true] {
+        let mut world = World::new();
+        let human = world.register_component::<Human>();
+        if dynamic_first {
+            // SAFETY: `cast_human` casts a pointer to a `Human`.
+            unsafe { world.register_component_as_dynamic::<dyn Person>(human, cast_human) };
+            world.register_component_as::<dyn Person, Human>();
+        } else {
+            world.register_component_as::<dyn Person, Human>();
+            // SAFETY: `cast_human` casts a pointer to a `Human`.
+            unsafe { world.register_component_as_dynamic::<dyn Person>(human, cast_human) };
+        }
+        assert_eq!(world.trait_impl_storage_breakdown::<dyn Person>(), (1, 0));
+
+        world.spawn(Human("Garbanzo".to_owned(), 7));
+        let names: Vec<Vec<String>> = world
+            .query::<&dyn Person>()
+            .iter(&world)
+            .map(|people| people.iter().map(|p| p.name().to_owned()).collect())
+            .collect();
+        assert_eq!(names, [["Garbanzo"]]);
+    }
+}
+
+fn print_combined_ages(people: Query<&dyn Person>, mut output: ResMut<Output>) {
+    for traits in &people {
+        let total = traits.fold(0, |total, person| total + person.age());
+        output.0.push(format!("{}: {total}", traits.entity()));
+    }
+}
+
+#[test]
+fn fold() {
+    let mut world = World::new();
+    world.init_resource::<Output>();
+    world
+        .register_component_as::<dyn Person, Human>()
+        .register_component_as::<dyn Person, Dolphin>();
+
+    let human = world.spawn(Human("Garbanzo".to_owned(), 7)).id();
+    let both = world
+        .spawn((Human("Garbanzo".to_owned(), 14), Dolphin(27)))
+        .id();
+
+    let mut schedule = Schedule::default();
+    schedule.add_systems(print_combined_ages);
+    schedule.run(&mut world);
+
+    assert_eq!(
+        world.resource::<Output>().0,
+        &[format!("{human}: 7"), format!("{both}: 41")]
+    );
+}
+
+#[derive(Resource)]
+pub struct Mayor(u32);
+
+impl Person for Mayor {
+    fn name(&self) -> &str {
+        "Mayor"
+    }
+    fn age(&self) -> u32 {
+        self.0
+    }
+    fn set_age(&mut self, age: u32) {
+        self.0 = age;
+    }
+}
+
+#[test]
+fn resource_trait() {
+    let mut world = World::new();
+    world.register_resource_as::<dyn Person, Mayor>();
+    assert!(world.resource_trait::<dyn Person>().is_none());
+
+    world.insert_resource(Mayor(64));
+    let mayor = world.resource_trait::<dyn Person>().unwrap();
+    assert_eq!(mayor.name(), "Mayor");
+    assert_eq!(mayor.age(), 64);
+}
+
+fn age_by_group(mut people: Query<&mut dyn Person>) {
+    for mut traits in &mut people {
+        let (mut humans, mut others) = traits.partition_mut(|person| person.name() != "Reginald");
+        for human in &mut humans {
+            human.set_age(human.age() + 1);
+        }
+        for other in &mut others {
+            other.set_age(other.age() + 10);
+        }
+    }
+}
+
+#[test]
+fn partition_mut() {
+    let mut world = World::new();
+    world
+        .register_component_as::<dyn Person, Human>()
+        .register_component_as::<dyn Person, Dolphin>();
+
+    world.spawn(Human("Garbanzo".to_owned(), 7));
+    world.spawn((Human("Garbanzo".to_owned(), 14), Dolphin(27)));
+
+    let mut schedule = Schedule::default();
+    schedule.add_systems(age_by_group);
+    schedule.run(&mut world);
+
+    let ages: Vec<Vec<u32>> = world
+        .query::<&dyn Person>()
+        .iter(&world)
+        .map(|people| people.iter().map(|person| person.age()).collect())
+        .collect();
+    assert_eq!(ages, [vec![8], vec![15, 37]]);
+}
+
+fn count_added(people: Query<&dyn Person>, mut output: ResMut<Output>) {
+    let entities = people.iter().count();
+    let added: usize = people
+        .iter()
+        .map(|traits| traits.iter_added().count())
+        .sum();
+    output.0.push(format!("{entities} entities, {added} added"));
+}
+
+#[test]
+fn spawn_batch() {
+    let mut world = World::new();
+    world.init_resource::<Output>();
+    world
+        .register_component_as::<dyn Person, Human>()
+        .register_component_as::<dyn Person, Dolphin>();
+
+    world.spawn_batch((0..1000).map(|i| Human(format!("Human {i}"), i)));
+    world.spawn_batch((0..500).map(|i| (Human(format!("Both {i}"), i), Dolphin(i))));
+
+    let mut schedule = Schedule::default();
+    schedule.add_systems(count_added);
+    schedule.run(&mut world);
+    schedule.run(&mut world);
+
+    assert_eq!(
+        world.resource::<Output>().0,
+        &["1500 entities, 2000 added", "1500 entities, 0 added"]
+    );
+}
+
+fn par_total_age(people: Query<&dyn Person>, mut output: ResMut<Output>) {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    let total = AtomicU32::new(0);
+    people
+        .par_iter()
+        .batching_strategy(bevy_ecs::query::BatchingStrategy::fixed(16))
+        .for_each(|traits| {
+            for person in traits {
+                total.fetch_add(person.age(), Ordering::Relaxed);
+            }
+        });
+    output.0.push(format!("Total: {}", total.into_inner()));
+}
+
+#[test]
+fn par_iter_batching_strategy() {
+    let mut world = World::new();
+    world.init_resource::<Output>();
+    world
+        .register_component_as::<dyn Person, Human>()
+        .register_component_as::<dyn Person, Dolphin>();
+
+    world.spawn_batch((0..100).map(|_| Human("Garbanzo".to_owned(), 1)));
+    world.spawn_batch((0..100).map(|_| (Human("Garbanzo".to_owned(), 1), Dolphin(2))));
+
+    let mut schedule = Schedule::default();
+    schedule.add_systems(par_total_age);
+    schedule.run(&mut world);
+
+    assert_eq!(world.resource::<Output>().0, &["Total: 400"]);
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "does not match its actual size")]
+fn wrong_registered_size() {
+    let mut world = World::new();
+    let human = world.register_component::<Human>();
+    let mut registry = TraitImplRegistry::<dyn Person>::default();
+    let meta = TraitImplMeta {
+        size_bytes: std::mem::size_of::<Human>() + 1,
+        dyn_ctor: crate::dyn_constructor::DynCtor { cast: cast_human },
+        type_id: None,
+    };
+    registry.register(
+        human,
+        bevy_ecs::component::StorageType::Table,
+        meta,
+        None,
+        None,
+    );
+    world.insert_resource(registry);
+    world.spawn(Human("Garbanzo".to_owned(), 7));
+
+    world.query::<One<&dyn Person>>().iter(&world).count();
+}
+
+fn count_changed(people: Query<&dyn Person>, mut output: ResMut<Output>) {
+    let changed: usize = people
+        .iter()
+        .map(|traits| traits.iter_changed().count())
+        .sum();
+    output.0.push(format!("changed: {changed}"));
+}
+
+#[test]
+fn set_all_ticks() {
+    let mut world = World::new();
+    world.init_resource::<Output>();
+    world
+        .register_component_as::<dyn Person, Human>()
+        .register_component_as::<dyn Person, Dolphin>();
+
+    let human = world.spawn(Human("Garbanzo".to_owned(), 7)).id();
+    world.spawn(Dolphin(27));
+
+    let mut schedule = Schedule::default();
+    schedule.add_systems(count_changed);
+    schedule.run(&mut world);
+    schedule.run(&mut world);
+
+    // Restoring a newer tick causes the impls to be reported as changed.
+    let now = world.change_tick();
+    for mut traits in world.query::<&mut dyn Person>().iter_mut(&mut world) {
+        traits.set_all_ticks(now, now);
+    }
+    schedule.run(&mut world);
+
+    // Restoring an old tick after a change hides the change.
+    world.get_mut::<Human>(human).unwrap().1 += 1;
+    for mut traits in world.query::<&mut dyn Person>().iter_mut(&mut world) {
+        let tick = bevy_ecs::component::Tick::new(0);
+        traits.set_all_ticks(tick, tick);
+    }
+    schedule.run(&mut world);
+
+    assert_eq!(
+        world.resource::<Output>().0,
+        &["changed: 2", "changed: 0", "changed: 2", "changed: 0"]
+    );
+}
+
+#[queryable(upcast)]
+pub trait Sound {
+    fn sound(&self) -> &str;
+}
+
+#[derive(Component)]
+pub struct Cow;
+
+#[derive(Component)]
+pub struct Duck;
+
+impl Sound for Cow {
+    fn sound(&self) -> &str {
+        "moo"
+    }
+}
+
+impl Sound for Duck {
+    fn sound(&self) -> &str {
+        "quack"
+    }
+}
+
+fn make_sound(animal: &dyn Sound) -> String {
+    animal.sound().to_owned()
+}
+
+#[test]
+fn upcast() {
+    assert_eq!(make_sound(Cow.as_dyn()), "moo");
+    assert_eq!(make_sound(Duck.as_dyn()), "quack");
+}
+
+fn print_indexed(people: Query<&dyn Person>, mut output: ResMut<Output>) {
+    for (index, entity, person) in people.iter_indexed() {
+        output
+            .0
+            .push(format!("{index}: {entity} {}", person.name()));
+    }
+}
+
+#[test]
+fn iter_indexed() {
+    let mut world = World::new();
+    world.init_resource::<Output>();
+    world
+        .register_component_as::<dyn Person, Human>()
+        .register_component_as::<dyn Person, Dolphin>();
+
+    let dolphin = world.spawn(Dolphin(27)).id();
+    let human = world.spawn(Human("Garbanzo".to_owned(), 7)).id();
+
+    let mut schedule = Schedule::default();
+    schedule.add_systems(print_indexed);
+    schedule.run(&mut world);
+
+    // The new dolphin is stored next to the old one, so query iteration would
+    // visit it before the human.
+    let new_dolphin = world.spawn(Dolphin(5)).id();
+    schedule.run(&mut world);
+
+    assert_eq!(
+        world.resource::<Output>().0,
+        &[
+            format!("0: {dolphin} Reginald"),
+            format!("1: {human} Garbanzo"),
+            format!("0: {dolphin} Reginald"),
+            format!("1: {human} Garbanzo"),
+            format!("2: {new_dolphin} Reginald"),
+        ]
+    );
+}
+
+#[test]
+fn take_impls() {
+    let mut world = World::new();
+    world.register_component_as::<dyn Person, Dolphin>();
+    for age in 0..100 {
+        world.spawn(Dolphin(age));
+    }
+
+    let mut system_state = bevy_ecs::system::SystemState::<Query<&dyn Person>>::new(&mut world);
+    let people = system_state.get(&world);
+
+    let mut pulled = 0;
+    let ages: Vec<u32> = people
+        .take_impls(3)
+        .inspect(|_| pulled += 1)
+        .map(|person| person.age())
+        .collect();
+    assert_eq!(ages, [0, 1, 2]);
+    // Only three impls were pulled through the iterator.
+    assert_eq!(pulled, 3);
+
+    assert_eq!(people.take_impls(0).count(), 0);
+    // Asking for more impls than exist yields all of them.
+    assert_eq!(people.take_impls(1000).count(), 100);
+}
+
+#[test]
+fn group_by_component() {
+    let mut world = World::new();
+    world
+        .register_component_as::<dyn Person, Human>()
+        .register_component_as::<dyn Person, Dolphin>();
+
+    world.spawn(Human("Garbanzo".to_owned(), 7));
+    world.spawn((Human("Garbanzo".to_owned(), 14), Dolphin(27)));
+    world.spawn(Human("Garbanzo".to_owned(), 21));
+
+    let human = world.component_id::<Human>().unwrap();
+    let dolphin = world.component_id::<Dolphin>().unwrap();
+
+    let mut system_state = bevy_ecs::system::SystemState::<Query<&dyn Person>>::new(&mut world);
+    let people = system_state.get(&world);
+    let groups = people.group_by_component();
+    assert_eq!(groups.len(), 2);
+    assert_eq!(groups[&human].len(), 3);
+    assert_eq!(groups[&dolphin].len(), 1);
+    assert_eq!(groups[&dolphin][0].age(), 27);
+}
+
+fn print_changed_fem(people: Query<&dyn Person, With<Fem>>, mut output: ResMut<Output>) {
+    for person in people.iter().flat_map(|traits| traits.iter_changed()) {
+        output.0.push(format!("fem changed: {}", person.name()));
+    }
+}
+
+fn print_changed_not_fem(people: Query<&dyn Person, Without<Fem>>, mut output: ResMut<Output>) {
+    for person in people.iter().flat_map(|traits| traits.iter_changed()) {
+        output.0.push(format!("not fem changed: {}", person.name()));
+    }
+}
+
+#[test]
+fn without_filter_change_detection() {
+    let mut world = World::new();
+    world.init_resource::<Output>();
+    world
+        .register_component_as::<dyn Person, Human>()
+        .register_component_as::<dyn Person, Dolphin>();
+
+    world.spawn(Human("Henry".to_owned(), 22));
+    let garbanzo = world.spawn((Human("Garbanzo".to_owned(), 17), Fem)).id();
+
+    let mut print = Schedule::default();
+    print.add_systems((print_changed_fem, print_changed_not_fem).chain());
+    let mut age_up = Schedule::default();
+    age_up.add_systems(age_up_not);
+
+    print.run(&mut world);
+    print.run(&mut world);
+
+    // Mutating through the filtered query only marks the unfiltered entity as changed.
+    age_up.run(&mut world);
+    print.run(&mut world);
+
+    // Changing the filtered-out entity isn't seen through `Without<Fem>`.
+    world.get_mut::<Human>(garbanzo).unwrap().1 += 1;
+    print.run(&mut world);
+
+    // Adding a second impl to an entity matched by `Without<Fem>` only reports the new impl.
+    let henry = world
+        .query_filtered::<Entity, Without<Fem>>()
+        .single(&world);
+    world.entity_mut(henry).insert(Dolphin(27));
+    print.run(&mut world);
+
+    assert_eq!(
+        world.resource::<Output>().0,
+        &[
+            "fem changed: Garbanzo",
+            "not fem changed: Henry",
+            "not fem changed: Henry",
+            "fem changed: Garbanzo",
+            "not fem changed: Reginald",
+        ]
+    );
+
+    let ages: Vec<u32> = world
+        .query_filtered::<&dyn Person, Without<Fem>>()
+        .iter(&world)
+        .flat_map(|traits| traits.iter().map(|p| p.age()).collect::<Vec<_>>())
+        .collect();
+    assert_eq!(ages, [23, 27]);
+}
+
+fn register_humans(mut commands: Commands) {
+    commands.register_trait_impl::<dyn Person, Human>();
+    commands.spawn(Human("Garbanzo".to_owned(), 7));
+}
+
+fn register_dolphins(mut commands: Commands) {
+    commands.register_trait_impl::<dyn Person, Dolphin>();
+    commands.spawn(Dolphin(27));
+}
+
+fn print_names(people: Query<&dyn Person>, mut output: ResMut<Output>) {
+    for person in people.iter().flatten() {
+        output.0.push(person.name().to_owned());
+    }
+}
+
+#[test]
+fn register_with_commands() {
+    let mut world = World::new();
+    world.init_resource::<Output>();
+
+    let mut startup = Schedule::default();
+    startup.add_systems(register_humans);
+    startup.run(&mut world);
+
+    let mut update = Schedule::default();
+    update.add_systems(print_names);
+    update.run(&mut world);
+
+    // The registry is sealed now, so this registration is rejected.
+    let mut late = Schedule::default();
+    late.add_systems(register_dolphins);
+    late.run(&mut world);
+    update.run(&mut world);
+
+    assert_eq!(world.trait_impl_storage_breakdown::<dyn Person>(), (1, 0));
+    assert_eq!(world.resource::<Output>().0, &["Garbanzo", "Garbanzo"]);
+}
+
+fn rename_humans_untyped(
+    mut people: Query<&mut dyn Person>,
+    components: &bevy_ecs::component::Components,
+) {
+    let human = components.component_id::<Human>().unwrap();
+    for mut traits in &mut people {
+        for (component, untyped) in traits.iter_untyped_mut() {
+            if component == human {
+                // SAFETY: The component is a `Human`.
+                unsafe { untyped.into_inner().deref_mut::<Human>() }.0 = "Henry".to_owned();
+            }
+        }
+    }
+}
+
+fn print_changed_names(people: Query<&dyn Person>, mut output: ResMut<Output>) {
+    for person in people.iter().flat_map(|traits| traits.iter_changed()) {
+        output.0.push(person.name().to_owned());
+    }
+}
+
+#[test]
+fn iter_untyped_mut() {
+    let mut world = World::new();
+    world.init_resource::<Output>();
+    world
+        .register_component_as::<dyn Person, Human>()
+        .register_component_as::<dyn Person, Dolphin>();
+
+    world.spawn((Human("Garbanzo".to_owned(), 7), Dolphin(27)));
+
+    let mut print = Schedule::default();
+    print.add_systems(print_changed_names);
+    let mut rename = Schedule::default();
+    rename.add_systems(rename_humans_untyped);
+
+    print.run(&mut world);
+    print.run(&mut world);
+    rename.run(&mut world);
+    print.run(&mut world);
+
+    assert_eq!(
+        world.resource::<Output>().0,
+        &["Garbanzo", "Reginald", "Henry"]
+    );
+}
+
+#[derive(Component)]
+struct Tag<const N: usize>;
+
+fn spawn_tagged<const N: usize>(world: &mut World) {
+    world.spawn((Human("Garbanzo".to_owned(), N as u32), Tag::<N>));
+}
+
+#[test]
+fn trait_query_report() {
+    let mut world = World::new();
+    assert_eq!(
+        world.trait_query_report::<dyn Messages>(),
+        TraitQueryReport::default()
+    );
+
+    world
+        .register_component_as::<dyn Person, Human>()
+        .register_component_as::<dyn Person, Dolphin>()
+        .register_component_as::<dyn Messages, RecA>()
+        .register_component_as::<dyn Messages, RecB>();
+
+    for _ in 0..100 {
+        world.spawn(RecA(vec![]));
+    }
+    world.spawn(RecB(vec![]));
+
+    let report = world.trait_query_report::<dyn Messages>();
+    assert_eq!(
+        report,
+        TraitQueryReport {
+            matched_archetypes: 2,
+            matched_entities: 101,
+            registered_impls: 2,
+            table_impls: 1,
+            sparse_impls: 1,
+        }
+    );
+    assert!(!report.is_fragmented());
+
+    // Every entity is in its own archetype.
+    spawn_tagged::<0>(&mut world);
+    spawn_tagged::<1>(&mut world);
+    spawn_tagged::<2>(&mut world);
+    spawn_tagged::<3>(&mut world);
+    spawn_tagged::<4>(&mut world);
+    spawn_tagged::<5>(&mut world);
+    spawn_tagged::<6>(&mut world);
+    spawn_tagged::<7>(&mut world);
+    spawn_tagged::<8>(&mut world);
+    spawn_tagged::<9>(&mut world);
+    spawn_tagged::<10>(&mut world);
+    spawn_tagged::<11>(&mut world);
+    spawn_tagged::<12>(&mut world);
+    spawn_tagged::<13>(&mut world);
+    spawn_tagged::<14>(&mut world);
+    world.spawn(Dolphin(27));
+
+    let report = world.trait_query_report::<dyn Person>();
+    assert_eq!(
+        report,
+        TraitQueryReport {
+            matched_archetypes: 16,
+            matched_entities: 16,
+            registered_impls: 2,
+            table_impls: 2,
+            sparse_impls: 0,
+        }
+    );
+    assert_eq!(report.entities_per_archetype(), 1.0);
+    assert!(report.is_fragmented());
+    assert!(report.to_string().contains("warning"));
+}
+
+fn print_exactly_two(people: Query<Exactly<2, &dyn Person>>, mut output: ResMut<Output>) {
+    for traits in &people {
+        let names: Vec<&str> = traits.iter().map(|person| person.name()).collect();
+        output.0.push(names.join(", "));
+    }
+}
+
+#[test]
+fn exactly() {
+    let mut world = World::new();
+    world.init_resource::<Output>();
+    world
+        .register_component_as::<dyn Person, Human>()
+        .register_component_as::<dyn Person, Dolphin>();
+
+    world.spawn(Human("Henry".to_owned(), 22));
+    world.spawn((Human("Garbanzo".to_owned(), 17), Dolphin(17)));
+    world.spawn((Dolphin(27), Fem));
+
+    let mut schedule = Schedule::default();
+    schedule.add_systems(print_exactly_two);
+    schedule.run(&mut world);
+
+    assert_eq!(world.resource::<Output>().0, &["Garbanzo, Reginald"]);
+
+    let singles = world
+        .query::<Exactly<1, &dyn Person>>()
+        .iter(&world)
+        .count();
+    assert_eq!(singles, 2);
+}
+
+#[test]
+fn try_into_array() {
+    let mut world = World::new();
+    world
+        .register_component_as::<dyn Person, Human>()
+        .register_component_as::<dyn Person, Dolphin>();
+
+    let one = world.spawn(Human("Henry".to_owned(), 22)).id();
+    let two = world
+        .spawn((Human("Garbanzo".to_owned(), 17), Dolphin(27)))
+        .id();
+
+    let [human, dolphin] = world
+        .get_traits::<dyn Person>(two)
+        .unwrap()
+        .try_into_array::<2>()
+        .unwrap();
+    assert_eq!(human.name(), "Garbanzo");
+    assert_eq!(dolphin.age(), 27);
+
+    let traits = world.get_traits::<dyn Person>(two).unwrap();
+    assert!(traits.try_into_array::<1>().is_none());
+    let traits = world.get_traits::<dyn Person>(two).unwrap();
+    assert!(traits.try_into_array::<3>().is_none());
+
+    let [henry] = world
+        .get_traits::<dyn Person>(one)
+        .unwrap()
+        .try_into_array()
+        .unwrap();
+    assert_eq!(henry.name(), "Henry");
+    let traits = world.get_traits::<dyn Person>(one).unwrap();
+    assert!(traits.try_into_array::<2>().is_none());
+}
+
+fn print_write_traits_len(mut messages: Query<&mut dyn Messages>, mut output: ResMut<Output>) {
+    for traits in &mut messages {
+        output.0.push(format!(
+            "{}: {} {}",
+            traits.entity(),
+            traits.len(),
+            traits.is_empty()
+        ));
+    }
+}
+
+#[test]
+fn write_traits_len() {
+    let mut world = World::new();
+    world.init_resource::<Output>();
+    world
+        .register_component_as::<dyn Messages, RecA>()
+        .register_component_as::<dyn Messages, RecB>();
+
+    let a = world.spawn(RecA(vec![])).id();
+    let b = world.spawn(RecB(vec![])).id();
+    let both = world.spawn((RecA(vec![]), RecB(vec![]))).id();
+
+    let mut schedule = Schedule::default();
+    schedule.add_systems(print_write_traits_len);
+    schedule.run(&mut world);
+
+    assert_eq!(
+        world.resource::<Output>().0,
+        &[
+            format!("{a}: 1 false"),
+            format!("{b}: 1 false"),
+            format!("{both}: 2 false")
+        ]
+    );
+}
+
+#[derive(bevy_ecs::system::SystemParam)]
+struct People<'w, 's> {
+    all: Query<'w, 's, &'static mut dyn Person, Without<Fem>>,
+    fem: Query<'w, 's, One<&'static dyn Person>, With<Fem>>,
+}
+
+fn age_up_people_param(mut people: People, mut output: ResMut<Output>) {
+    for mut person in people.all.iter_mut().flatten() {
+        let age = person.age();
+        person.set_age(age + 1);
+        output
+            .0
+            .push(format!("{}: {}", person.name(), person.age()));
+    }
+    for person in &people.fem {
+        output
+            .0
+            .push(format!("{}: {}", person.name(), person.age()));
+    }
+}
+
+#[test]
+fn derived_system_param() {
+    let mut world = World::new();
+    world.init_resource::<Output>();
+    world
+        .register_component_as::<dyn Person, Human>()
+        .register_component_as::<dyn Person, Dolphin>();
+
+    world.spawn(Human("Henry".to_owned(), 22));
+    world.spawn((Dolphin(27), Fem));
+
+    let mut schedule = Schedule::default();
+    schedule.add_systems(age_up_people_param);
+    schedule.run(&mut world);
+
+    assert_eq!(world.resource::<Output>().0, &["Henry: 23", "Reginald: 27"]);
+}
+
+#[derive(Component, Default)]
+struct Description(String);
+
+fn describe_people(mut people: Query<(&dyn Person, &mut Description)>) {
+    for (traits, mut description) in &mut people {
+        let names: Vec<&str> = traits.iter().map(|person| person.name()).collect();
+        description.0 = names.join(" and ");
+    }
+}
+
+fn describe_person(mut people: Query<(One<&dyn Person>, &mut Description)>) {
+    for (person, mut description) in &mut people {
+        description.0 = format!("{} ({})", person.name(), person.age());
+    }
+}
+
+#[test]
+fn trait_query_with_mut_component() {
+    let mut world = World::new();
+    world
+        .register_component_as::<dyn Person, Human>()
+        .register_component_as::<dyn Person, Dolphin>();
+
+    let henry = world
+        .spawn((Human("Henry".to_owned(), 22), Description::default()))
+        .id();
+    let pair = world
+        .spawn((
+            Human("Garbanzo".to_owned(), 17),
+            Dolphin(27),
+            Description::default(),
+        ))
+        .id();
+    // Not matched, since it has no impls.
+    let nobody = world.spawn(Description::default()).id();
+
+    let mut schedule = Schedule::default();
+    schedule.add_systems(describe_people);
+    schedule.run(&mut world);
+
+    assert_eq!(world.get::<Description>(henry).unwrap().0, "Henry");
+    assert_eq!(
+        world.get::<Description>(pair).unwrap().0,
+        "Garbanzo and Reginald"
+    );
+    assert_eq!(world.get::<Description>(nobody).unwrap().0, "");
+
+    let mut schedule = Schedule::default();
+    schedule.add_systems(describe_person);
+    schedule.run(&mut world);
+
+    assert_eq!(world.get::<Description>(henry).unwrap().0, "Henry (22)");
+    assert_eq!(
+        world.get::<Description>(pair).unwrap().0,
+        "Garbanzo and Reginald"
+    );
+}
+
+#[test]
+fn register_components_as_macro() {
+    let mut world = World::new();
+    crate::register_components_as!(world,
+        dyn Person => Human, Dolphin;
+        dyn Messages => RecA, RecB,;
+    );
+    assert_eq!(world.trait_impl_storage_breakdown::<dyn Person>(), (2, 0));
+    assert_eq!(world.trait_impl_storage_breakdown::<dyn Messages>(), (1, 1));
+
+    let mut world = World::new();
+    crate::register_components_as!(&mut world, dyn Person => Dolphin);
+    assert_eq!(world.trait_impl_storage_breakdown::<dyn Person>(), (1, 0));
+}
+
+#[test]
+fn map_one() {
+    let mut world = World::new();
+    world
+        .register_component_as::<dyn Person, Human>()
+        .register_component_as::<dyn Person, Dolphin>();
+
+    let henry = world.spawn(Human("Henry".to_owned(), 22)).id();
+    let pair = world
+        .spawn((Human("Garbanzo".to_owned(), 17), Dolphin(27)))
+        .id();
+    let dolphin = world.spawn(Dolphin(5)).id();
+    let nobody = world.spawn(Fem).id();
+
+    let mut system_state = bevy_ecs::system::SystemState::<Query<&dyn Person>>::new(&mut world);
+    let people = system_state.get(&world);
+    let map = people.map_one();
+    assert_eq!(map.len(), 3);
+    assert_eq!(map[&henry].name(), "Henry");
+    // Human was registered first, so it is used for entities with both impls.
+    assert_eq!(map[&pair].name(), "Garbanzo");
+    assert_eq!(map[&dolphin].age(), 5);
+    assert!(!map.contains_key(&nobody));
+}
+
+#[cfg(feature = "bevy_app")]
+struct HumanPlugin;
+
+#[cfg(feature = "bevy_app")]
+impl bevy_app::Plugin for HumanPlugin {
+    fn build(&self, app: &mut bevy_app::App) {
+        app.register_component_as::<dyn Person, Human>();
+    }
+}
+
+#[cfg(feature = "bevy_app")]
+struct DolphinPlugin;
+
+#[cfg(feature = "bevy_app")]
+impl bevy_app::Plugin for DolphinPlugin {
+    fn build(&self, app: &mut bevy_app::App) {
+        app.register_component_as::<dyn Person, Dolphin>();
+    }
+}
+
+#[cfg(feature = "bevy_app")]
+fn names_with_plugin_order(dolphins_first: bool) -> Vec<String> {
+    let mut app = bevy_app::App::empty();
+    app.deterministic_trait_queries();
+    if dolphins_first {
+        app.add_plugins((DolphinPlugin, HumanPlugin));
+    } else {
+        app.add_plugins((HumanPlugin, DolphinPlugin));
+    }
+    let world = app.world_mut();
+    world.spawn((Human("Garbanzo".to_owned(), 7), Dolphin(27)));
+    world
+        .query::<&dyn Person>()
+        .iter(world)
+        .flatten()
+        .map(|person| person.name().to_owned())
+        .collect()
+}
+
+#[test]
+#[cfg(feature = "bevy_app")]
+fn deterministic_trait_queries() {
+    let expected = ["Reginald", "Garbanzo"];
+    assert_eq!(names_with_plugin_order(false), expected);
+    assert_eq!(names_with_plugin_order(true), expected);
+}
+
+fn age_up_dolphins_by_id(
+    mut people: Query<&mut dyn Person>,
+    components: &bevy_ecs::component::Components,
+) {
+    let dolphin = components.component_id::<Dolphin>().unwrap();
+    for mut traits in &mut people {
+        for (component, mut person) in traits.iter_mut_with_id() {
+            if component == dolphin {
+                let age = person.age();
+                person.set_age(age + 1);
+            }
+        }
+    }
+}
+
+#[test]
+fn iter_mut_with_id() {
+    let mut world = World::new();
+    world
+        .register_component_as::<dyn Person, Human>()
+        .register_component_as::<dyn Person, Dolphin>();
+
+    let pair = world
+        .spawn((Human("Garbanzo".to_owned(), 17), Dolphin(27)))
+        .id();
+    let human = world.spawn(Human("Henry".to_owned(), 22)).id();
+
+    let mut schedule = Schedule::default();
+    schedule.add_systems(age_up_dolphins_by_id);
+    schedule.run(&mut world);
+
+    assert_eq!(world.get::<Dolphin>(pair).unwrap().0, 28);
+    assert_eq!(world.get::<Human>(pair).unwrap().1, 17);
+    assert_eq!(world.get::<Human>(human).unwrap().1, 22);
+}
+
+fn birthday(mut person: Mut<dyn Person>) {
+    let age = person.age();
+    person.set_age(age + 1);
+}
+
+fn double_birthday(mut people: Query<&mut dyn Person>) {
+    for traits in &mut people {
+        for mut person in traits {
+            // `reborrow` works for trait objects too, so `person` is still usable afterwards.
+            birthday(person.reborrow());
+            birthday(person.reborrow());
+            assert!(person.is_changed());
+        }
+    }
+}
+
+#[test]
+fn reborrow_trait_object() {
+    let mut world = World::new();
+    world
+        .register_component_as::<dyn Person, Human>()
+        .register_component_as::<dyn Person, Dolphin>();
+
+    let pair = world
+        .spawn((Human("Garbanzo".to_owned(), 17), Dolphin(27)))
+        .id();
+
+    let mut schedule = Schedule::default();
+    schedule.add_systems(double_birthday);
+    schedule.run(&mut world);
+
+    assert_eq!(world.get::<Human>(pair).unwrap().1, 19);
+    assert_eq!(world.get::<Dolphin>(pair).unwrap().0, 29);
+}
+
+#[derive(Component)]
+pub struct Parrot(Vec<String>);
+
+impl Person for Parrot {
+    fn name(&self) -> &str {
+        "Polly"
+    }
+    fn age(&self) -> u32 {
+        3
+    }
+    fn set_age(&mut self, _: u32) {}
+}
+
+impl Messages for Parrot {
+    fn send(&mut self, m: &dyn Display) {
+        self.0.push(format!("Parrot: {m}"));
+    }
+    fn read(&self) -> &[String] {
+        &self.0
+    }
+}
+
+fn print_either(query: Query<EitherTrait<dyn Person, dyn Messages>>, mut output: ResMut<Output>) {
+    for traits in &query {
+        let items: Vec<String> = traits
+            .iter()
+            .map(|item| match item {
+                Either::Left(person) => format!("person {}", person.name()),
+                Either::Right(messages) => format!("messages {:?}", messages.read()),
+            })
+            .collect();
+        output.0.push(items.join(", "));
+    }
+}
+
+#[test]
+fn either_trait() {
+    let mut world = World::new();
+    world.init_resource::<Output>();
+    world
+        .register_component_as::<dyn Person, Human>()
+        .register_component_as::<dyn Person, Parrot>()
+        .register_component_as::<dyn Messages, RecA>()
+        .register_component_as::<dyn Messages, RecB>()
+        .register_component_as::<dyn Messages, Parrot>();
+
+    world.spawn(Human("Henry".to_owned(), 22));
+    world.spawn(RecB(vec!["hi".to_owned()]));
+    world.spawn((Human("Garbanzo".to_owned(), 17), RecA(vec![])));
+    world.spawn(Parrot(vec!["squawk".to_owned()]));
+    world.spawn(Fem);
+
+    let mut schedule = Schedule::default();
+    schedule.add_systems(print_either);
+    schedule.run(&mut world);
+
+    let mut output = world.resource::<Output>().0.clone();
+    output.sort();
+    assert_eq!(
+        output,
+        &[
+            "messages [\"hi\"]",
+            "person Garbanzo, messages []",
+            "person Henry",
+            // A component implementing both traits is yielded for each of them.
+            "person Polly, messages [\"squawk\"]",
+        ]
+    );
+}
+
+fn either_then_mut_messages(
+    _: Query<EitherTrait<dyn Person, dyn Messages>>,
+    _: Query<&mut dyn Messages>,
+) {
+}
+
+#[test]
+#[should_panic]
+fn either_trait_conflicts_with_mut_access() {
+    let mut world = World::new();
+    world
+        .register_component_as::<dyn Person, Human>()
+        .register_component_as::<dyn Messages, RecA>();
+
+    let mut schedule = Schedule::default();
+    schedule.add_systems(either_then_mut_messages);
+    schedule.run(&mut world);
+}
+
+fn print_typed_messages(query: Query<OneTyped<&dyn Messages>>, mut output: ResMut<Output>) {
+    for messages in &query {
+        let storage = if messages.type_id() == std::any::TypeId::of::<RecA>() {
+            "table"
+        } else if messages.is::<RecB>() {
+            "sparse"
+        } else {
+            "unknown"
+        };
+        output.0.push(format!("{storage}: {:?}", messages.read()));
+        // Downcasting only succeeds for the concrete type of the impl.
+        assert_eq!(messages.is::<RecA>(), messages.downcast::<RecA>().is_some());
+    }
+}
+
+#[test]
+fn one_typed() {
+    let mut world = World::new();
+    world.init_resource::<Output>();
+    world
+        .register_component_as::<dyn Messages, RecA>()
+        .register_component_as::<dyn Messages, RecB>();
+
+    world.spawn(RecA(vec!["a".to_owned()]));
+    world.spawn(RecB(vec!["b".to_owned()]));
+    // Entities with more than one impl are not matched.
+    world.spawn((RecA(vec![]), RecB(vec![])));
+
+    let mut schedule = Schedule::default();
+    schedule.add_systems(print_typed_messages);
+    schedule.run(&mut world);
+
+    let mut output = world.resource::<Output>().0.clone();
+    output.sort();
+    assert_eq!(output, &[r#"sparse: ["b"]"#, r#"table: ["a"]"#]);
+}
+
+#[test]
+fn scan_trait_impls() {
+    let mut world = World::new();
+    world
+        .register_component_as::<dyn Messages, RecA>()
+        .register_component_as::<dyn Messages, RecB>();
+
+    world.spawn(RecA(vec![]));
+    world.spawn(RecB(vec![]));
+    world.spawn((RecA(vec![]), RecB(vec![])));
+    world.spawn(Human("Garbanzo".to_owned(), 7));
+
+    let mut scanned = world.scan_trait_impls::<dyn Messages>();
+    scanned.sort();
+
+    let mut queried: Vec<_> = world
+        .query::<(Entity, &dyn Messages)>()
+        .iter(&world)
+        .flat_map(|(entity, traits)| {
+            traits
+                .iter_with_id()
+                .map(move |(component, _)| (entity, component))
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    queried.sort();
+
+    assert_eq!(scanned.len(), 4);
+    assert_eq!(scanned, queried);
+}
+
+fn print_messages_in_order(mut messages: Query<&mut dyn Messages>, mut output: ResMut<Output>) {
+    for mut traits in &mut messages {
+        let read: Vec<_> = traits.iter().flat_map(|m| m.read().to_vec()).collect();
+        output.0.push(format!("read: {read:?}"));
+        let written: Vec<_> = traits.iter_mut().flat_map(|m| m.read().to_vec()).collect();
+        output.0.push(format!("write: {written:?}"));
+    }
+}
+
+#[test]
+fn iteration_follows_registration_order() {
+    let mut world = World::new();
+    world.init_resource::<Output>();
+    // Register the sparse set impl before the table impl.
+    world
+        .register_component_as::<dyn Messages, RecB>()
+        .register_component_as::<dyn Messages, RecA>();
+
+    world.spawn((RecA(vec!["a".to_owned()]), RecB(vec!["b".to_owned()])));
+
+    let mut schedule = Schedule::default();
+    schedule.add_systems(print_messages_in_order);
+    schedule.run(&mut world);
+
+    assert_eq!(
+        world.resource::<Output>().0,
+        &[r#"read: ["b", "a"]"#, r#"write: ["b", "a"]"#]
+    );
+}
+
+fn print_change_kinds(people: Query<&dyn Person>, mut output: ResMut<Output>) {
+    for traits in &people {
+        for (person, kind) in traits.iter_classified() {
+            output.0.push(format!("{}: {kind:?}", person.name()));
+        }
+    }
+}
+
+fn age_up_humans(mut humans: Query<&mut Human>) {
+    for mut human in &mut humans {
+        human.1 += 1;
+    }
+}
+
+fn give_parrots(mut commands: Commands, humans: Query<Entity, (With<Human>, Without<Parrot>)>) {
+    for entity in &humans {
+        commands.entity(entity).insert(Parrot(vec![]));
+    }
+}
+
+#[test]
+fn iter_classified() {
+    let mut world = World::new();
+    world.init_resource::<Output>();
+    world
+        .register_component_as::<dyn Person, Human>()
+        .register_component_as::<dyn Person, Dolphin>()
+        .register_component_as::<dyn Person, Parrot>();
+
+    world.spawn((Human("Garbanzo".to_owned(), 7), Dolphin(27)));
+
+    let mut schedule = Schedule::default();
+    schedule.add_systems((print_change_kinds, age_up_humans, give_parrots).chain());
+
+    schedule.run(&mut world);
+    world.resource_mut::<Output>().0.clear();
+    // The human was changed and the parrot was added after the first run.
+    schedule.run(&mut world);
+
+    assert_eq!(
+        world.resource::<Output>().0,
+        &["Garbanzo: Changed", "Reginald: Unchanged", "Polly: Added"]
+    );
+}
+
+#[test]
+fn update_archetypes_matches_new_archetypes() {
+    let mut world = World::new();
+    world
+        .register_component_as::<dyn Messages, RecA>()
+        .register_component_as::<dyn Messages, RecB>();
+
+    world.spawn(RecA(vec!["before".to_owned()]));
+    let mut state = QueryState::<All<&dyn Messages>>::new(&mut world);
+
+    // These all create new archetypes, including one with only a sparse set impl.
+    world.spawn(RecB(vec!["sparse".to_owned()]));
+    world.spawn((RecA(vec!["both".to_owned()]), RecB(vec![])));
+    world.spawn((
+        RecA(vec!["human".to_owned()]),
+        Human("Garbanzo".to_owned(), 7),
+    ));
+    world.spawn(Human("Henry".to_owned(), 22));
+
+    // `iter_manual` doesn't update archetypes itself, so the new ones
+    // are only visited if `update_archetypes` matched them.
+    assert_eq!(state.iter_manual(&world).count(), 1);
+    state.update_archetypes(&world);
+
+    let mut messages: Vec<_> = state
+        .iter_manual(&world)
+        .flat_map(|traits| {
+            traits
+                .iter()
+                .flat_map(|m| m.read().to_vec())
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    messages.sort();
+    assert_eq!(messages, ["before", "both", "human", "sparse"]);
+}
+
+#[test]
+fn try_register_after_seal() {
+    let mut world = World::new();
+    assert!(world
+        .try_register_component_as::<dyn Person, Human>()
+        .is_ok());
+
+    // Initializing a trait query seals the registry.
+    world.query::<&dyn Person>();
+
+    assert!(world
+        .try_register_component_as::<dyn Person, Human>()
+        .is_ok());
+    let error = world
+        .try_register_component_as::<dyn Person, Dolphin>()
+        .err()
+        .unwrap();
+    assert_eq!(error.0, std::any::type_name::<dyn Person>());
+    assert_eq!(world.trait_impl_storage_breakdown::<dyn Person>(), (1, 0));
+}
+
+#[cfg(feature = "bevy_app")]
+fn spawn_human_and_dolphin(mut commands: Commands) {
+    commands.spawn(Human("Garbanzo".to_owned(), 7));
+    commands.spawn(Dolphin(27));
+}
+
+#[test]
+#[cfg(feature = "bevy_app")]
+fn register_component_as_lazy() {
+    use bevy_app::{App, Startup, Update};
+
+    let mut app = App::new();
+    app.init_resource::<Output>()
+        .register_component_as::<dyn Person, Human>()
+        .register_component_as_lazy::<dyn Person, Dolphin>()
+        .add_systems(Startup, spawn_human_and_dolphin)
+        .add_systems(Update, print_names);
+
+    // Dolphins are registered after the startup systems run,
+    // but before the query in `Update` is initialized.
+    app.update();
+    let mut output = app.world().resource::<Output>().0.clone();
+    output.sort();
+    assert_eq!(output, ["Garbanzo", "Reginald"]);
+}
+
+#[test]
+#[cfg(feature = "bevy_app")]
+#[should_panic = "Cannot lazily register"]
+fn register_component_as_lazy_after_seal() {
+    use bevy_app::{App, Startup};
+
+    let mut app = App::new();
+    app.init_resource::<Output>()
+        .register_component_as::<dyn Person, Human>()
+        .register_component_as_lazy::<dyn Person, Dolphin>()
+        // Initializing this query seals the registry before `PreUpdate` runs.
+        .add_systems(Startup, print_names);
+
+    app.update();
+}
+
+fn print_with_any_filter_info(
+    people: Query<Entity, WithAny<dyn Person>>,
+    mut output: ResMut<Output>,
+) {
+    output.0.push(format!("{} people", people.iter().len()));
+    for person in &people {
+        output.0.push(format!("{person}"));
+    }
+}
+
+#[test]
+fn with_any_filter() {
+    let mut world = World::new();
+    world.init_resource::<Output>();
+    world
+        .register_component_as::<dyn Person, Human>()
+        .register_component_as::<dyn Person, Dolphin>();
+
+    world.spawn(Human("Henry".to_owned(), 22));
+    world.spawn((Human("Henry".to_owned(), 22), Dolphin(22)));
+    world.spawn(Dolphin(22));
+    world.spawn(Fem);
+
+    let mut schedule = Schedule::default();
+    schedule.add_systems(print_with_any_filter_info);
+    schedule.run(&mut world);
+
+    assert_eq!(
+        world.resource::<Output>().0,
+        &["3 people", "0v1", "1v1", "2v1"]
+    );
+}
+
+fn tag_people_and_others(
+    mut people: Query<&mut Fem, WithAny<dyn Person>>,
+    mut others: Query<&mut Fem, WithoutAny<dyn Person>>,
+    mut output: ResMut<Output>,
+) {
+    output.0.push(format!(
+        "{} people, {} others",
+        people.iter_mut().count(),
+        others.iter_mut().count()
+    ));
+}
+
+// `WithAny` and `WithoutAny` are disjoint, so these queries must not conflict.
+#[test]
+fn with_any_is_disjoint_from_without_any() {
+    let mut world = World::new();
+    world.init_resource::<Output>();
+    world
+        .register_component_as::<dyn Person, Human>()
+        .register_component_as::<dyn Person, Dolphin>();
+
+    world.spawn((Human("Henry".to_owned(), 22), Fem));
+    world.spawn((Dolphin(22), Fem));
+    world.spawn(Fem);
+
+    let mut schedule = Schedule::default();
+    schedule.add_systems(tag_people_and_others);
+    schedule.run(&mut world);
+
+    assert_eq!(world.resource::<Output>().0, &["2 people, 1 others"]);
+}
+
+fn print_changed_mut_with_id(
+    mut q: Query<&mut dyn Messages>,
+    components: &bevy_ecs::component::Components,
+    mut output: ResMut<Output>,
+) {
+    for mut traits in &mut q {
+        for (id, data) in traits.iter_added_mut_with_id() {
+            assert!(data.is_added());
+            let name = message_component_name(components, id);
+            output.0.push(format!("Added {name}"));
+        }
+        for (id, data) in traits.iter_changed_mut_with_id() {
+            let name = message_component_name(components, id);
+            output.0.push(format!("Changed {name}: {:?}", data.read()));
+        }
+    }
+}
+
+#[test]
+fn iter_changed_mut_with_id() {
+    let mut world = World::new();
+    world.init_resource::<Output>();
+    world
+        .register_component_as::<dyn Messages, RecA>()
+        .register_component_as::<dyn Messages, RecB>();
+
+    world.spawn((RecA(vec![]), RecB(vec![])));
+
+    let mut schedule = Schedule::default();
+    schedule.add_systems((print_changed_mut_with_id, send_rec_a).chain());
+
+    schedule.run(&mut world);
+    schedule.run(&mut world);
+
+    assert_eq!(
+        world.resource::<Output>().0,
+        &[
+            "Added RecA",
+            "Added RecB",
+            "Changed RecA: []",
+            "Changed RecB: []",
+            r#"Changed RecA: ["RecA: Hello"]"#,
+        ]
+    );
+}
+
+fn print_count_changes(changes: TraitCountChanges<dyn Person>, mut output: ResMut<Output>) {
+    for change in &changes {
+        output.0.push(format!(
+            "{}: {} -> {} ({:+})",
+            change.entity,
+            change.previous,
+            change.current,
+            change.delta()
+        ));
+    }
+}
+
+#[test]
+fn trait_count_changes() {
+    let mut world = World::new();
+    world.init_resource::<Output>();
+    world
+        .register_component_as::<dyn Person, Human>()
+        .register_component_as::<dyn Person, Dolphin>();
+
+    let henry = world.spawn(Human("Henry".to_owned(), 22)).id();
+    let eliza = world
+        .spawn((Human("Eliza".to_owned(), 31), Dolphin(6)))
+        .id();
+    world.spawn(Fem);
+
+    let mut schedule = Schedule::default();
+    schedule.add_systems(print_count_changes);
+
+    schedule.run(&mut world);
+    assert_eq!(
+        world.resource::<Output>().0,
+        &["0v1: 0 -> 1 (+1)", "1v1: 0 -> 2 (+2)"]
+    );
+
+    world.resource_mut::<Output>().0.clear();
+    world.entity_mut(henry).insert(Dolphin(3));
+    world.entity_mut(eliza).remove::<Dolphin>();
+    schedule.run(&mut world);
+    assert_eq!(
+        world.resource::<Output>().0,
+        &["0v1: 1 -> 2 (+1)", "1v1: 2 -> 1 (-1)"]
+    );
+
+    world.resource_mut::<Output>().0.clear();
+    world.despawn(henry);
+    world.entity_mut(eliza).remove::<Human>();
+    schedule.run(&mut world);
+    assert_eq!(
+        world.resource::<Output>().0,
+        &["0v1: 2 -> 0 (-2)", "1v1: 1 -> 0 (-1)"]
+    );
+
+    // Nothing changed since the last run.
+    world.resource_mut::<Output>().0.clear();
+    schedule.run(&mut world);
+    assert!(world.resource::<Output>().0.is_empty());
+}
+
+fn person_names(world: &World, entity: Entity) -> Option<Vec<String>> {
+    let people = world.trait_components::<dyn Person>(entity)?;
+    Some(people.map(|person| person.name().to_owned()).collect())
+}
+
+#[test]
+fn trait_components() {
+    let mut world = World::new();
+    let eliza = world
+        .spawn((Human("Eliza".to_owned(), 31), Dolphin(6)))
+        .id();
+    // No impls have been registered yet.
+    assert!(world.trait_components::<dyn Person>(eliza).is_none());
+
+    world
+        .register_component_as::<dyn Person, Human>()
+        .register_component_as::<dyn Person, Dolphin>();
+    let henry = world.spawn(Human("Henry".to_owned(), 22)).id();
+    let fem = world.spawn(Fem).id();
+
+    assert_eq!(person_names(&world, eliza).unwrap(), ["Eliza", "Reginald"]);
+    assert_eq!(person_names(&world, henry).unwrap(), ["Henry"]);
+    assert!(person_names(&world, fem).unwrap().is_empty());
+
+    world.despawn(henry);
+    assert!(person_names(&world, henry).is_none());
+}
+
+#[cfg(feature = "bevy_hierarchy")]
+#[test]
+fn iter_descendant_traits() {
+    use bevy_hierarchy::{BuildChildren, Children};
+
+    let mut world = World::new();
+    world
+        .register_component_as::<dyn Person, Human>()
+        .register_component_as::<dyn Person, Dolphin>();
+
+    let root = world.spawn(Human("Henry".to_owned(), 22)).id();
+    let a = world.spawn(Dolphin(27)).id();
+    let b = world.spawn(Fem).id();
+    let c = world
+        .spawn((Human("Eliza".to_owned(), 31), Dolphin(6)))
+        .id();
+    world.entity_mut(root).add_children(&[a, b]);
+    world.entity_mut(b).add_child(c);
+    // Not part of the hierarchy.
+    world.spawn(Human("Garbanzo".to_owned(), 7));
+
+    let mut system_state =
+        bevy_ecs::system::SystemState::<(Query<&dyn Person>, Query<&Children>)>::new(&mut world);
+    let (people, children) = system_state.get(&world);
+    let found: Vec<_> = people
+        .iter_descendant_traits(root, &children)
+        .map(|(entity, person)| (entity, person.name().to_owned()))
+        .collect();
+    assert_eq!(
+        found,
+        [
+            (root, "Henry".to_owned()),
+            (a, "Reginald".to_owned()),
+            (c, "Eliza".to_owned()),
+            (c, "Reginald".to_owned()),
+        ]
+    );
+}
+
+#[test]
+fn first_and_nth() {
+    let mut world = World::new();
+    world.init_resource::<Output>();
+    world
+        .register_component_as::<dyn Person, Human>()
+        .register_component_as::<dyn Person, Dolphin>();
+
+    world.spawn((Human("Henry".to_owned(), 22), Dolphin(6)));
+    world.spawn(Dolphin(27));
+
+    let mut schedule = Schedule::default();
+    schedule.add_systems((print_first_and_second, print_changed_all_info, age_up_first).chain());
+
+    schedule.run(&mut world);
+    schedule.run(&mut world);
+
+    assert_eq!(
+        world.resource::<Output>().0,
+        &[
+            "Henry, Reginald",
+            "Reginald",
+            "Changed people:",
+            "Henry: 22",
+            "Reginald: 6",
+            "Reginald: 27",
+            "",
+            "Henry, Reginald",
+            "Reginald",
+            // Only the first impl of each entity was touched.
+            "Changed people:",
+            "Henry: 23",
+            "Reginald: 28",
+            "",
+        ]
+    );
+}
+
+fn print_first_and_second(people: Query<&dyn Person>, mut output: ResMut<Output>) {
+    for all in &people {
+        let first = all.first().unwrap();
+        assert_eq!(all.nth(0).unwrap().name(), first.name());
+        assert!(all.nth(all.len()).is_none());
+        match all.nth(1) {
+            Some(second) => output
+                .0
+                .push(format!("{}, {}", first.name(), second.name())),
+            None => output.0.push(first.name().to_owned()),
+        }
+    }
+}
+
+fn age_up_first(mut people: Query<&mut dyn Person>) {
+    for mut all in &mut people {
+        let mut first = all.first_mut().unwrap();
+        let age = first.age();
+        first.set_age(age + 1);
+        assert!(all.nth_mut(2).is_none());
+    }
+}
+
+#[test]
+fn last_changed_ticks() {
+    use bevy_ecs::change_detection::DetectChanges;
+
+    let mut world = World::new();
+    world
+        .register_component_as::<dyn Person, Human>()
+        .register_component_as::<dyn Person, Dolphin>();
+    let entity = world
+        .spawn((Human("Henry".to_owned(), 22), Dolphin(6)))
+        .id();
+
+    let mut people = world.query::<&dyn Person>();
+    let before: Vec<_> = people
+        .get(&world, entity)
+        .unwrap()
+        .iter()
+        .map(|person| person.last_changed())
+        .collect();
+
+    world.increment_change_tick();
+    let tick = world.change_tick();
+    let mut people_mut = world.query::<&mut dyn Person>();
+    let mut all = people_mut.get_mut(&mut world, entity).unwrap();
+    let mut human = all.first_mut().unwrap();
+    assert_eq!(human.last_changed(), before[0]);
+    human.set_age(23);
+    assert_eq!(human.last_changed(), tick);
+
+    let after: Vec<_> = people
+        .get(&world, entity)
+        .unwrap()
+        .iter()
+        .map(|person| person.last_changed())
+        .collect();
+    assert_eq!(after, [tick, before[1]]);
+    assert!(after[0].is_newer_than(before[0], tick));
+}
+
+#[test]
+fn all_added_and_changed_filters() {
+    let mut world = World::new();
+    world.init_resource::<Output>();
+    world
+        .register_component_as::<dyn Person, Human>()
+        .register_component_as::<dyn Person, Dolphin>();
+
+    world.spawn(Human("Henry".to_owned(), 22));
+    world.spawn(Dolphin(27));
+    world.spawn((Human("Eliza".to_owned(), 31), Dolphin(6)));
+    world.spawn(Fem);
+
+    let mut schedule = Schedule::default();
+    schedule.add_systems((print_added_and_changed, age_up_humans).chain());
+
+    schedule.run(&mut world);
+    schedule.run(&mut world);
+    world.spawn(Dolphin(3));
+    schedule.run(&mut world);
+
+    assert_eq!(
+        world.resource::<Output>().0,
+        &[
+            "Added: Henry",
+            "Added: Reginald",
+            "Added: Eliza, Reginald",
+            "Changed: Henry",
+            "Changed: Reginald",
+            "Changed: Eliza, Reginald",
+            "",
+            // Only the humans have changed, but the dolphin of a changed entity
+            // is still yielded by the data part of the query.
+            "Changed: Henry",
+            "Changed: Eliza, Reginald",
+            "",
+            "Added: Reginald",
+            "Changed: Henry",
+            "Changed: Reginald",
+            "Changed: Eliza, Reginald",
+            "",
+        ]
+    );
+}
+
+fn print_added_and_changed(
+    added: Query<&dyn Person, AllAdded<dyn Person>>,
+    changed: Query<&dyn Person, AllChanged<dyn Person>>,
+    mut output: ResMut<Output>,
+) {
+    let names = |all: ReadTraits<dyn Person>| {
+        all.iter()
+            .map(|person| person.name().to_owned())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    for all in &added {
+        output.0.push(format!("Added: {}", names(all)));
+    }
+    for all in &changed {
+        output.0.push(format!("Changed: {}", names(all)));
+    }
+    output.0.push(Default::default());
+}
+
+#[test]
+fn remove_impls_where() {
+    let mut world = World::new();
+    world
+        .register_component_as::<dyn Person, Human>()
+        .register_component_as::<dyn Person, Dolphin>();
+
+    let henry = world
+        .spawn((Human("Henry".to_owned(), 22), Dolphin(27)))
+        .id();
+    let eliza = world.spawn(Human("Eliza".to_owned(), 31)).id();
+    world.spawn(Dolphin(6));
+
+    let mut system_state =
+        bevy_ecs::system::SystemState::<(Commands, Query<&dyn Person>)>::new(&mut world);
+    let (mut commands, people) = system_state.get_mut(&mut world);
+    people.remove_impls_where(&mut commands, |person| person.age() < 25);
+    system_state.apply(&mut world);
+
+    // Only the failing impls are removed, the rest of the entity is left alone.
+    assert!(world.get::<Human>(henry).is_some());
+    assert!(world.get::<Dolphin>(henry).is_none());
+    assert!(world.get_entity(eliza).is_ok());
+    assert!(world.get::<Human>(eliza).is_none());
+
+    let mut people = world.query::<&dyn Person>();
+    let names: Vec<_> = people
+        .iter(&world)
+        .flatten()
+        .map(|person| person.name().to_owned())
+        .collect();
+    assert_eq!(names, ["Henry", "Reginald"]);
+}
@@ -0,0 +1,176 @@
+use bevy_ecs::{change_detection::Ref, component::ComponentId, entity::Entity, world::World};
+
+use crate::{ReadTraits, TraitImplRegistry, TraitQuery, TraitQueryReport};
+
+/// Extension methods for accessing the trait impls of an entity directly from a [`World`].
+///
+/// These work anywhere a `&World` is available, including exclusive systems, observers
+/// (via `DeferredWorld`) and component hooks.
+pub trait TraitWorldExt {
+    /// Returns all of the components implementing `Trait` for `entity`.
+    ///
+    /// Returns `None` if the entity does not exist, or if no impls have been registered for `Trait`.
+    ///
+    /// ```
+    /// # use bevy::prelude::*;
+    /// # use bevy_trait_query::*;
+    /// #
+    /// # #[bevy_trait_query::queryable]
+    /// # pub trait Tooltip {
+    /// #     fn tooltip(&self) -> &str;
+    /// # }
+    /// #
+    /// # #[derive(Component)]
+    /// # struct Monster;
+    /// #
+    /// # impl Tooltip for Monster {
+    /// #     fn tooltip(&self) -> &str {
+    /// #         "Run!"
+    /// #     }
+    /// # }
+    /// #
+    /// let mut world = World::new();
+    /// world.register_component_as::<dyn Tooltip, Monster>();
+    /// let monster = world.spawn(Monster).id();
+    ///
+    /// let tooltips = world.get_traits::<dyn Tooltip>(monster).unwrap();
+    /// assert_eq!(tooltips.iter().next().unwrap().tooltip(), "Run!");
+    /// ```
+    fn get_traits<Trait: ?Sized + TraitQuery>(
+        &self,
+        entity: Entity,
+    ) -> Option<ReadTraits<'_, Trait>>;
+
+    /// Returns an iterator over the components implementing `Trait` for `entity`,
+    /// without change detection.
+    ///
+    /// This is a shorthand for [`get_traits`](Self::get_traits) when only the trait objects
+    /// are needed. Returns `None` if the entity does not exist,
+    /// or if no impls have been registered for `Trait`.
+    ///
+    /// ```
+    /// # use bevy::prelude::*;
+    /// # use bevy_trait_query::*;
+    /// #
+    /// # #[bevy_trait_query::queryable]
+    /// # pub trait Tooltip {
+    /// #     fn tooltip(&self) -> &str;
+    /// # }
+    /// #
+    /// fn print_tooltips(world: &World, entity: Entity) {
+    ///     for tooltip in world.trait_components::<dyn Tooltip>(entity).into_iter().flatten() {
+    ///         println!("Tooltip: {}", tooltip.tooltip());
+    ///     }
+    /// }
+    /// ```
+    fn trait_components<Trait: ?Sized + TraitQuery>(
+        &self,
+        entity: Entity,
+    ) -> Option<impl Iterator<Item = &Trait>>;
+
+    /// Returns the number of archetypes containing at least one component implementing `Trait`.
+    ///
+    /// This is the number of archetypes that a query such as `Query<&dyn Trait>` has to visit,
+    /// so it can be a useful measure of how fragmented the world is for a given trait.
+    fn count_matching_archetypes<Trait: ?Sized + TraitQuery>(&self) -> usize;
+
+    /// Collects statistics about the impls of `Trait` in this world,
+    /// which can help diagnose why queries for it are slow.
+    ///
+    /// ```
+    /// # use bevy::prelude::*;
+    /// # use bevy_trait_query::*;
+    /// #
+    /// # #[bevy_trait_query::queryable]
+    /// # pub trait Tooltip {
+    /// #     fn tooltip(&self) -> &str;
+    /// # }
+    /// #
+    /// fn print_tooltip_report(world: &World) {
+    ///     let report = world.trait_query_report::<dyn Tooltip>();
+    ///     if report.is_fragmented() {
+    ///         println!("{report}");
+    ///     }
+    /// }
+    /// ```
+    fn trait_query_report<Trait: ?Sized + TraitQuery>(&self) -> TraitQueryReport;
+
+    /// Scans every archetype in the world for components registered for `Trait`,
+    /// returning each entity along with the components it has that implement `Trait`.
+    ///
+    /// Unlike a query, this doesn't need a system or mutable access to the world,
+    /// and doesn't seal the trait's registry. It visits every archetype on each call,
+    /// so it is meant for tooling and diagnostics rather than per-frame use.
+    /// Returns an empty list if no impls have been registered for `Trait`.
+    fn scan_trait_impls<Trait: ?Sized + TraitQuery>(&self) -> Vec<(Entity, ComponentId)>;
+}
+
+impl TraitWorldExt for World {
+    fn get_traits<Trait: ?Sized + TraitQuery>(
+        &self,
+        entity: Entity,
+    ) -> Option<ReadTraits<'_, Trait>> {
+        let registry = self.get_resource::<TraitImplRegistry<Trait>>()?;
+        let location = self.entities().get(entity)?;
+        let storages = self.storages();
+        let table = storages.tables.get(location.table_id)?;
+        Some(ReadTraits {
+            registry,
+            entity,
+            table,
+            table_row: location.table_row,
+            sparse_sets: &storages.sparse_sets,
+            single_table: registry.single_table(),
+            last_run: self.last_change_tick(),
+            this_run: self.read_change_tick(),
+        })
+    }
+
+    fn trait_components<Trait: ?Sized + TraitQuery>(
+        &self,
+        entity: Entity,
+    ) -> Option<impl Iterator<Item = &Trait>> {
+        let traits = self.get_traits::<Trait>(entity)?;
+        Some(traits.into_iter().map(Ref::into_inner))
+    }
+
+    fn count_matching_archetypes<Trait: ?Sized + TraitQuery>(&self) -> usize {
+        let Some(registry) = self.get_resource::<TraitImplRegistry<Trait>>() else {
+            return 0;
+        };
+        self.archetypes()
+            .iter()
+            .filter(|archetype| {
+                registry
+                    .components
+                    .iter()
+                    .any(|&component| archetype.contains(component))
+            })
+            .count()
+    }
+
+    fn trait_query_report<Trait: ?Sized + TraitQuery>(&self) -> TraitQueryReport {
+        TraitQueryReport::new::<Trait>(self)
+    }
+
+    fn scan_trait_impls<Trait: ?Sized + TraitQuery>(&self) -> Vec<(Entity, ComponentId)> {
+        let Some(registry) = self.get_resource::<TraitImplRegistry<Trait>>() else {
+            return Vec::new();
+        };
+        let mut impls = Vec::new();
+        for archetype in self.archetypes().iter() {
+            for &component in &registry.components {
+                if !archetype.contains(component) {
+                    continue;
+                }
+                impls.extend(
+                    archetype
+                        .entities()
+                        .iter()
+                        .map(|entity| (entity.id(), component)),
+                );
+            }
+        }
+        impls
+    }
+}
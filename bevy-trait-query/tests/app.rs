@@ -0,0 +1,119 @@
+use bevy::prelude::*;
+use bevy_trait_query::{One, RegisterExt};
+
+#[bevy_trait_query::queryable]
+pub trait Tooltip {
+    fn tooltip(&self) -> &str;
+    fn set_tooltip(&mut self, text: &str);
+}
+
+#[derive(Component)]
+struct Player(String);
+
+#[derive(Component)]
+#[component(storage = "SparseSet")]
+struct Monster(String);
+
+impl Tooltip for Player {
+    fn tooltip(&self) -> &str {
+        &self.0
+    }
+    fn set_tooltip(&mut self, text: &str) {
+        self.0 = text.to_owned();
+    }
+}
+
+impl Tooltip for Monster {
+    fn tooltip(&self) -> &str {
+        &self.0
+    }
+    fn set_tooltip(&mut self, text: &str) {
+        self.0 = text.to_owned();
+    }
+}
+
+#[derive(Resource, Default)]
+struct Output(Vec<String>);
+
+struct TooltipPlugin;
+
+impl Plugin for TooltipPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_component_as::<dyn Tooltip, Player>()
+            .register_component_as::<dyn Tooltip, Monster>()
+            .init_resource::<Output>()
+            .add_systems(Startup, setup)
+            .add_systems(
+                Update,
+                (print_one, print_all, print_changed, rename_players).chain(),
+            );
+    }
+}
+
+fn setup(mut commands: Commands) {
+    commands.spawn(Player("Fourier".to_owned()));
+    commands.spawn(Monster("Run!".to_owned()));
+    commands.spawn((Player("Both".to_owned()), Monster("Boo".to_owned())));
+}
+
+fn print_one(tooltips: Query<One<&dyn Tooltip>>, mut output: ResMut<Output>) {
+    let mut all: Vec<_> = tooltips.iter().map(|t| t.tooltip().to_owned()).collect();
+    all.sort();
+    output.0.push(format!("one: {}", all.join(", ")));
+}
+
+fn print_all(tooltips: Query<&dyn Tooltip>, mut output: ResMut<Output>) {
+    let mut all: Vec<_> = tooltips
+        .iter()
+        .flatten()
+        .map(|t| t.tooltip().to_owned())
+        .collect();
+    all.sort();
+    output.0.push(format!("all: {}", all.join(", ")));
+}
+
+fn print_changed(tooltips: Query<&dyn Tooltip>, mut output: ResMut<Output>) {
+    let mut all: Vec<_> = tooltips
+        .iter()
+        .flat_map(|t| t.iter_changed())
+        .map(|t| t.tooltip().to_owned())
+        .collect();
+    all.sort();
+    output.0.push(format!("changed: {}", all.join(", ")));
+}
+
+fn rename_players(mut players: Query<&mut dyn Tooltip, Without<Monster>>) {
+    for tooltips in &mut players {
+        for mut tooltip in tooltips {
+            if !tooltip.tooltip().ends_with('!') {
+                let new = format!("{}!", tooltip.tooltip());
+                tooltip.set_tooltip(&new);
+            }
+        }
+    }
+}
+
+#[test]
+fn plugin_registration() {
+    let mut app = App::new();
+    app.add_plugins(TooltipPlugin);
+
+    app.update();
+    app.update();
+    app.update();
+
+    assert_eq!(
+        app.world().resource::<Output>().0,
+        &[
+            "one: Fourier, Run!",
+            "all: Boo, Both, Fourier, Run!",
+            "changed: Boo, Both, Fourier, Run!",
+            "one: Fourier!, Run!",
+            "all: Boo, Both, Fourier!, Run!",
+            "changed: Fourier!",
+            "one: Fourier!, Run!",
+            "all: Boo, Both, Fourier!, Run!",
+            "changed: ",
+        ]
+    );
+}
@@ -0,0 +1,72 @@
+use bevy::{
+    ecs::{component::ComponentId, world::DeferredWorld},
+    prelude::*,
+};
+use bevy_trait_query::{RegisterExt, TraitWorldExt};
+
+#[bevy_trait_query::queryable]
+pub trait Farewell {
+    fn goodbye(&self) -> String;
+}
+
+/// Marker that says goodbye on behalf of all of its entity's `Farewell` impls.
+#[derive(Component)]
+#[component(on_remove = say_goodbye)]
+struct Mortal;
+
+#[derive(Component)]
+struct Player(String);
+
+#[derive(Component)]
+#[component(storage = "SparseSet")]
+struct Pet(String);
+
+impl Farewell for Player {
+    fn goodbye(&self) -> String {
+        format!("Goodbye from {}", self.0)
+    }
+}
+
+impl Farewell for Pet {
+    fn goodbye(&self) -> String {
+        format!("Woof from {}", self.0)
+    }
+}
+
+#[derive(Resource, Default)]
+struct Output(Vec<String>);
+
+fn say_goodbye(mut world: DeferredWorld, entity: Entity, _: ComponentId) {
+    // The entity's components are still present while `on_remove` hooks run.
+    let goodbyes: Vec<_> = world
+        .get_traits::<dyn Farewell>(entity)
+        .into_iter()
+        .flatten()
+        .map(|f| f.goodbye())
+        .collect();
+    world.resource_mut::<Output>().0.extend(goodbyes);
+}
+
+#[test]
+fn read_traits_on_despawn() {
+    let mut world = World::new();
+    world.init_resource::<Output>();
+    world
+        .register_component_as::<dyn Farewell, Player>()
+        .register_component_as::<dyn Farewell, Pet>();
+
+    let player = world
+        .spawn((Mortal, Player("Fourier".to_owned()), Pet("Rex".to_owned())))
+        .id();
+    let pet = world.spawn((Mortal, Pet("Tom".to_owned()))).id();
+    let immortal = world.spawn(Player("Zeus".to_owned())).id();
+
+    world.despawn(player);
+    world.entity_mut(pet).remove::<Mortal>();
+    world.despawn(immortal);
+
+    assert_eq!(
+        world.resource::<Output>().0,
+        &["Goodbye from Fourier", "Woof from Rex", "Woof from Tom"]
+    );
+}
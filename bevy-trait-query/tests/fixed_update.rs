@@ -0,0 +1,103 @@
+use std::time::Duration;
+
+use bevy::{prelude::*, time::TimeUpdateStrategy};
+use bevy_trait_query::RegisterExt;
+
+#[bevy_trait_query::queryable]
+pub trait Health {
+    fn health(&self) -> u32;
+    fn damage(&mut self, amount: u32);
+}
+
+#[derive(Component)]
+struct Player(u32);
+
+#[derive(Component)]
+#[component(storage = "SparseSet")]
+struct Monster(u32);
+
+impl Health for Player {
+    fn health(&self) -> u32 {
+        self.0
+    }
+    fn damage(&mut self, amount: u32) {
+        self.0 -= amount;
+    }
+}
+
+impl Health for Monster {
+    fn health(&self) -> u32 {
+        self.0
+    }
+    fn damage(&mut self, amount: u32) {
+        self.0 -= amount;
+    }
+}
+
+#[derive(Resource, Default)]
+struct Output(Vec<String>);
+
+#[derive(Resource, Default)]
+struct Step(u32);
+
+fn setup(mut commands: Commands) {
+    commands.spawn(Player(10));
+    commands.spawn(Monster(20));
+}
+
+fn print_changed(q: Query<&dyn Health>, step: Res<Step>, mut output: ResMut<Output>) {
+    let mut changed: Vec<_> = q
+        .iter()
+        .flat_map(|t| t.iter_changed())
+        .map(|t| t.health().to_string())
+        .collect();
+    changed.sort();
+    output
+        .0
+        .push(format!("step {}: {}", step.0, changed.join(", ")));
+}
+
+// Damages the player during the second fixed step only.
+fn damage_player(mut q: Query<&mut dyn Health, With<Player>>, mut step: ResMut<Step>) {
+    if step.0 == 1 {
+        for health in &mut q {
+            for mut health in health {
+                health.damage(1);
+            }
+        }
+    }
+    step.0 += 1;
+}
+
+#[test]
+fn changes_in_fixed_update() {
+    let mut app = App::new();
+    app.add_plugins(bevy::time::TimePlugin)
+        // Two fixed steps will run for each update after the first one.
+        .insert_resource(Time::<Fixed>::from_duration(Duration::from_millis(100)))
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(
+            200,
+        )))
+        .register_component_as::<dyn Health, Player>()
+        .register_component_as::<dyn Health, Monster>()
+        .init_resource::<Output>()
+        .init_resource::<Step>()
+        .add_systems(Startup, setup)
+        .add_systems(FixedUpdate, (print_changed, damage_player).chain());
+
+    // The first update has a delta of zero, so no fixed steps are run.
+    app.update();
+    app.update();
+    app.update();
+
+    assert_eq!(
+        app.world().resource::<Output>().0,
+        &[
+            "step 0: 10, 20",
+            "step 1: ",
+            // The change made at the end of the previous step, which ran during the previous update.
+            "step 2: 9",
+            "step 3: ",
+        ]
+    );
+}
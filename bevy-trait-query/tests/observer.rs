@@ -0,0 +1,61 @@
+use bevy::{ecs::world::DeferredWorld, prelude::*};
+use bevy_trait_query::{RegisterExt, TraitWorldExt};
+
+#[bevy_trait_query::queryable]
+pub trait Tooltip {
+    fn tooltip(&self) -> &str;
+}
+
+#[derive(Component)]
+struct Player(String);
+
+#[derive(Component)]
+#[component(storage = "SparseSet")]
+struct Monster;
+
+impl Tooltip for Player {
+    fn tooltip(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Tooltip for Monster {
+    fn tooltip(&self) -> &str {
+        "Run!"
+    }
+}
+
+#[derive(Event)]
+struct Hovered;
+
+#[derive(Resource, Default)]
+struct Output(Vec<String>);
+
+#[test]
+fn observer_reads_trait_impls() {
+    let mut world = World::new();
+    world.init_resource::<Output>();
+    world
+        .register_component_as::<dyn Tooltip, Player>()
+        .register_component_as::<dyn Tooltip, Monster>();
+
+    world.add_observer(|trigger: Trigger<Hovered>, mut world: DeferredWorld| {
+        let tooltips: Vec<_> = world
+            .get_traits::<dyn Tooltip>(trigger.entity())
+            .into_iter()
+            .flatten()
+            .map(|t| t.tooltip().to_owned())
+            .collect();
+        world.resource_mut::<Output>().0.push(tooltips.join(", "));
+    });
+
+    let player = world.spawn(Player("Fourier".to_owned())).id();
+    let both = world.spawn((Player("Both".to_owned()), Monster)).id();
+    let neither = world.spawn_empty().id();
+
+    world.trigger_targets(Hovered, player);
+    world.trigger_targets(Hovered, both);
+    world.trigger_targets(Hovered, neither);
+
+    assert_eq!(world.resource::<Output>().0, &["Fourier", "Both, Run!", ""]);
+}
@@ -0,0 +1,84 @@
+use bevy::prelude::*;
+use bevy_trait_query::{One, RegisterExt, TraitImplExport, TraitRegistryExt};
+
+#[bevy_trait_query::queryable]
+pub trait Describe {
+    fn describe(&self) -> String;
+}
+
+#[derive(Component)]
+struct Crate(u32);
+
+#[derive(Component)]
+#[component(storage = "SparseSet")]
+struct Barrel(u32);
+
+impl Describe for Crate {
+    fn describe(&self) -> String {
+        format!("Crate of {}", self.0)
+    }
+}
+
+impl Describe for Barrel {
+    fn describe(&self) -> String {
+        format!("Barrel of {}", self.0)
+    }
+}
+
+fn populate(world: &mut World) {
+    world.spawn(Crate(1));
+    world.spawn(Barrel(2));
+    world.spawn((Crate(3), Barrel(4)));
+}
+
+fn describe_one(world: &mut World) -> Vec<String> {
+    let mut query = world.query::<One<&dyn Describe>>();
+    let mut all: Vec<_> = query.iter(world).map(|d| d.describe()).collect();
+    all.sort();
+    all
+}
+
+fn describe_all(world: &mut World) -> Vec<String> {
+    let mut query = world.query::<&dyn Describe>();
+    let mut all: Vec<_> = query.iter(world).flatten().map(|d| d.describe()).collect();
+    all.sort();
+    all
+}
+
+#[test]
+fn export_import_roundtrip() {
+    let export: TraitImplExport<dyn Describe>;
+    let (one_before, all_before);
+    {
+        let mut world = World::new();
+        world
+            .register_component_as::<dyn Describe, Crate>()
+            .register_component_as::<dyn Describe, Barrel>();
+        export = world.export_trait_impls::<dyn Describe>();
+        populate(&mut world);
+        one_before = describe_one(&mut world);
+        all_before = describe_all(&mut world);
+    }
+    assert_eq!(export.len(), 2);
+
+    let mut world = World::new();
+    world.import_trait_impls(&export);
+    populate(&mut world);
+
+    let one_after = describe_one(&mut world);
+    let all_after = describe_all(&mut world);
+    assert_eq!(one_before, one_after);
+    assert_eq!(all_before, all_after);
+    assert_eq!(one_after, ["Barrel of 2", "Crate of 1", "Crate of 3"]);
+    assert_eq!(
+        all_after,
+        ["Barrel of 2", "Barrel of 4", "Crate of 1", "Crate of 3"]
+    );
+}
+
+#[test]
+fn export_without_registrations() {
+    let world = World::new();
+    let export = world.export_trait_impls::<dyn Describe>();
+    assert!(export.is_empty());
+}
@@ -0,0 +1,12 @@
+#[test]
+fn ui() {
+    let t = trybuild::TestCases::new();
+    t.pass("tests/ui/no_bounds_pass.rs");
+    t.pass("tests/ui/renamed_import.rs");
+    t.pass("tests/ui/sized_methods_pass.rs");
+    t.compile_fail("tests/ui/no_bounds_missing_static.rs");
+    t.compile_fail("tests/ui/dynamic_non_queryable.rs");
+    t.compile_fail("tests/ui/impl_trait_return.rs");
+    t.compile_fail("tests/ui/generic_method.rs");
+    t.compile_fail("tests/ui/self_return.rs");
+}
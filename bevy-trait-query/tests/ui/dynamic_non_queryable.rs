@@ -0,0 +1,15 @@
+use bevy::prelude::*;
+use bevy_trait_query::TraitCommandsExt;
+
+pub trait NotQueryable {}
+
+fn insert(mut commands: Commands, id: bevy::ecs::component::ComponentId) {
+    let entity = commands.spawn_empty().id();
+    unsafe {
+        commands
+            .entity(entity)
+            .insert_trait_impl_dynamic::<dyn NotQueryable>(id, vec![]);
+    }
+}
+
+fn main() {}
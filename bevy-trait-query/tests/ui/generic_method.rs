@@ -0,0 +1,6 @@
+#[bevy_trait_query::queryable]
+pub trait Modifier {
+    fn apply<T: Into<f32>>(&self, v: T);
+}
+
+fn main() {}
@@ -0,0 +1,6 @@
+#[bevy_trait_query::queryable]
+pub trait Inventory {
+    fn items(&self) -> impl Iterator<Item = u32>;
+}
+
+fn main() {}
@@ -0,0 +1,6 @@
+#[bevy_trait_query::queryable(no_bounds)]
+pub trait Container<T>: 'static {
+    fn get(&self) -> &T;
+}
+
+fn main() {}
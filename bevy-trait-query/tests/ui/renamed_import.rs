@@ -0,0 +1,46 @@
+use ::bevy_trait_query as btq;
+use bevy::prelude::*;
+use btq::RegisterExt;
+
+// Would shadow the crate if the generated code used a relative path.
+#[allow(dead_code)]
+mod bevy_trait_query {}
+
+#[btq::queryable]
+pub trait Tooltip {
+    fn tooltip(&self) -> &str;
+}
+
+#[derive(Component)]
+struct Monster;
+
+impl Tooltip for Monster {
+    fn tooltip(&self) -> &str {
+        "Run!"
+    }
+}
+
+fn main() {
+    // Traits declared inside of a function body are supported as well.
+    #[btq::queryable]
+    trait Local {
+        fn value(&self) -> u32;
+    }
+
+    impl Local for Monster {
+        fn value(&self) -> u32 {
+            1
+        }
+    }
+
+    let mut world = World::new();
+    world
+        .register_component_as::<dyn Tooltip, Monster>()
+        .register_component_as::<dyn Local, Monster>();
+    world.spawn(Monster);
+
+    let mut query = world.query::<(&dyn Tooltip, btq::One<&dyn Local>)>();
+    for (tooltips, local) in query.iter(&world) {
+        assert_eq!(tooltips.iter().count(), local.value() as usize);
+    }
+}
@@ -0,0 +1,6 @@
+#[bevy_trait_query::queryable]
+pub trait Spawner {
+    fn duplicate(&self) -> Self;
+}
+
+fn main() {}
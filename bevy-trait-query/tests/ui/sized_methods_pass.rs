@@ -0,0 +1,16 @@
+#[bevy_trait_query::queryable]
+pub trait Stat {
+    type Value;
+
+    fn value(&self) -> Self::Value;
+
+    fn new(value: Self::Value) -> Self
+    where
+        Self: Sized;
+
+    fn scale<T: Into<f32>>(&mut self, factor: T)
+    where
+        Self: Sized;
+}
+
+fn main() {}